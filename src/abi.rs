@@ -0,0 +1,581 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::header_parser::{parse_header_file, CHeader, CType, CTypeKind};
+use crate::manifest::BuildManifest;
+use crate::symbol_check::{library_name, read_exported_symbols};
+use crate::target_cfg::TargetCfg;
+
+/// The snapshot file checked into the crate directory by `build --abi-update`.
+pub const ABI_SNAPSHOT_FILE: &str = "ghostbind.abi.json";
+
+/// The public C surface of one build: exported symbols, declared signatures and type layouts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbiSnapshot {
+    pub crate_name: String,
+    pub rustc_target: String,
+    pub symbols: BTreeSet<String>,
+    /// Function name -> normalized prototype
+    pub functions: BTreeMap<String, String>,
+    pub types: BTreeMap<String, TypeLayout>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeLayout {
+    pub kind: String,
+    /// `None` when the layout depends on a type the headers don't define
+    pub size: Option<u64>,
+    pub align: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldLayout>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldLayout {
+    pub name: String,
+    pub ty: String,
+    pub offset: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    /// Existing binaries and sources keep working
+    Compatible,
+    /// New surface; existing binaries keep working
+    Additive,
+    /// Existing binaries may fail to link or misbehave
+    Breaking,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ChangeKind::Compatible => "compatible",
+            ChangeKind::Additive => "additive",
+            ChangeKind::Breaking => "breaking",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AbiChange {
+    pub kind: ChangeKind,
+    pub item: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AbiDiff {
+    pub changes: Vec<AbiChange>,
+}
+
+impl AbiDiff {
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|c| c.kind == ChangeKind::Breaking)
+    }
+
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.kind == kind).count()
+    }
+
+    fn push(&mut self, kind: ChangeKind, item: &str, description: String) {
+        self.changes.push(AbiChange {
+            kind,
+            item: item.to_string(),
+            description,
+        });
+    }
+}
+
+impl AbiSnapshot {
+    /// Builds a snapshot from the artifact and headers a manifest points at, with the
+    /// pointer width from the target's cfgs.
+    pub fn from_manifest(manifest: &BuildManifest, target_cfg: &TargetCfg) -> Result<Self> {
+        let pointer_size = target_cfg.value("target_pointer_width")
+            .and_then(|bits| bits.parse::<u64>().ok())
+            .map(|bits| bits / 8)
+            .ok_or_else(|| anyhow!("rustc reported no target_pointer_width for {}", manifest.rustc_target))?;
+        let symbols = read_exported_symbols(&manifest.artifact, &library_name(&manifest.artifact))?;
+
+        let mut header = CHeader::default();
+        for path in &manifest.c_headers()? {
+            let parsed = parse_header_file(path)?;
+            header.functions.extend(parsed.functions);
+            header.types.extend(parsed.types);
+        }
+
+        Ok(Self::new(&manifest.crate_name, &manifest.rustc_target, pointer_size, symbols, &header))
+    }
+
+    pub fn new(crate_name: &str, rustc_target: &str, pointer_size: u64, symbols: BTreeSet<String>, header: &CHeader) -> Self {
+        let functions = header.functions.iter()
+            .map(|f| (f.name.clone(), f.signature()))
+            .collect();
+
+        let layouts = LayoutCalculator::new(header, rustc_target, pointer_size);
+        let types = header.types.iter()
+            .filter(|t| matches!(t.kind, CTypeKind::Struct | CTypeKind::Union | CTypeKind::Enum))
+            .map(|t| (t.name.clone(), layouts.type_layout(t)))
+            .collect();
+
+        Self {
+            crate_name: crate_name.to_string(),
+            rustc_target: rustc_target.to_string(),
+            symbols,
+            functions,
+            types,
+        }
+    }
+
+    /// The ABI a manifest recorded at build time, or a snapshot file such as the one
+    /// `build --abi-update` writes.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::InvalidConfig(format!("Failed to read {}: {}", path.display(), e)))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| Error::InvalidConfig(format!("Failed to parse {}: {}", path.display(), e)))?;
+
+        if value.get("symbols").is_some() {
+            return serde_json::from_value(value)
+                .map_err(|e| Error::InvalidConfig(format!("Failed to parse ABI snapshot {}: {}", path.display(), e)).into());
+        }
+
+        let manifest: BuildManifest = serde_json::from_value(value)
+            .map_err(|e| Error::InvalidConfig(format!("Failed to parse manifest JSON from {}: {}", path.display(), e)))?;
+        manifest.abi.ok_or_else(|| Error::InvalidConfig(format!(
+            "{} records no C ABI: it has no C header, or was written by an older ghostbind. Rebuild, or pass an ABI snapshot",
+            path.display()
+        )).into())
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ABI snapshot {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse ABI snapshot {}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize ABI snapshot")?;
+        content.push('\n');
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write ABI snapshot {}", path.display()))
+    }
+
+    /// Classifies every difference between this (older) snapshot and a newer one.
+    pub fn diff(&self, new: &AbiSnapshot) -> AbiDiff {
+        let mut diff = AbiDiff::default();
+
+        let names: BTreeSet<&String> = self.symbols.iter()
+            .chain(&new.symbols)
+            .chain(self.functions.keys())
+            .chain(new.functions.keys())
+            .collect();
+
+        for name in names {
+            let (old_sig, new_sig) = (self.functions.get(name), new.functions.get(name));
+            match (self.symbols.contains(name), new.symbols.contains(name)) {
+                (true, false) => diff.push(ChangeKind::Breaking, name, "symbol removed".to_string()),
+                (false, true) => diff.push(ChangeKind::Additive, name, "symbol added".to_string()),
+                _ => match (old_sig, new_sig) {
+                    (Some(old), Some(new)) if old != new => {
+                        diff.push(ChangeKind::Breaking, name, format!("signature changed from `{}` to `{}`", old, new));
+                    }
+                    (Some(_), None) => diff.push(ChangeKind::Compatible, name, "no longer declared in headers".to_string()),
+                    (None, Some(_)) => diff.push(ChangeKind::Additive, name, "now declared in headers".to_string()),
+                    _ => {}
+                },
+            }
+        }
+
+        let type_names: BTreeSet<&String> = self.types.keys().chain(new.types.keys()).collect();
+        for name in type_names {
+            match (self.types.get(name), new.types.get(name)) {
+                (Some(_), None) => diff.push(ChangeKind::Breaking, name, "type removed".to_string()),
+                (None, Some(_)) => diff.push(ChangeKind::Additive, name, "type added".to_string()),
+                (Some(old), Some(new)) => diff_layout(&mut diff, name, old, new),
+                (None, None) => {}
+            }
+        }
+
+        diff
+    }
+}
+
+fn diff_layout(diff: &mut AbiDiff, name: &str, old: &TypeLayout, new: &TypeLayout) {
+    if old.kind != new.kind {
+        diff.push(ChangeKind::Breaking, name, format!("changed from {} to {}", old.kind, new.kind));
+        return;
+    }
+    if old.size != new.size {
+        diff.push(ChangeKind::Breaking, name, format!("size changed from {} to {}", describe(old.size), describe(new.size)));
+    }
+    if old.align != new.align {
+        diff.push(ChangeKind::Breaking, name, format!("alignment changed from {} to {}", describe(old.align), describe(new.align)));
+    }
+
+    for (index, old_field) in old.fields.iter().enumerate() {
+        let item = format!("{}.{}", name, old_field.name);
+        match new.fields.iter().find(|f| f.name == old_field.name) {
+            Some(new_field) if new_field.ty != old_field.ty => {
+                diff.push(ChangeKind::Breaking, &item, format!("type changed from `{}` to `{}`", old_field.ty, new_field.ty));
+            }
+            Some(new_field) if new_field.offset != old_field.offset => {
+                diff.push(
+                    ChangeKind::Breaking,
+                    &item,
+                    format!("offset changed from {} to {}", describe(old_field.offset), describe(new_field.offset)),
+                );
+            }
+            Some(_) => {}
+            // Same slot, same type, new name: only source code notices
+            None => match new.fields.get(index) {
+                Some(renamed) if renamed.ty == old_field.ty && renamed.offset == old_field.offset
+                    && !old.fields.iter().any(|f| f.name == renamed.name) =>
+                {
+                    diff.push(ChangeKind::Compatible, &item, format!("renamed to `{}`", renamed.name));
+                }
+                _ => diff.push(ChangeKind::Breaking, &item, "field removed".to_string()),
+            },
+        }
+    }
+
+    for (index, new_field) in new.fields.iter().enumerate() {
+        let renamed = old.fields.get(index).is_some_and(|f| {
+            f.ty == new_field.ty && f.offset == new_field.offset && !new.fields.iter().any(|n| n.name == f.name)
+        });
+        if !renamed && !old.fields.iter().any(|f| f.name == new_field.name) {
+            // A new field that doesn't change the size only extends a union or fills padding
+            let kind = if old.size == new.size && old.size.is_some() { ChangeKind::Additive } else { ChangeKind::Breaking };
+            diff.push(kind, &format!("{}.{}", name, new_field.name), "field added".to_string());
+        }
+    }
+
+    for (variant, old_value) in &old.variants {
+        let item = format!("{}::{}", name, variant);
+        match new.variants.get(variant) {
+            None => diff.push(ChangeKind::Breaking, &item, "variant removed".to_string()),
+            Some(new_value) if new_value != old_value => {
+                diff.push(ChangeKind::Breaking, &item, format!("value changed from {} to {}", old_value, new_value));
+            }
+            Some(_) => {}
+        }
+    }
+    for variant in new.variants.keys().filter(|v| !old.variants.contains_key(*v)) {
+        diff.push(ChangeKind::Additive, &format!("{}::{}", name, variant), "variant added".to_string());
+    }
+}
+
+fn describe(value: Option<u64>) -> String {
+    value.map_or_else(|| "unknown".to_string(), |v| v.to_string())
+}
+
+/// Computes C layouts for header types using the target's data model.
+pub struct LayoutCalculator<'a> {
+    header: &'a CHeader,
+    pointer_size: u64,
+    long_size: u64,
+    /// Alignment of 8-byte scalars, which i386 System V lowers to 4 inside structs
+    wide_align: u64,
+}
+
+impl<'a> LayoutCalculator<'a> {
+    /// `pointer_size` is in bytes, from `target_pointer_width`: the triple alone can't tell
+    /// that `arm64_32-apple-watchos` or `x86_64-unknown-linux-gnux32` use 4.
+    pub fn new(header: &'a CHeader, rustc_target: &str, pointer_size: u64) -> Self {
+        let arch = rustc_target.split('-').next().unwrap_or_default();
+        // Windows is LLP64: `long` stays 32-bit on 64-bit targets
+        let long_size = if rustc_target.contains("windows") { 4 } else { pointer_size };
+        let i386 = matches!(arch, "i386" | "i586" | "i686" | "x86");
+        let wide_align = if i386 && !rustc_target.contains("windows") { 4 } else { 8 };
+
+        Self {
+            header,
+            pointer_size,
+            long_size,
+            wide_align,
+        }
+    }
+
+    pub fn type_layout(&self, ty: &CType) -> TypeLayout {
+        let (size, align) = self.named_size_align(&ty.name, 0).unzip();
+        let kind = match ty.kind {
+            CTypeKind::Union => "union",
+            CTypeKind::Enum => "enum",
+            _ => "struct",
+        };
+
+        let mut variants = BTreeMap::new();
+        let mut next = 0;
+        for (variant, value) in &ty.variants {
+            let value = value.unwrap_or(next);
+            variants.insert(variant.clone(), value);
+            next = value + 1;
+        }
+
+        let fields = self.field_offsets(ty, 0)
+            .into_iter()
+            .zip(&ty.fields)
+            .map(|(offset, field)| FieldLayout {
+                name: field.name.clone(),
+                ty: match field.array_len {
+                    Some(len) => format!("{}[{}]", field.ty, len),
+                    None => field.ty.clone(),
+                },
+                offset,
+            })
+            .collect();
+
+        TypeLayout {
+            kind: kind.to_string(),
+            size,
+            align,
+            fields,
+            variants,
+        }
+    }
+
+    /// Offsets of each field; unknown from the first field whose layout can't be computed.
+    fn field_offsets(&self, ty: &CType, depth: usize) -> Vec<Option<u64>> {
+        let mut offsets = Vec::new();
+        let mut offset = Some(0u64);
+
+        for field in &ty.fields {
+            let layout = self.field_size_align(&field.ty, field.array_len, depth);
+            if ty.kind == CTypeKind::Union {
+                offsets.push(Some(0));
+                continue;
+            }
+            offset = offset.zip(layout).map(|(offset, (_, align))| offset.next_multiple_of(align));
+            offsets.push(offset);
+            offset = offset.zip(layout).map(|(offset, (size, _))| offset + size);
+        }
+
+        offsets
+    }
+
+    fn field_size_align(&self, ty: &str, array_len: Option<usize>, depth: usize) -> Option<(u64, u64)> {
+        let (size, align) = self.size_align(ty, depth)?;
+        Some((size * array_len.unwrap_or(1) as u64, align))
+    }
+
+    fn size_align(&self, ty: &str, depth: usize) -> Option<(u64, u64)> {
+        let ty = ty.replace("const", " ").replace("volatile", " ");
+        let ty = ty.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if ty.ends_with('*') || ty.contains("(*)") {
+            return Some((self.pointer_size, self.pointer_size));
+        }
+
+        let primitive = match ty.as_str() {
+            "bool" | "_Bool" | "char" | "signed char" | "unsigned char" | "int8_t" | "uint8_t" => 1,
+            "short" | "unsigned short" | "int16_t" | "uint16_t" => 2,
+            "int" | "unsigned" | "unsigned int" | "int32_t" | "uint32_t" | "float" => 4,
+            "long long" | "unsigned long long" | "int64_t" | "uint64_t" | "double" => 8,
+            "long" | "unsigned long" => self.long_size,
+            "size_t" | "ptrdiff_t" | "intptr_t" | "uintptr_t" => self.pointer_size,
+            _ => 0,
+        };
+        if primitive > 0 {
+            return Some((primitive, primitive.min(self.wide_align)));
+        }
+
+        let name = ["struct ", "union ", "enum "].iter()
+            .find_map(|keyword| ty.strip_prefix(keyword))
+            .unwrap_or(&ty);
+        self.named_size_align(name, depth + 1)
+    }
+
+    fn named_size_align(&self, name: &str, depth: usize) -> Option<(u64, u64)> {
+        // Guards against self-referential typedefs
+        if depth > 32 {
+            return None;
+        }
+
+        let ty = self.header.type_named(name)?;
+        match ty.kind {
+            CTypeKind::Struct | CTypeKind::Union => {
+                let mut size = 0;
+                let mut align = 1;
+                for field in &ty.fields {
+                    let (field_size, field_align) = self.field_size_align(&field.ty, field.array_len, depth)?;
+                    align = align.max(field_align);
+                    size = if ty.kind == CTypeKind::Union {
+                        size.max(field_size)
+                    } else {
+                        size.next_multiple_of(field_align) + field_size
+                    };
+                }
+                Some((size.next_multiple_of(align), align))
+            }
+            CTypeKind::Enum => {
+                // cbindgen declares `#[repr(u8)]` and friends as `enum X {...}; typedef uint8_t X;`
+                let repr = self.header.types.iter()
+                    .find(|t| t.name == name && t.kind == CTypeKind::Typedef)
+                    .and_then(|t| t.aliased.as_deref());
+                match repr {
+                    Some(repr) if repr != format!("enum {}", name) => self.size_align(repr, depth),
+                    _ => Some((4, 4)),
+                }
+            }
+            CTypeKind::Typedef => self.size_align(ty.aliased.as_deref()?, depth),
+            CTypeKind::Opaque => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+use crate::header_parser::parse_header;
+
+    const HEADER: &str = r#"
+enum Status {
+  Ok,
+  Failed = 5,
+  Retry,
+};
+typedef uint8_t Status;
+
+typedef struct Handle Handle;
+
+typedef struct Point {
+  double x;
+  double y;
+} Point;
+
+typedef struct Packet {
+  uint8_t tag;
+  uint32_t len;
+  const uint8_t *data;
+  Point origin;
+  uint16_t flags[3];
+} Packet;
+
+typedef struct Slice {
+  const uint8_t *ptr;
+  uintptr_t len;
+} Slice;
+
+int32_t send(Handle *handle, const Packet *packet);
+"#;
+
+    fn snapshot(header: &str, target: &str) -> AbiSnapshot {
+        let header = parse_header(header);
+        let symbols = header.functions.iter().map(|f| f.name.clone()).collect();
+        let pointer_size = if target.starts_with("x86_64") && !target.ends_with("x32") { 8 } else { 4 };
+        AbiSnapshot::new("my_crate", target, pointer_size, symbols, &header)
+    }
+
+    #[test]
+    fn test_layouts() {
+        let abi = snapshot(HEADER, "x86_64-unknown-linux-gnu");
+
+        let packet = &abi.types["Packet"];
+        let offsets: Vec<Option<u64>> = packet.fields.iter().map(|f| f.offset).collect();
+        assert_eq!(offsets, vec![Some(0), Some(4), Some(8), Some(16), Some(32)]);
+        assert_eq!((packet.size, packet.align), (Some(40), Some(8)));
+        assert_eq!(packet.fields[4].ty, "uint16_t[3]");
+
+        let status = &abi.types["Status"];
+        assert_eq!(status.size, Some(1));
+        assert_eq!(status.variants["Retry"], 6);
+
+        let arm32 = snapshot(HEADER, "armv7-unknown-linux-gnueabihf");
+        assert_eq!(abi.types["Slice"].size, Some(16));
+        assert_eq!(arm32.types["Slice"].size, Some(8));
+        assert_eq!(arm32.types["Slice"].fields[1].offset, Some(4));
+
+        // x32 has 4-byte pointers and `long`, but keeps 8-byte alignment for `double`
+        let x32 = snapshot(HEADER, "x86_64-unknown-linux-gnux32");
+        assert_eq!(x32.types["Slice"].size, Some(8));
+        assert_eq!(x32.types["Point"].align, Some(8));
+    }
+
+    #[test]
+    fn test_diff_classification() {
+        let old = snapshot(HEADER, "x86_64-unknown-linux-gnu");
+
+        let additive = snapshot(&format!("{}\nvoid reset(void);\n", HEADER), "x86_64-unknown-linux-gnu");
+        let diff = old.diff(&additive);
+        assert!(!diff.is_breaking());
+        assert_eq!(diff.count(ChangeKind::Additive), 1);
+
+        let breaking = snapshot(
+            &HEADER.replace("uint32_t len;", "uint64_t len;").replace("Retry,", "Retry,\n  Fatal,"),
+            "x86_64-unknown-linux-gnu",
+        );
+        let diff = old.diff(&breaking);
+        assert!(diff.is_breaking());
+        assert!(diff.changes.iter().any(|c| c.item == "Packet.len" && c.kind == ChangeKind::Breaking));
+        assert!(diff.changes.iter().any(|c| c.item == "Status::Fatal" && c.kind == ChangeKind::Additive));
+
+        let path = std::env::temp_dir().join(format!("ghostbind-abi-{}.json", std::process::id()));
+        old.write(&path).unwrap();
+        assert_eq!(AbiSnapshot::read(&path).unwrap(), old);
+        fs::remove_file(&path).unwrap();
+
+        // `#[must_use]` only adds the template's attribute macro to the declaration
+        let must_use = snapshot(&HEADER.replace("int32_t send(", "MY_CRATE_MUST_USE int32_t send("), "x86_64-unknown-linux-gnu");
+        assert!(old.diff(&must_use).changes.is_empty());
+
+        let renamed = snapshot(&HEADER.replace("uint8_t tag;", "uint8_t kind;"), "x86_64-unknown-linux-gnu");
+        let diff = old.diff(&renamed);
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].kind, ChangeKind::Compatible);
+    }
+
+    #[test]
+    fn test_load_recorded_builds() {
+        let dir = std::env::temp_dir().join(format!("ghostbind-abi-builds-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // Both builds point at the same cache paths, which only hold the newer build's files
+        let write_build = |name: &str, header: &str| {
+            let manifest = serde_json::json!({
+                "crate_name": "my_crate",
+                "kind": "staticlib",
+                "artifact": ".ghostbind/cache/x86_64-unknown-linux-gnu/libmy_crate.a",
+                "headers": [".ghostbind/cache/x86_64-unknown-linux-gnu/headers/my_crate.h"],
+                "rustc_target": "x86_64-unknown-linux-gnu",
+                "link_libs": [],
+                "link_search": [],
+                "abi": snapshot(header, "x86_64-unknown-linux-gnu"),
+            });
+            let path = dir.join(name);
+            fs::write(&path, serde_json::to_string(&manifest).unwrap()).unwrap();
+            path
+        };
+        let old = write_build("old.json", &format!("{}\nint32_t multiply(int32_t a, int32_t b);\n", HEADER));
+        let new = write_build("new.json", &format!("{}\nint32_t multiply2(int32_t a, int32_t b);\n", HEADER));
+
+        let diff = AbiSnapshot::load(&old).unwrap().diff(&AbiSnapshot::load(&new).unwrap());
+        assert!(diff.is_breaking());
+        assert!(diff.changes.contains(&AbiChange {
+            kind: ChangeKind::Breaking,
+            item: "multiply".to_string(),
+            description: "symbol removed".to_string(),
+        }));
+
+        // A snapshot file loads as well, and a manifest without a recorded ABI is rejected
+        let snapshot_path = dir.join("ghostbind.abi.json");
+        AbiSnapshot::load(&new).unwrap().write(&snapshot_path).unwrap();
+        assert_eq!(AbiSnapshot::load(&snapshot_path).unwrap(), AbiSnapshot::load(&new).unwrap());
+        let mut bare: serde_json::Value = serde_json::from_str(&fs::read_to_string(&old).unwrap()).unwrap();
+        bare.as_object_mut().unwrap().remove("abi");
+        fs::write(&old, bare.to_string()).unwrap();
+        let error = AbiSnapshot::load(&old).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(Error::find(&error), Some(Error::InvalidConfig(_))));
+    }
+}
@@ -4,11 +4,57 @@ use std::path::{Path, PathBuf};
 
 use crate::cargo_integration::{BuildProfile, CrateInfo, TargetKind};
 
+/// The file names a library is built and installed under for a target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryNames {
+    /// The file the linker or loader uses, e.g. `libfoo.a`, `libfoo.so` or `foo.dll`
+    pub library: String,
+    /// The import library of a Windows DLL, e.g. `foo.dll.lib`
+    pub import_library: Option<String>,
+}
+
+impl LibraryNames {
+    pub fn for_target(crate_name: &str, kind: &str, rustc_target: &str) -> Self {
+        let name = crate_name.replace('-', "_");
+        let windows = rustc_target.contains("windows");
+        let msvc = rustc_target.contains("msvc");
+
+        match kind {
+            "cdylib" if windows => Self {
+                library: format!("{}.dll", name),
+                import_library: Some(if msvc { format!("{}.dll.lib", name) } else { format!("lib{}.dll.a", name) }),
+            },
+            "cdylib" if rustc_target.contains("apple") => Self {
+                library: format!("lib{}.dylib", name),
+                import_library: None,
+            },
+            "cdylib" => Self {
+                library: format!("lib{}.so", name),
+                import_library: None,
+            },
+            _ if msvc => Self {
+                library: format!("{}.lib", name),
+                import_library: None,
+            },
+            _ => Self {
+                library: format!("lib{}.a", name),
+                import_library: None,
+            },
+        }
+    }
+
+    /// The file other code links against.
+    pub fn link_file(&self) -> &str {
+        self.import_library.as_deref().unwrap_or(&self.library)
+    }
+}
+
 pub struct ArtifactDiscovery {
     target_dir: PathBuf,
     target_triple: Option<String>,
     profile: BuildProfile,
     cache_dir: PathBuf,
+    cross_compile: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -17,6 +63,8 @@ pub struct DiscoveredArtifact {
     pub kind: ArtifactKind,
     pub original_path: PathBuf,
     pub cached_path: PathBuf,
+    /// The import library of a Windows DLL, cached next to the DLL
+    pub import_library: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -55,9 +103,16 @@ impl ArtifactDiscovery {
             target_triple,
             profile,
             cache_dir,
+            cross_compile: true,
         }
     }
 
+    /// Cargo only nests artifacts under the target triple when `--target` was passed.
+    pub fn cross_compile(mut self, cross_compile: bool) -> Self {
+        self.cross_compile = cross_compile;
+        self
+    }
+
     pub fn discover_artifacts(&self, crate_info: &CrateInfo) -> Result<Vec<DiscoveredArtifact>> {
         let mut artifacts = Vec::new();
 
@@ -77,19 +132,20 @@ impl ArtifactDiscovery {
 
     fn find_artifact(&self, crate_name: &str, kind: &ArtifactKind) -> Result<DiscoveredArtifact> {
         let build_dir = self.get_build_directory();
-        let lib_patterns = self.get_library_patterns(crate_name, kind);
-
-        for pattern in &lib_patterns {
-            let artifact_path = build_dir.join(pattern);
-            if artifact_path.exists() {
-                let cached_path = self.get_cache_path(crate_name, kind);
-                return Ok(DiscoveredArtifact {
-                    name: crate_name.to_string(),
-                    kind: kind.clone(),
-                    original_path: artifact_path,
-                    cached_path,
-                });
-            }
+        let names = self.get_library_names(crate_name, kind);
+
+        let artifact_path = build_dir.join(&names.library);
+        if artifact_path.exists() {
+            let import_library = names.import_library.as_ref()
+                .map(|name| build_dir.join(name))
+                .filter(|path| path.exists());
+            return Ok(DiscoveredArtifact {
+                name: crate_name.to_string(),
+                kind: kind.clone(),
+                original_path: artifact_path,
+                cached_path: self.get_cache_path(crate_name, kind),
+                import_library,
+            });
         }
 
         Err(anyhow!(
@@ -103,63 +159,38 @@ impl ArtifactDiscovery {
     fn get_build_directory(&self) -> PathBuf {
         let mut build_dir = self.target_dir.clone();
 
-        if let Some(ref target) = self.target_triple {
+        if let Some(ref target) = self.target_triple
+            && self.cross_compile
+        {
             build_dir = build_dir.join(target);
         }
 
         build_dir.join(self.profile.as_str())
     }
 
-    fn get_library_patterns(&self, crate_name: &str, kind: &ArtifactKind) -> Vec<String> {
-        let normalized_name = crate_name.replace('-', "_");
-
-        match kind {
-            ArtifactKind::StaticLib => {
-                if cfg!(target_os = "windows") {
-                    vec![format!("{}.lib", normalized_name)]
-                } else {
-                    vec![format!("lib{}.a", normalized_name)]
-                }
-            }
-            ArtifactKind::DynamicLib => {
-                if cfg!(target_os = "windows") {
-                    vec![
-                        format!("{}.dll", normalized_name),
-                        format!("{}.dll.lib", normalized_name),
-                    ]
-                } else if cfg!(target_os = "macos") {
-                    vec![format!("lib{}.dylib", normalized_name)]
-                } else {
-                    vec![format!("lib{}.so", normalized_name)]
-                }
-            }
+    /// The target the artifacts were built for; without an explicit triple this is the host.
+    fn target(&self) -> &str {
+        match &self.target_triple {
+            Some(target) => target,
+            None if cfg!(all(target_os = "windows", target_env = "msvc")) => "windows-msvc",
+            None if cfg!(target_os = "windows") => "windows-gnu",
+            None if cfg!(target_vendor = "apple") => "apple-darwin",
+            None => "unknown-linux",
         }
     }
 
+    fn get_library_names(&self, crate_name: &str, kind: &ArtifactKind) -> LibraryNames {
+        LibraryNames::for_target(crate_name, kind.as_str(), self.target())
+    }
+
     fn get_cache_path(&self, crate_name: &str, kind: &ArtifactKind) -> PathBuf {
         let target_str = self.target_triple.as_deref().unwrap_or("native");
 
+        // Keep the platform file name so `-l<crate>` finds the cached library
         self.cache_dir
             .join(target_str)
             .join(self.profile.as_str())
-            .join(format!("{}.{}", crate_name, self.get_artifact_extension(kind)))
-    }
-
-    fn get_artifact_extension(&self, kind: &ArtifactKind) -> &str {
-        match kind {
-            ArtifactKind::StaticLib => {
-                if cfg!(target_os = "windows") { "lib" } else { "a" }
-            }
-            ArtifactKind::DynamicLib => {
-                if cfg!(target_os = "windows") {
-                    "dll"
-                } else if cfg!(target_os = "macos") {
-                    "dylib"
-                } else {
-                    "so"
-                }
-            }
-        }
+            .join(self.get_library_names(crate_name, kind).library)
     }
 
     pub fn cache_artifacts(&self, artifacts: &[DiscoveredArtifact]) -> Result<()> {
@@ -186,12 +217,12 @@ impl ArtifactDiscovery {
                 )
             })?;
 
-        println!(
-            "Cached {} artifact: {} -> {}",
-            artifact.kind.as_str(),
-            artifact.original_path.display(),
-            artifact.cached_path.display()
-        );
+        if let Some(import_library) = &artifact.import_library
+            && let (Some(cache_parent), Some(file_name)) = (artifact.cached_path.parent(), import_library.file_name())
+        {
+            fs::copy(import_library, cache_parent.join(file_name))
+                .with_context(|| format!("Failed to copy import library {}", import_library.display()))?;
+        }
 
         Ok(())
     }
@@ -202,34 +233,37 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_library_patterns() {
-        let discovery = ArtifactDiscovery::new("/tmp", None, BuildProfile::Release);
-
-        let static_patterns = discovery.get_library_patterns("my_crate", &ArtifactKind::StaticLib);
-        let dynamic_patterns = discovery.get_library_patterns("my_crate", &ArtifactKind::DynamicLib);
-
-        if cfg!(target_os = "windows") {
-            assert!(static_patterns.contains(&"my_crate.lib".to_string()));
-            assert!(dynamic_patterns.contains(&"my_crate.dll".to_string()));
-        } else {
-            assert!(static_patterns.contains(&"libmy_crate.a".to_string()));
-            if cfg!(target_os = "macos") {
-                assert!(dynamic_patterns.contains(&"libmy_crate.dylib".to_string()));
-            } else {
-                assert!(dynamic_patterns.contains(&"libmy_crate.so".to_string()));
-            }
-        }
+    fn test_library_names() {
+        assert_eq!(LibraryNames::for_target("my-crate", "staticlib", "x86_64-unknown-linux-gnu").library, "libmy_crate.a");
+        assert_eq!(LibraryNames::for_target("my-crate", "staticlib", "x86_64-pc-windows-msvc").library, "my_crate.lib");
+        assert_eq!(LibraryNames::for_target("my-crate", "staticlib", "x86_64-pc-windows-gnu").library, "libmy_crate.a");
+        assert_eq!(LibraryNames::for_target("my-crate", "cdylib", "aarch64-apple-darwin").library, "libmy_crate.dylib");
+
+        let dll = LibraryNames::for_target("my-crate", "cdylib", "x86_64-pc-windows-gnu");
+        assert_eq!(dll.library, "my_crate.dll");
+        assert_eq!(dll.link_file(), "libmy_crate.dll.a");
     }
 
     #[test]
     fn test_cache_path_generation() {
         let discovery = ArtifactDiscovery::new("/tmp", Some("x86_64-unknown-linux-gnu".to_string()), BuildProfile::Release);
+        let cache_path = discovery.get_cache_path("my-crate", &ArtifactKind::StaticLib);
+        assert_eq!(cache_path, PathBuf::from(".ghostbind/cache/x86_64-unknown-linux-gnu/release/libmy_crate.a"));
+
+        // The target decides the name, not the host
+        let windows = ArtifactDiscovery::new("/tmp", Some("x86_64-pc-windows-msvc".to_string()), BuildProfile::Debug);
+        let cache_path = windows.get_cache_path("my-crate", &ArtifactKind::DynamicLib);
+        assert_eq!(cache_path, PathBuf::from(".ghostbind/cache/x86_64-pc-windows-msvc/debug/my_crate.dll"));
+    }
+
+    #[test]
+    fn test_host_build_directory() {
+        let target = Some("x86_64-unknown-linux-gnu".to_string());
 
-        let cache_path = discovery.get_cache_path("my_crate", &ArtifactKind::StaticLib);
-        let expected_extension = if cfg!(target_os = "windows") { "lib" } else { "a" };
+        let cross = ArtifactDiscovery::new("/tmp/target", target.clone(), BuildProfile::Release);
+        assert_eq!(cross.get_build_directory(), PathBuf::from("/tmp/target/x86_64-unknown-linux-gnu/release"));
 
-        assert!(cache_path.to_string_lossy().contains("x86_64-unknown-linux-gnu"));
-        assert!(cache_path.to_string_lossy().contains("release"));
-        assert!(cache_path.to_string_lossy().ends_with(&format!("my_crate.{}", expected_extension)));
+        let host = ArtifactDiscovery::new("/tmp/target", target, BuildProfile::Release).cross_compile(false);
+        assert_eq!(host.get_build_directory(), PathBuf::from("/tmp/target/release"));
     }
 }
\ No newline at end of file
@@ -1,14 +1,21 @@
 use anyhow::{anyhow, Context, Result};
-use cargo_metadata::{Metadata, MetadataCommand};
+use cargo_metadata::{CargoOpt, DependencyKind, Metadata, MetadataCommand, Package, PackageId};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::error::Error;
+use crate::target_cfg::rustflags;
+
 pub struct CargoBuilder {
     manifest_path: PathBuf,
     target: Option<String>,
     profile: BuildProfile,
     features: Vec<String>,
     no_default_features: bool,
+    target_dir: Option<PathBuf>,
+    reproducible: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -29,15 +36,107 @@ impl BuildProfile {
 #[derive(Debug, Clone)]
 pub struct CrateInfo {
     pub name: String,
+    pub version: String,
     pub targets: Vec<CrateTarget>,
     pub manifest_dir: PathBuf,
     pub target_directory: PathBuf,
+    pub workspace_root: PathBuf,
+    /// Features of this crate enabled by the resolved feature set
+    pub enabled_features: Vec<String>,
+    /// The raw `cargo metadata` output, resolved with the selected features
+    pub metadata: Metadata,
+}
+
+impl CrateInfo {
+    /// The library target; its staticlib and cdylib kinds share the same sources.
+    pub fn lib_target(&self) -> Result<&CrateTarget> {
+        self.targets.iter()
+            .find(|t| t.kind.is_library())
+            .ok_or_else(|| anyhow!("Crate '{}' has no library target", self.name))
+    }
+
+    /// The library's root source file and every module file it declares. `cfg`-gated
+    /// modules are included; binaries and files no `mod` points at are not.
+    pub fn lib_source_files(&self) -> Result<Vec<PathBuf>> {
+        let mut sources = BTreeSet::new();
+        collect_module_files(&self.lib_target()?.src_path, true, &mut sources)?;
+        Ok(sources.into_iter().collect())
+    }
+}
+
+/// Adds `file` and the files of the modules it declares. A file that owns its directory
+/// (a crate root, `mod.rs` or a `#[path]` target) keeps child modules next to itself;
+/// `foo.rs` keeps them in `foo/`.
+fn collect_module_files(file: &Path, owns_dir: bool, sources: &mut BTreeSet<PathBuf>) -> Result<()> {
+    if !sources.insert(file.to_path_buf()) {
+        return Ok(());
+    }
+
+    let source = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    // Whoever parses the file next reports the syntax error
+    let Ok(parsed) = syn::parse_file(&source) else {
+        return Ok(());
+    };
+
+    let dir = file.parent().unwrap_or(Path::new(""));
+    let module_dir = match file.file_stem() {
+        Some(stem) if !owns_dir => dir.join(stem),
+        _ => dir.to_path_buf(),
+    };
+    collect_declared_modules(&parsed.items, dir, &module_dir, sources)
+}
+
+/// `path_dir` is what `#[path]` is relative to, `module_dir` where `mod foo;` looks for `foo.rs`.
+fn collect_declared_modules(items: &[syn::Item], path_dir: &Path, module_dir: &Path, sources: &mut BTreeSet<PathBuf>) -> Result<()> {
+    for item in items {
+        let syn::Item::Mod(item_mod) = item else {
+            continue;
+        };
+        let name = item_mod.ident.to_string();
+        let name = name.trim_start_matches("r#");
+        let path_attr = item_mod.attrs.iter().find_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. }),
+                ..
+            }) if path.is_ident("path") => Some(value.value()),
+            _ => None,
+        });
+
+        match (&item_mod.content, path_attr) {
+            (Some((_, items)), path_attr) => {
+                let nested = match path_attr {
+                    Some(path) => path_dir.join(path),
+                    None => module_dir.join(name),
+                };
+                collect_declared_modules(items, &nested, &nested, sources)?;
+            }
+            (None, Some(path)) => {
+                let file = path_dir.join(path);
+                if file.exists() {
+                    collect_module_files(&file, true, sources)?;
+                }
+            }
+            (None, None) => {
+                let flat = module_dir.join(format!("{}.rs", name));
+                let nested = module_dir.join(name).join("mod.rs");
+                if flat.exists() {
+                    collect_module_files(&flat, false, sources)?;
+                } else if nested.exists() {
+                    collect_module_files(&nested, true, sources)?;
+                }
+            }
+        }
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone)]
 pub struct CrateTarget {
     pub name: String,
     pub kind: TargetKind,
+    pub src_path: PathBuf,
 }
 
 #[derive(Debug, Clone)]
@@ -74,9 +173,24 @@ impl CargoBuilder {
             profile: BuildProfile::Release,
             features: Vec::new(),
             no_default_features: false,
+            target_dir: None,
+            reproducible: false,
         }
     }
 
+    /// Builds into `target_dir` instead of the crate's own target directory.
+    pub fn target_dir(mut self, target_dir: Option<PathBuf>) -> Self {
+        self.target_dir = target_dir;
+        self
+    }
+
+    /// Builds with `--locked`, a fixed `SOURCE_DATE_EPOCH` and machine-specific paths
+    /// remapped, so the same sources give the same artifact on any machine.
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
     pub fn target(mut self, target: String) -> Self {
         self.target = Some(target);
         self
@@ -98,15 +212,42 @@ impl CargoBuilder {
     }
 
     pub fn get_metadata(&self) -> Result<CrateInfo> {
-        let mut cmd = MetadataCommand::new();
-        cmd.manifest_path(&self.manifest_path);
+        if !self.manifest_path.exists() {
+            return Err(Error::InvalidConfig(format!("Cargo manifest not found: {}", self.manifest_path.display())).into());
+        }
 
-        let metadata = cmd.exec()
+        let metadata = self.metadata_command().exec()
+            .map_err(metadata_error)
             .context("Failed to execute cargo metadata")?;
 
         self.extract_crate_info(&metadata)
     }
 
+    /// The dependency graph as built for `target`, leaving out dependencies of other platforms.
+    pub fn resolve_for_platform(&self, target: &str) -> Result<Metadata> {
+        let mut cmd = self.metadata_command();
+        cmd.other_options(vec!["--filter-platform".to_string(), target.to_string()]);
+
+        cmd.exec()
+            .map_err(metadata_error)
+            .with_context(|| format!("Failed to execute cargo metadata for {}", target))
+    }
+
+    fn metadata_command(&self) -> MetadataCommand {
+        let mut cmd = MetadataCommand::new();
+        cmd.manifest_path(&self.manifest_path);
+
+        // Resolve the dependency graph with the same features we build with
+        if self.no_default_features {
+            cmd.features(CargoOpt::NoDefaultFeatures);
+        }
+        if !self.features.is_empty() {
+            cmd.features(CargoOpt::SomeFeatures(self.features.clone()));
+        }
+
+        cmd
+    }
+
     fn extract_crate_info(&self, metadata: &Metadata) -> Result<CrateInfo> {
         // Find the root package (the one with the manifest we're looking at)
         let manifest_path_canonical = self.manifest_path.canonicalize()
@@ -128,21 +269,54 @@ impl CargoBuilder {
             .map(|target| CrateTarget {
                 name: target.name.clone(),
                 kind: TargetKind::from_cargo_kinds(&target.kind),
+                src_path: target.src_path.clone().into_std_path_buf(),
             })
             .collect();
 
-        let manifest_dir = self.manifest_path.parent()
+        let enabled_features = metadata.resolve.as_ref()
+            .and_then(|resolve| resolve.nodes.iter().find(|node| node.id == package.id))
+            .map(|node| node.features.clone())
+            .unwrap_or_default();
+
+        // Use the absolute path cargo reports rather than the one we were given
+        let manifest_dir = package.manifest_path.parent()
             .ok_or_else(|| anyhow!("Invalid manifest path"))?;
 
         Ok(CrateInfo {
             name: package.name.clone(),
+            version: package.version.to_string(),
             targets,
-            manifest_dir: manifest_dir.to_path_buf(),
+            manifest_dir: manifest_dir.to_path_buf().into_std_path_buf(),
             target_directory: metadata.target_directory.clone().into_std_path_buf(),
+            workspace_root: metadata.workspace_root.clone().into_std_path_buf(),
+            enabled_features,
+            metadata: metadata.clone(),
         })
     }
 
     pub fn build(&self) -> Result<()> {
+        let mut cmd = self.build_command()?;
+        let output = cmd.output()
+            .context("Failed to execute cargo build")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+            if let Some(ref target) = self.target
+                && stderr.contains("target may not be installed")
+            {
+                return Err(Error::TargetUnsupported {
+                    target: target.clone(),
+                    reason: format!("its standard library is not installed. Run: rustup target add {}", target),
+                }.into());
+            }
+            return Err(Error::CargoFailed { stderr }.into());
+        }
+
+        Ok(())
+    }
+
+    /// The `cargo build` invocation, as `build` runs it.
+    fn build_command(&self) -> Result<Command> {
         let mut cmd = Command::new("cargo");
         cmd.arg("build");
         cmd.arg("--manifest-path").arg(&self.manifest_path);
@@ -168,18 +342,227 @@ impl CargoBuilder {
             cmd.arg("--features").arg(self.features.join(","));
         }
 
+        if let Some(ref target_dir) = self.target_dir {
+            cmd.arg("--target-dir").arg(target_dir);
+        }
+
+        if self.reproducible {
+            self.configure_reproducible(&mut cmd)?;
+        }
+
         // Only build library targets for FFI
         cmd.arg("--lib");
 
+        Ok(cmd)
+    }
+
+    /// The `cargo build` command line, for the build's provenance.
+    pub fn command_line(&self) -> Result<String> {
+        let cmd = self.build_command()?;
+        let mut words = vec![cmd.get_program().to_string_lossy().into_owned()];
+        for arg in cmd.get_args() {
+            let arg = arg.to_string_lossy();
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                words.push(format!("'{}'", arg));
+            } else {
+                words.push(arg.into_owned());
+            }
+        }
+        Ok(words.join(" "))
+    }
+
+    /// The RUSTFLAGS the build runs with, if any, space-separated. Flags passed with
+    /// `--config` are part of the command line instead.
+    pub fn rustflags(&self) -> Result<Option<String>> {
+        let cmd = self.build_command()?;
+        let encoded = cmd.get_envs()
+            .find(|(key, _)| *key == "CARGO_ENCODED_RUSTFLAGS")
+            .and_then(|(_, value)| value.map(|v| v.to_string_lossy().into_owned()))
+            .or_else(|| std::env::var("CARGO_ENCODED_RUSTFLAGS").ok());
+        let flags = rustflags(encoded, std::env::var("RUSTFLAGS").ok()).join(" ");
+        Ok(Some(flags).filter(|flags| !flags.is_empty()))
+    }
+
+    /// `SOURCE_DATE_EPOCH` from the environment, else the time of the crate's last git
+    /// commit, else the Unix epoch.
+    pub fn source_date_epoch(&self) -> Result<u64> {
+        if let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH") {
+            return epoch.trim().parse()
+                .with_context(|| format!("Invalid SOURCE_DATE_EPOCH: {}", epoch));
+        }
+
+        let commit_time = Command::new("git")
+            .args(["log", "-1", "--format=%ct"])
+            .current_dir(self.crate_dir()?)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok());
+        Ok(commit_time.unwrap_or(0))
+    }
+
+    fn crate_dir(&self) -> Result<PathBuf> {
+        self.manifest_path.canonicalize()
+            .with_context(|| format!("Failed to canonicalize manifest path: {}", self.manifest_path.display()))?
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| anyhow!("Invalid manifest path"))
+    }
+
+    fn configure_reproducible(&self, cmd: &mut Command) -> Result<()> {
+        cmd.arg("--locked");
+
+        let crate_dir = self.crate_dir()?;
+        cmd.env("SOURCE_DATE_EPOCH", self.source_date_epoch()?.to_string());
+
+        // rustc applies the last matching remap, so go from the broadest prefix to the narrowest
+        let mut remaps = Vec::new();
+        if let Some(home) = std::env::var_os("HOME") {
+            remaps.push((PathBuf::from(home), "/home"));
+        }
+        let cargo_home = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")));
+        if let Some(cargo_home) = cargo_home {
+            remaps.push((cargo_home.join("registry").join("src"), "/cargo/registry/src"));
+            remaps.push((cargo_home.join("git").join("checkouts"), "/cargo/git"));
+        }
+        remaps.push((crate_dir, "/build"));
+        if let Some(ref target_dir) = self.target_dir {
+            remaps.push((std::path::absolute(target_dir)?, "/build/target"));
+        }
+
+        let remaps = remaps.into_iter()
+            .map(|(from, to)| format!("--remap-path-prefix={}={}", from.display(), to))
+            .collect();
+        match extra_rustflags(std::env::var("CARGO_ENCODED_RUSTFLAGS").ok(), std::env::var("RUSTFLAGS").ok(), remaps) {
+            ExtraRustflags::Encoded(flags) => {
+                cmd.env_remove("RUSTFLAGS");
+                cmd.env("CARGO_ENCODED_RUSTFLAGS", flags);
+            }
+            ExtraRustflags::Config(config) => {
+                cmd.arg("--config").arg(config);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds the library as an rlib in a separate target directory so Rust code can link
+    /// against it, even when the crate itself only produces a staticlib or cdylib.
+    pub fn build_rlib(&self, lib_name: &str, target_dir: &Path) -> Result<PathBuf> {
+        let mut cmd = Command::new("cargo");
+        cmd.arg("rustc");
+        cmd.arg("--manifest-path").arg(&self.manifest_path);
+        cmd.args(["--lib", "--crate-type", "rlib"]);
+        cmd.arg("--target-dir").arg(target_dir);
+
+        if let BuildProfile::Release = self.profile {
+            cmd.arg("--release");
+        }
+
+        if self.no_default_features {
+            cmd.arg("--no-default-features");
+        }
+
+        if !self.features.is_empty() {
+            cmd.arg("--features").arg(self.features.join(","));
+        }
+
         let output = cmd.output()
-            .context("Failed to execute cargo build")?;
+            .context("Failed to execute cargo rustc")?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("Cargo build failed: {}", stderr));
+            return Err(anyhow!("Cargo rlib build failed: {}", stderr));
         }
 
-        Ok(())
+        Ok(target_dir.join(self.profile.as_str()).join(format!("lib{}.rlib", lib_name.replace('-', "_"))))
+    }
+}
+
+/// Where extra rustc flags for a build go.
+#[derive(Debug, PartialEq, Eq)]
+enum ExtraRustflags {
+    /// `CARGO_ENCODED_RUSTFLAGS`: the flags the environment already sets, then the extra ones
+    Encoded(String),
+    /// A `--config build.rustflags=[...]` value, which cargo appends to `build.rustflags`
+    /// from .cargo/config.toml
+    Config(String),
+}
+
+/// Setting RUSTFLAGS would make cargo ignore the rustflags in .cargo/config.toml, so
+/// that's only done when the environment sets them already. (`target.<triple>.rustflags`
+/// in a config file still take precedence over `build.rustflags`.)
+fn extra_rustflags(encoded: Option<String>, plain: Option<String>, extra: Vec<String>) -> ExtraRustflags {
+    if encoded.is_none() && plain.is_none() {
+        let values: Vec<String> = extra.into_iter().map(|flag| toml::Value::String(flag).to_string()).collect();
+        return ExtraRustflags::Config(format!("build.rustflags=[{}]", values.join(", ")));
+    }
+
+    let mut flags = rustflags(encoded, plain);
+    flags.extend(extra);
+    ExtraRustflags::Encoded(flags.join("\x1f"))
+}
+
+/// cargo's own output when `cargo metadata` ran and failed, else the error itself.
+fn metadata_error(error: cargo_metadata::Error) -> Error {
+    match error {
+        cargo_metadata::Error::CargoMetadata { stderr } => Error::CargoFailed { stderr },
+        other => Error::CargoFailed { stderr: other.to_string() },
+    }
+}
+
+/// The packages linked into a crate's artifact and the dependency edges between them.
+#[derive(Debug, Clone)]
+pub struct LinkedDependencies<'a> {
+    pub root: &'a Package,
+    /// Every dependency, sorted by name and version
+    pub packages: Vec<&'a Package>,
+    /// The linked dependencies of the root and of each package
+    pub edges: BTreeMap<&'a PackageId, Vec<&'a PackageId>>,
+}
+
+impl<'a> LinkedDependencies<'a> {
+    /// Every package reachable from `crate_name` through normal dependencies. Build
+    /// dependencies and proc-macros run at compile time and are not linked in.
+    pub fn resolve(crate_name: &str, metadata: &'a Metadata) -> Result<Self> {
+        let packages: HashMap<&PackageId, &Package> = metadata.packages.iter().map(|p| (&p.id, p)).collect();
+        let root = metadata.packages.iter()
+            .find(|p| p.name == crate_name && metadata.workspace_members.contains(&p.id))
+            .ok_or_else(|| anyhow!("Could not find package '{}' in cargo metadata", crate_name))?;
+        let resolve = metadata.resolve.as_ref()
+            .ok_or_else(|| anyhow!("cargo metadata did not resolve dependencies"))?;
+        let nodes: HashMap<&PackageId, _> = resolve.nodes.iter().map(|n| (&n.id, n)).collect();
+
+        let mut seen = BTreeSet::new();
+        let mut edges = BTreeMap::new();
+        let mut queue = VecDeque::from([&root.id]);
+        while let Some(id) = queue.pop_front() {
+            let Some(node) = nodes.get(id) else { continue };
+            let mut linked = Vec::new();
+            for dep in &node.deps {
+                let normal = dep.dep_kinds.iter().any(|k| k.kind == DependencyKind::Normal);
+                let proc_macro = packages.get(&dep.pkg)
+                    .is_some_and(|p| p.targets.iter().all(|t| t.kind.iter().any(|k| k == "proc-macro")));
+                if normal && !proc_macro {
+                    linked.push(&dep.pkg);
+                    if seen.insert(&dep.pkg) {
+                        queue.push_back(&dep.pkg);
+                    }
+                }
+            }
+            edges.insert(id, linked);
+        }
+
+        let mut linked: Vec<&Package> = seen.into_iter().filter_map(|id| packages.get(id).copied()).collect();
+        linked.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        Ok(Self {
+            root,
+            packages: linked,
+            edges,
+        })
     }
 }
 
@@ -203,4 +586,56 @@ mod tests {
         assert!(TargetKind::CdyLib.is_library());
         assert!(!TargetKind::Bin.is_library());
     }
+
+    #[test]
+    fn test_extra_rustflags() {
+        let remap = vec!["--remap-path-prefix=C:\\Users\\Jane Doe=/home".to_string()];
+
+        assert_eq!(
+            extra_rustflags(None, None, remap.clone()),
+            ExtraRustflags::Config(r#"build.rustflags=['--remap-path-prefix=C:\Users\Jane Doe=/home']"#.to_string())
+        );
+        assert_eq!(
+            extra_rustflags(None, Some("-C target-cpu=native".to_string()), remap.clone()),
+            ExtraRustflags::Encoded("-C\x1ftarget-cpu=native\x1f--remap-path-prefix=C:\\Users\\Jane Doe=/home".to_string())
+        );
+        assert_eq!(
+            extra_rustflags(Some(String::new()), None, remap),
+            ExtraRustflags::Encoded("--remap-path-prefix=C:\\Users\\Jane Doe=/home".to_string())
+        );
+    }
+
+    #[test]
+    fn test_collect_module_files() {
+        let dir = std::env::temp_dir().join(format!("ghostbind-modules-{}", std::process::id()));
+        let files = [
+            ("src/lib.rs", "mod flat;\nmod nested;\nmod inline { mod deep; }\n#[path = \"gen/out.rs\"]\nmod generated;\n#[cfg(windows)]\nmod win;\nmod missing;\n"),
+            ("src/flat.rs", "mod child;\n"),
+            ("src/flat/child.rs", ""),
+            ("src/nested/mod.rs", "mod leaf;\n"),
+            ("src/nested/leaf.rs", ""),
+            ("src/inline/deep.rs", ""),
+            ("src/gen/out.rs", "mod helper;\n"),
+            ("src/gen/helper.rs", ""),
+            ("src/win.rs", ""),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/bin/tool.rs", "fn main() {}\n"),
+            ("src/unlinked.rs", ""),
+        ];
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let mut sources = BTreeSet::new();
+        collect_module_files(&dir.join("src/lib.rs"), true, &mut sources).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let found: Vec<_> = sources.iter().map(|p| p.strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/")).collect();
+        assert_eq!(found, vec![
+            "src/flat/child.rs", "src/flat.rs", "src/gen/helper.rs", "src/gen/out.rs", "src/inline/deep.rs",
+            "src/lib.rs", "src/nested/leaf.rs", "src/nested/mod.rs", "src/win.rs",
+        ]);
+    }
 }
\ No newline at end of file
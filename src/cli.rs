@@ -1,37 +1,246 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::json;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
 
-use crate::artifact_discovery::ArtifactDiscovery;
+use crate::abi::{AbiSnapshot, ChangeKind};
 use crate::cargo_integration::{BuildProfile, CargoBuilder};
-use crate::header_generation::HeaderGenerator;
-use crate::manifest::ManifestGenerator;
-use crate::target_mapping::TargetMapping;
+use crate::error::{Error, EXIT_FAILURE};
+use crate::header_generation::{ConfigStatus, HeaderGenerator, HeaderLanguage, HeaderMode};
+use crate::install::Installer;
+use crate::lint::FfiLinter;
+use crate::manifest::{ManifestFormat, ManifestGenerator};
+use crate::package::Packager;
+use crate::pipeline::{check_headers, check_licenses, check_symbols, collect_licenses, Event, Pipeline};
+use crate::reproducible::ReproVerifier;
+use crate::scaffold::Scaffolder;
+use crate::sbom::SbomFormat;
+use crate::smoke_test::SmokeTester;
+use crate::symbol_check::{library_name, SymbolCheckMode};
+use crate::target_mapping::{get_host_target, TargetMapping};
+use crate::watch::{output_fingerprint, watched_paths, Watcher};
+use crate::zig_module::ZigModuleGenerator;
+
+/// `println!` for command output, honouring `--message-format`.
+macro_rules! say {
+    ($out:expr) => { $out.text("") };
+    ($out:expr, $($arg:tt)*) => { $out.text(format_args!($($arg)*)) };
+}
 
 #[derive(Parser)]
 #[command(name = "ghostbind")]
 #[command(about = "A tiny, predictable build/FFI bridge that lets Zig consume Rust crates")]
 #[command(version = "0.1.0")]
 pub struct Cli {
+    /// Output format: human-readable text, or one JSON event per line on stdout (text goes to stderr)
+    #[arg(long, global = true, value_enum, default_value = "human")]
+    pub message_format: MessageFormat,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
+/// Where a command's output goes. With `--message-format json` stdout only carries
+/// newline-delimited JSON events and the human-readable text moves to stderr.
+#[derive(Debug, Clone, Copy)]
+struct Output {
+    format: MessageFormat,
+}
+
+impl Output {
+    fn is_json(&self) -> bool {
+        self.format == MessageFormat::Json
+    }
+
+    fn text(&self, text: impl fmt::Display) {
+        match self.format {
+            MessageFormat::Human => println!("{}", text),
+            MessageFormat::Json => eprintln!("{}", text),
+        }
+    }
+
+    fn event(&self, event: &Event) {
+        match self.format {
+            MessageFormat::Human => println!("{}", event),
+            MessageFormat::Json => println!("{}", event.to_json()),
+        }
+    }
+
+    /// A command's `--json` report: pretty-printed, or as a single `report` event.
+    fn report(&self, command: &str, report: &impl Serialize) -> Result<()> {
+        match self.format {
+            MessageFormat::Human => println!("{}", serde_json::to_string_pretty(report)?),
+            MessageFormat::Json => println!("{}", json!({ "event": "report", "command": command, "report": report })),
+        }
+        Ok(())
+    }
+
+    fn error(&self, error: &anyhow::Error) {
+        eprintln!("Error: {}", error);
+        let mut causes = Vec::new();
+        let mut source = error.source();
+        while let Some(cause) = source {
+            eprintln!("Caused by: {}", cause);
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+
+        if self.is_json() {
+            let typed = Error::find(error);
+            println!("{}", json!({
+                "event": "error",
+                "code": exit_code(error),
+                "kind": typed.map(Error::kind).unwrap_or("other"),
+                "message": error.to_string(),
+                "causes": causes,
+            }));
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Build a Rust crate and generate FFI artifacts
-    Build {
+    Build(BuildArgs),
+
+    /// Generate headers only (assumes crate is already built)
+    Headers {
+        /// Path to Cargo.toml
+        #[arg(long, default_value = "Cargo.toml")]
+        manifest_path: PathBuf,
+
+        /// Target triple
+        #[arg(long)]
+        target: Option<String>,
+
+        /// Path to cbindgen config
+        #[arg(long)]
+        cbindgen_config: Option<PathBuf>,
+
+        /// Header mode for cfg-gated items (per-target or merged)
+        #[arg(long, default_value = "per-target")]
+        header_mode: String,
+
+        /// Generate a header per language (c, c++, cython) instead of the cbindgen config's
+        #[arg(long, value_delimiter = ',')]
+        header_lang: Vec<String>,
+    },
+
+    /// Compile-check the headers listed in a ghostbind manifest
+    CheckHeader {
+        /// Path to the ghostbind manifest JSON
+        manifest: PathBuf,
+    },
+
+    /// Compare header declarations with the symbols exported by a manifest's artifact
+    CheckSymbols {
+        /// Path to the ghostbind manifest JSON
+        manifest: PathBuf,
+    },
+
+    /// Compare the C ABI of two builds and fail on breaking changes
+    AbiDiff {
+        /// Manifest or ABI snapshot of the previous build
+        old: PathBuf,
+
+        /// Manifest or ABI snapshot of the new build
+        new: PathBuf,
+    },
+
+    /// Build and run a C program that links a manifest's artifact and calls its functions
+    Test {
+        /// Path to the ghostbind manifest JSON
+        manifest: PathBuf,
+    },
+
+    /// Write a Zig package with a build.zig `link` function for built manifests
+    ZigModule {
+        /// Manifests of the crate, one per target
+        #[arg(required = true)]
+        manifests: Vec<PathBuf>,
+
+        /// Directory to write build.zig and build.zig.zon to
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Show what a manifest describes and the provenance of its build
+    Inspect {
+        /// Path to the ghostbind manifest JSON
+        manifest: PathBuf,
+    },
+
+    /// Install a manifest's library, headers and package files into a prefix
+    Install {
+        /// Path to the ghostbind manifest JSON
+        manifest: PathBuf,
+
+        /// Installation prefix
+        #[arg(long)]
+        prefix: PathBuf,
+
+        /// Stage the install under this directory (defaults to $DESTDIR)
+        #[arg(long)]
+        destdir: Option<PathBuf>,
+    },
+
+    /// Pack each manifest's build into a <crate>-<version>-<zig-target>.tar.gz release archive
+    Package {
+        /// Manifests to package, one archive each
+        #[arg(required = true)]
+        manifests: Vec<PathBuf>,
+
+        /// Path to Cargo.toml (LICENSE files are taken from its directory)
+        #[arg(long, default_value = "Cargo.toml")]
+        manifest_path: PathBuf,
+
+        /// Directory to write the archives to
+        #[arg(long, default_value = ".ghostbind/dist")]
+        output: PathBuf,
+    },
+
+    /// Collect the licenses of the dependencies linked into the crate's artifact
+    Licenses {
         /// Path to Cargo.toml
         #[arg(long, default_value = "Cargo.toml")]
         manifest_path: PathBuf,
 
-        /// Target triple for Zig (will be mapped to Rust target)
+        /// Rust target triple (defaults to the host)
+        #[arg(long)]
+        target: Option<String>,
+
+        /// Features to enable
+        #[arg(long)]
+        features: Vec<String>,
+
+        /// Disable default features
+        #[arg(long)]
+        no_default_features: bool,
+
+        /// Print the summary as JSON
         #[arg(long)]
-        zig_target: Option<String>,
+        json: bool,
+    },
+
+    /// Build the crate twice in reproducible mode and compare the artifact and header hashes
+    VerifyRepro {
+        /// Path to Cargo.toml
+        #[arg(long, default_value = "Cargo.toml")]
+        manifest_path: PathBuf,
 
-        /// Override Rust target (bypasses mapping)
+        /// Rust target triple (defaults to the host)
         #[arg(long)]
-        rust_target: Option<String>,
+        target: Option<String>,
 
         /// Build profile
         #[arg(long, default_value = "release")]
@@ -48,271 +257,913 @@ pub enum Commands {
         /// Path to cbindgen config
         #[arg(long)]
         cbindgen_config: Option<PathBuf>,
+    },
+
+    /// Check exported extern "C" functions against the FFI safety rules
+    Lint {
+        /// Path to Cargo.toml
+        #[arg(long, default_value = "Cargo.toml")]
+        manifest_path: PathBuf,
 
-        /// Generate default cbindgen config if none exists
+        /// Print diagnostics as JSON
         #[arg(long)]
-        generate_cbindgen_config: bool,
+        json: bool,
     },
 
-    /// Generate headers only (assumes crate is already built)
-    Headers {
+    /// Rebuild whenever the crate's sources, Cargo.toml or cbindgen config change
+    Watch {
+        #[command(flatten)]
+        build: BuildArgs,
+
+        /// Command to run after each build that changed the artifacts or headers, e.g. "zig build run"
+        #[arg(long)]
+        exec: Option<String>,
+
+        /// Milliseconds the files must stay unchanged before rebuilding
+        #[arg(long, default_value = "300")]
+        debounce: u64,
+    },
+
+    /// Write cbindgen.toml from ghostbind's template for the crate
+    CbindgenConfig {
         /// Path to Cargo.toml
         #[arg(long, default_value = "Cargo.toml")]
         manifest_path: PathBuf,
 
-        /// Target triple
+        /// Language of the bindings (c, c++ or cython)
+        #[arg(long, default_value = "c")]
+        lang: String,
+
+        /// Replace an existing cbindgen.toml that differs from the template. The template's
+        /// type prefix and enum variant renaming change the names the header exports
         #[arg(long)]
-        target: Option<String>,
+        force: bool,
 
-        /// Path to cbindgen config
+        /// Show how an existing cbindgen.toml differs from the template, without writing
+        #[arg(long, conflicts_with = "force")]
+        diff: bool,
+    },
+
+    /// Create a new FFI crate and a Zig project that builds and links it
+    Init {
+        /// Directory to create the project in
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Crate and project name [default: the directory's name]
         #[arg(long)]
-        cbindgen_config: Option<PathBuf>,
+        name: Option<String>,
+
+        /// Create the Rust FFI crate (with --zig, in `rust/` inside the project)
+        #[arg(long)]
+        lib: bool,
+
+        /// Create a Zig project whose build.zig runs ghostbind and links the crate
+        #[arg(long)]
+        zig: bool,
+
+        /// The crate's directory, relative to the Zig project [default: rust]
+        #[arg(long = "crate")]
+        crate_dir: Option<PathBuf>,
     },
 
     /// Check system requirements and configuration
     Doctor,
 }
 
+#[derive(Args)]
+pub struct BuildArgs {
+    /// Path to Cargo.toml
+    #[arg(long, default_value = "Cargo.toml")]
+    pub manifest_path: PathBuf,
+
+    /// Target triple for Zig (will be mapped to Rust target)
+    #[arg(long)]
+    pub zig_target: Option<String>,
+
+    /// Override Rust target (bypasses mapping)
+    #[arg(long)]
+    pub rust_target: Option<String>,
+
+    /// Build profile
+    #[arg(long, default_value = "release")]
+    pub profile: String,
+
+    /// Features to enable
+    #[arg(long)]
+    pub features: Vec<String>,
+
+    /// Disable default features
+    #[arg(long)]
+    pub no_default_features: bool,
+
+    /// Path to cbindgen config
+    #[arg(long)]
+    pub cbindgen_config: Option<PathBuf>,
+
+    /// Generate default cbindgen config if none exists
+    #[arg(long)]
+    pub generate_cbindgen_config: bool,
+
+    /// Header mode for cfg-gated items (per-target or merged)
+    #[arg(long, default_value = "per-target")]
+    pub header_mode: String,
+
+    /// Generate a header per language (c, c++, cython) instead of the cbindgen config's
+    #[arg(long, value_delimiter = ',')]
+    pub header_lang: Vec<String>,
+
+    /// Generate idiomatic Zig wrappers from `ghostbind:` doc annotations
+    #[arg(long)]
+    pub zig_wrappers: bool,
+
+    /// Generate a C++ header with a `unique_ptr` for each opaque handle and its `*_free` function
+    #[arg(long)]
+    pub cxx_wrappers: bool,
+
+    /// Compile-check the generated headers with a C compiler (and zig translate-c)
+    #[arg(long)]
+    pub check_header: bool,
+
+    /// Compare header declarations with exported symbols (off, warn or error)
+    #[arg(long, default_value = "warn")]
+    pub symbol_check: String,
+
+    /// Check Rust and C agree on the layout of exported #[repr(C)] types (host builds only)
+    #[arg(long)]
+    pub verify_layout: bool,
+
+    /// Fail if the C ABI differs from the crate's ghostbind.abi.json snapshot
+    #[arg(long, conflicts_with = "abi_update")]
+    pub abi_check: bool,
+
+    /// Regenerate the crate's ghostbind.abi.json snapshot
+    #[arg(long)]
+    pub abi_update: bool,
+
+    /// Manifest formats to write (json, zon, zig)
+    #[arg(long, value_delimiter = ',', default_value = "json")]
+    pub manifest_format: Vec<String>,
+
+    /// Generate a pkg-config file and a CMake package config
+    #[arg(long)]
+    pub pkg_config: bool,
+
+    /// Collect dependency licenses into THIRD_PARTY_NOTICES (fails on ghostbind.toml's deny list)
+    #[arg(long)]
+    pub licenses: bool,
+
+    /// Write an SBOM for each artifact (cyclonedx or spdx)
+    #[arg(long)]
+    pub sbom: Option<String>,
+
+    /// Build with --locked, a fixed SOURCE_DATE_EPOCH, remapped paths and normalized archives
+    #[arg(long)]
+    pub reproducible: bool,
+}
+
+/// Runs the command line and returns the process exit code.
+pub fn main() -> i32 {
+    let cli = Cli::parse();
+    let out = Output { format: cli.message_format };
+
+    match run_command(cli.command, out) {
+        Ok(()) => 0,
+        Err(error) => {
+            out.error(&error);
+            exit_code(&error)
+        }
+    }
+}
+
 pub fn run_cli() -> Result<()> {
     let cli = Cli::parse();
+    run_command(cli.command, Output { format: cli.message_format })
+}
 
-    match cli.command {
-        Commands::Build {
+/// The exit code for a failed command: the typed error's, or `EXIT_FAILURE`.
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    Error::find(error).map(Error::exit_code).unwrap_or(EXIT_FAILURE)
+}
+
+fn run_command(command: Commands, out: Output) -> Result<()> {
+    match command {
+        Commands::Build(args) => build_command(args, out),
+        Commands::Headers {
             manifest_path,
-            zig_target,
-            rust_target,
-            profile,
+            target,
+            cbindgen_config,
+            header_mode,
+            header_lang,
+        } => headers_command(manifest_path, target, cbindgen_config, header_mode, header_lang, out),
+        Commands::CheckHeader { manifest } => check_header_command(manifest, out),
+        Commands::CheckSymbols { manifest } => check_symbols_command(manifest, out),
+        Commands::AbiDiff { old, new } => abi_diff_command(old, new, out),
+        Commands::Test { manifest } => test_command(manifest, out),
+        Commands::ZigModule { manifests, output } => zig_module_command(manifests, output, out),
+        Commands::Inspect { manifest } => inspect_command(manifest, out),
+        Commands::Install { manifest, prefix, destdir } => install_command(manifest, prefix, destdir, out),
+        Commands::Package { manifests, manifest_path, output } => package_command(manifests, manifest_path, output, out),
+        Commands::Licenses {
+            manifest_path,
+            target,
             features,
             no_default_features,
-            cbindgen_config,
-            generate_cbindgen_config,
-        } => {
-            build_command(
-                manifest_path,
-                zig_target,
-                rust_target,
-                profile,
-                features,
-                no_default_features,
-                cbindgen_config,
-                generate_cbindgen_config,
-            )
-        }
-        Commands::Headers {
+            json,
+        } => licenses_command(manifest_path, target, features, no_default_features, json, out),
+        Commands::VerifyRepro {
             manifest_path,
             target,
+            profile,
+            features,
+            no_default_features,
             cbindgen_config,
-        } => headers_command(manifest_path, target, cbindgen_config),
-        Commands::Doctor => doctor_command(),
+        } => verify_repro_command(manifest_path, target, profile, features, no_default_features, cbindgen_config, out),
+        Commands::Lint { manifest_path, json } => lint_command(manifest_path, json, out),
+        Commands::Watch { build, exec, debounce } => watch_command(build, exec, debounce, out),
+        Commands::CbindgenConfig { manifest_path, lang, force, diff } => {
+            cbindgen_config_command(manifest_path, lang, force, diff, out)
+        }
+        Commands::Init { path, name, lib, zig, crate_dir } => init_command(path, name, lib, zig, crate_dir, out),
+        Commands::Doctor => doctor_command(out),
     }
 }
 
-fn build_command(
-    manifest_path: PathBuf,
-    zig_target: Option<String>,
-    rust_target_override: Option<String>,
-    profile: String,
-    features: Vec<String>,
-    no_default_features: bool,
-    cbindgen_config: Option<PathBuf>,
-    generate_cbindgen_config: bool,
-) -> Result<()> {
-    // Parse build profile
-    let build_profile = match profile.as_str() {
-        "debug" => BuildProfile::Debug,
-        "release" => BuildProfile::Release,
-        _ => return Err(anyhow::anyhow!("Invalid profile: {}. Use 'debug' or 'release'", profile)),
-    };
+fn build_command(args: BuildArgs, out: Output) -> Result<()> {
+    let outcome = build_pipeline(args)?
+        .on_event(move |event| out.event(event))
+        .run()?;
 
-    // Determine the Rust target
-    let rust_target = if let Some(override_target) = rust_target_override {
-        override_target
-    } else if let Some(zig_target) = zig_target {
-        let target_mapping = TargetMapping::new();
-        target_mapping.map_target_or_default(&zig_target)
-    } else {
-        // Use host target
-        get_host_target()?
-    };
+    // Output the manifest path for tooling
+    say!(out, "\nManifest path: {}", outcome.manifest_path.display());
+
+    Ok(())
+}
 
-    println!("Building crate with target: {}", rust_target);
+fn build_pipeline(args: BuildArgs) -> Result<Pipeline> {
+    let BuildArgs {
+        manifest_path,
+        zig_target,
+        rust_target,
+        profile,
+        features,
+        no_default_features,
+        cbindgen_config,
+        generate_cbindgen_config,
+        header_mode,
+        header_lang,
+        zig_wrappers,
+        cxx_wrappers,
+        check_header,
+        symbol_check,
+        verify_layout,
+        abi_check,
+        abi_update,
+        manifest_format,
+        pkg_config,
+        licenses,
+        sbom,
+        reproducible,
+    } = args;
 
-    // Create Cargo builder
-    let mut cargo_builder = CargoBuilder::new(&manifest_path)
-        .profile(build_profile.clone())
+    let manifest_formats = manifest_format.iter()
+        .map(|format| parse_manifest_format(format))
+        .collect::<Result<Vec<_>>>()?;
+
+    let header_languages = header_lang.iter()
+        .map(|language| parse_header_language(language))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Pipeline::new(&manifest_path)
+        .zig_target(zig_target)
+        .rust_target(rust_target)
+        .profile(parse_profile(&profile)?)
         .features(features)
-        .no_default_features(no_default_features);
+        .no_default_features(no_default_features)
+        .cbindgen_config(cbindgen_config)
+        .generate_cbindgen_config(generate_cbindgen_config)
+        .header_mode(parse_header_mode(&header_mode)?)
+        .header_languages(header_languages)
+        .zig_wrappers(zig_wrappers)
+        .cxx_wrappers(cxx_wrappers)
+        .check_header(check_header)
+        .symbol_check(parse_symbol_check_mode(&symbol_check)?)
+        .verify_layout(verify_layout)
+        .abi_check(abi_check)
+        .abi_update(abi_update)
+        .manifest_formats(manifest_formats)
+        .pkg_config(pkg_config)
+        .licenses(licenses)
+        .sbom(sbom.as_deref().map(parse_sbom_format).transpose()?)
+        .reproducible(reproducible))
+}
+
+fn watch_command(args: BuildArgs, exec: Option<String>, debounce: u64, out: Output) -> Result<()> {
+    let crate_info = CargoBuilder::new(&args.manifest_path).get_metadata()
+        .context("Failed to get crate metadata")?;
+    let cbindgen_config = args.cbindgen_config.clone();
+    let paths = watched_paths(&crate_info, cbindgen_config.as_deref())?;
 
-    let is_cross_compile = rust_target != get_host_target()?;
-    if is_cross_compile {
-        cargo_builder = cargo_builder.target(rust_target.clone());
+    // Only diagnostics in between the summaries, unless a tool is listening
+    let pipeline = build_pipeline(args)?
+        .on_event(move |event| {
+            if out.is_json() || matches!(event, Event::Warning(_) | Event::Error(_) | Event::Note(_)) {
+                out.event(event);
+            }
+        });
+    let mut watcher = Watcher::new(paths).debounce(Duration::from_millis(debounce));
+    let mut fingerprint = None;
+
+    say!(out, "Watching {} for changes (press Ctrl-C to stop)", crate_info.name);
+    loop {
+        let started = Instant::now();
+        match pipeline.run() {
+            Ok(outcome) => {
+                say!(out, "✓ Built {} for {} in {:.1}s ({} warning(s))",
+                    crate_info.name,
+                    outcome.rust_target,
+                    started.elapsed().as_secs_f64(),
+                    outcome.warnings.len()
+                );
+
+                // A file that can't be read now is retried after the next change
+                match output_fingerprint(&outcome) {
+                    Ok(current) => {
+                        if fingerprint.as_ref() == Some(&current) {
+                            say!(out, "  Artifacts and headers unchanged");
+                        } else if let Some(exec) = &exec {
+                            run_exec(exec, out);
+                        }
+                        fingerprint = Some(current);
+                    }
+                    Err(error) => {
+                        out.error(&error);
+                        fingerprint = None;
+                    }
+                }
+            }
+            Err(error) => out.error(&error.into()),
+        }
+
+        // Pick up modules the change added or removed
+        if let Ok(paths) = watched_paths(&crate_info, cbindgen_config.as_deref()) {
+            watcher.set_paths(paths);
+        }
+        let paths = watcher.wait_for_change();
+        out.event(&Event::ChangesDetected { paths });
+    }
+}
+
+/// Runs `--exec` through the shell. Its output goes to stderr when stdout carries JSON.
+fn run_exec(exec: &str, out: Output) {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(exec);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(exec);
+        cmd
+    };
+    if out.is_json() {
+        cmd.stdout(std::io::stderr());
+    }
+
+    say!(out, "Running {}", exec);
+    match cmd.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => out.event(&Event::Error(format!("`{}` failed with {}", exec, status))),
+        Err(error) => out.event(&Event::Error(format!("Failed to run `{}`: {}", exec, error))),
     }
+}
+
+fn headers_command(
+    manifest_path: PathBuf,
+    target: Option<String>,
+    cbindgen_config: Option<PathBuf>,
+    header_mode: String,
+    header_lang: Vec<String>,
+    out: Output,
+) -> Result<()> {
+    let header_mode = parse_header_mode(&header_mode)?;
+    let languages = header_lang.iter()
+        .map(|language| parse_header_language(language))
+        .collect::<Result<Vec<_>>>()?;
 
     // Get crate metadata
+    let cargo_builder = CargoBuilder::new(&manifest_path);
     let crate_info = cargo_builder.get_metadata()
         .context("Failed to get crate metadata")?;
 
-    println!("Found crate: {} with {} targets", crate_info.name, crate_info.targets.len());
+    // Generate headers
+    let header_generator = HeaderGenerator::new(cbindgen_config)
+        .mode(header_mode)
+        .languages(languages);
+    let headers = header_generator.generate_headers(&crate_info, target.as_deref())
+        .context("Failed to generate headers")?;
 
-    // Generate default cbindgen config if requested
-    if generate_cbindgen_config {
-        let header_generator = HeaderGenerator::new(None);
-        header_generator.create_default_cbindgen_config(&crate_info.manifest_dir)?;
+    say!(out, "Generated {} headers:", headers.len());
+    for header in &headers {
+        say!(out, "  {} ({})", header.header_path.display(), header.language.tag());
+        if out.is_json() {
+            out.event(&Event::HeaderGenerated { path: header.header_path.clone() });
+        }
     }
 
-    // Build the crate
-    cargo_builder.build()
-        .context("Failed to build crate")?;
+    Ok(())
+}
+
+fn check_header_command(manifest_path: PathBuf, out: Output) -> Result<()> {
+    let manifest_generator = ManifestGenerator::new();
+    let manifest = manifest_generator.read_manifest(&manifest_path)?;
+
+    check_headers(&manifest.crate_name, &manifest.c_headers()?, Some(&manifest.rustc_target), &|event| out.event(&event))?;
+    Ok(())
+}
+
+fn check_symbols_command(manifest_path: PathBuf, out: Output) -> Result<()> {
+    let manifest_generator = ManifestGenerator::new();
+    let manifest = manifest_generator.read_manifest(&manifest_path)?;
+
+    check_symbols(&library_name(&manifest.artifact), &manifest.artifact, &manifest.c_headers()?, SymbolCheckMode::Error, &|event| out.event(&event))?;
+    Ok(())
+}
 
-    println!("Crate built successfully");
+fn abi_diff_command(old_path: PathBuf, new_path: PathBuf, out: Output) -> Result<()> {
+    let old = AbiSnapshot::load(&old_path)?;
+    let new = AbiSnapshot::load(&new_path)?;
 
-    // Discover artifacts
-    let artifact_discovery = ArtifactDiscovery::new(
-        &crate_info.target_directory,
-        Some(rust_target.clone()),
-        build_profile,
+    if old.rustc_target != new.rustc_target {
+        say!(out, "warning: comparing builds for different targets ({} vs {})", old.rustc_target, new.rustc_target);
+    }
+
+    let diff = old.diff(&new);
+    for change in &diff.changes {
+        say!(out, "{}: {}: {}", change.kind.as_str(), change.item, change.description);
+    }
+
+    say!(out,
+        "\n{} breaking, {} additive, {} compatible change(s)",
+        diff.count(ChangeKind::Breaking),
+        diff.count(ChangeKind::Additive),
+        diff.count(ChangeKind::Compatible)
     );
+    if out.is_json() {
+        out.report("abi-diff", &json!({
+            "old_target": old.rustc_target,
+            "new_target": new.rustc_target,
+            "breaking": diff.count(ChangeKind::Breaking),
+            "additive": diff.count(ChangeKind::Additive),
+            "compatible": diff.count(ChangeKind::Compatible),
+            "changes": diff.changes,
+        }))?;
+    }
+
+    if diff.is_breaking() {
+        return Err(Error::CheckFailed(format!("The C ABI of {} has breaking changes", new.crate_name)).into());
+    }
+
+    Ok(())
+}
+
+fn test_command(manifest_path: PathBuf, out: Output) -> Result<()> {
+    let manifest_generator = ManifestGenerator::new();
+    let manifest = manifest_generator.read_manifest(&manifest_path)?;
+
+    let smoke_tester = SmokeTester::new();
+    let report = smoke_tester.run(&manifest)
+        .context("Smoke test failed")?;
+
+    out.event(&Event::CheckPassed(format!(
+        "Linked {} function(s) and called {} zero-argument function(s) from {}",
+        report.linked.len(),
+        report.called.len(),
+        manifest.crate_name
+    )));
+    Ok(())
+}
+
+fn zig_module_command(manifest_paths: Vec<PathBuf>, output: Option<PathBuf>, out: Output) -> Result<()> {
+    let manifest_generator = ManifestGenerator::new();
+    let manifests = manifest_paths.iter()
+        .map(|path| manifest_generator.read_manifest(path))
+        .collect::<Result<Vec<_>>>()?;
+
+    let module = ZigModuleGenerator::new()
+        .output_dir(output)
+        .generate(&manifests)
+        .context("Failed to generate Zig module")?;
 
-    let artifacts = artifact_discovery.discover_artifacts(&crate_info)
-        .context("Failed to discover artifacts")?;
+    out.event(&Event::Generated { what: "Zig module".to_string(), path: module.build_zig.clone() });
+    out.event(&Event::Generated { what: "Zig package manifest".to_string(), path: module.build_zig_zon.clone() });
+    say!(out, "\nAdd to the dependencies in your build.zig.zon:\n    {}", module.dependency_entry());
+    say!(out, "\nThen in your build.zig:");
+    say!(out, "    const {0} = @import(\"{0}\");", module.package_name);
+    say!(out, "    {}.link(exe);", module.package_name);
+    Ok(())
+}
 
-    if artifacts.is_empty() {
-        return Err(anyhow::anyhow!("No library artifacts found. Make sure your crate produces a staticlib or cdylib"));
+fn inspect_command(manifest_path: PathBuf, out: Output) -> Result<()> {
+    let manifest = ManifestGenerator::new().read_manifest(&manifest_path)?;
+    if out.is_json() {
+        out.report("inspect", &manifest)?;
     }
 
-    println!("Found {} artifacts", artifacts.len());
+    let version = if manifest.crate_version.is_empty() { String::new() } else { format!(" {}", manifest.crate_version) };
+    say!(out, "{}{} ({}, {})", manifest.crate_name, version, manifest.kind, manifest.rustc_target);
+    say!(out, "  artifact:   {}", manifest.artifact.display());
+    if manifest.header_files.is_empty() {
+        for header in &manifest.headers {
+            say!(out, "  header:     {}", header.display());
+        }
+    }
+    for header in &manifest.header_files {
+        say!(out, "  header:     {} ({})", header.path.display(), header.language);
+    }
+    if let Some(wrappers) = &manifest.cxx_wrappers {
+        say!(out, "  c++ raii:   {}", wrappers.display());
+    }
+    if !manifest.link_libs.is_empty() {
+        say!(out, "  link libs:  {}", manifest.link_libs.join(", "));
+    }
+    if let Some(sbom) = &manifest.sbom {
+        say!(out, "  sbom:       {}", sbom.display());
+    }
 
-    // Cache artifacts
-    artifact_discovery.cache_artifacts(&artifacts)
-        .context("Failed to cache artifacts")?;
+    let Some(provenance) = &manifest.provenance else {
+        say!(out, "\nNo provenance recorded in this manifest");
+        return Ok(());
+    };
 
-    // Generate headers
-    let header_generator = HeaderGenerator::new(cbindgen_config);
-    let headers = header_generator.generate_headers(&crate_info, Some(&rust_target))
-        .context("Failed to generate headers")?;
+    let unknown = "unknown";
+    say!(out, "\nProvenance:");
+    say!(out, "  built:      {} by ghostbind {}", provenance.timestamp, provenance.ghostbind_version);
+    match (&provenance.git_commit, provenance.git_dirty) {
+        (Some(commit), Some(true)) => say!(out, "  git:        {} (dirty)", commit),
+        (Some(commit), _) => say!(out, "  git:        {}", commit),
+        (None, _) => say!(out, "  git:        not a git checkout"),
+    }
+    say!(out, "  rustc:      {}", provenance.rustc_version.as_deref().unwrap_or(unknown));
+    say!(out, "  cargo:      {}", provenance.cargo_version.as_deref().unwrap_or(unknown));
+    say!(out, "  cbindgen:   {}", provenance.cbindgen_version.as_deref().unwrap_or(unknown));
+
+    let settings: Vec<String> = provenance.profile_settings.iter()
+        .map(|(key, value)| format!("{} = {}", key, value))
+        .collect();
+    if settings.is_empty() {
+        say!(out, "  profile:    {}", provenance.profile);
+    } else {
+        say!(out, "  profile:    {} ({})", provenance.profile, settings.join(", "));
+    }
 
-    // Generate manifest for the first (primary) artifact
-    let primary_artifact = &artifacts[0];
+    let features = if provenance.features.is_empty() { "(none)".to_string() } else { provenance.features.join(", ") };
+    let defaults = if provenance.no_default_features { " [no default features]" } else { "" };
+    say!(out, "  features:   {}{}", features, defaults);
+    say!(out, "  RUSTFLAGS:  {}", provenance.rustflags.as_deref().unwrap_or("(none)"));
+    say!(out, "  command:    {}", provenance.cargo_command);
+
+    Ok(())
+}
+
+fn install_command(manifest_path: PathBuf, prefix: PathBuf, destdir: Option<PathBuf>, out: Output) -> Result<()> {
     let manifest_generator = ManifestGenerator::new();
-    let manifest = manifest_generator.generate_manifest(
-        &crate_info.name,
-        primary_artifact,
-        &headers,
-        &rust_target,
-    ).context("Failed to generate manifest")?;
-
-    // Write manifest
-    let manifest_path = manifest_generator.write_manifest(
-        &manifest,
-        Some(&rust_target),
-    ).context("Failed to write manifest")?;
+    let manifest = manifest_generator.read_manifest(&manifest_path)?;
 
-    // Output the manifest path for tooling
-    println!("\nManifest path: {}", manifest_path.display());
+    let destdir = destdir.or_else(|| {
+        std::env::var_os("DESTDIR")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+    });
+
+    let installer = Installer::new(&prefix).destdir(destdir);
+    let report = installer.install(&manifest)
+        .with_context(|| format!("Failed to install {}", manifest.crate_name))?;
 
+    for file in &report.files {
+        out.event(&Event::Installed { path: file.clone() });
+    }
+    say!(out, "✓ Installed {} file(s) for {} into {}", report.files.len(), manifest.crate_name, prefix.display());
     Ok(())
 }
 
-fn headers_command(
+fn package_command(manifest_paths: Vec<PathBuf>, cargo_manifest: PathBuf, output: PathBuf, out: Output) -> Result<()> {
+    let manifest_generator = ManifestGenerator::new();
+    let license_dir = cargo_manifest.parent()
+        .map(|dir| if dir.as_os_str().is_empty() { Path::new(".") } else { dir })
+        .filter(|dir| dir.is_dir())
+        .map(Path::to_path_buf);
+    let packager = Packager::new()
+        .output_dir(output)
+        .license_dir(license_dir);
+
+    for manifest_path in &manifest_paths {
+        let manifest = manifest_generator.read_manifest(manifest_path)?;
+        let report = packager.package(&manifest)
+            .with_context(|| format!("Failed to package {}", manifest.crate_name))?;
+
+        for warning in &report.warnings {
+            out.event(&Event::Warning(warning.clone()));
+        }
+        out.event(&Event::PackageWritten { archive: report.archive.clone(), zig_hash: report.zig_hash.clone() });
+        say!(out, "  zig fetch hash: {}", report.zig_hash);
+        say!(out, "  .{} = .{{ .url = \"<url>/{}\", .hash = \"{}\" }},",
+            manifest.crate_name.replace('-', "_"),
+            report.archive.file_name().unwrap_or_default().to_string_lossy(),
+            report.zig_hash
+        );
+    }
+    Ok(())
+}
+
+fn licenses_command(
     manifest_path: PathBuf,
     target: Option<String>,
+    features: Vec<String>,
+    no_default_features: bool,
+    json: bool,
+    out: Output,
+) -> Result<()> {
+    let cargo_builder = CargoBuilder::new(&manifest_path)
+        .features(features)
+        .no_default_features(no_default_features);
+    let crate_info = cargo_builder.get_metadata()
+        .context("Failed to get crate metadata")?;
+    let target = match target {
+        Some(target) => target,
+        None => get_host_target()?,
+    };
+
+    let metadata = cargo_builder.resolve_for_platform(&target)?;
+    let report = collect_licenses(&crate_info, &metadata, &target)?;
+
+    if json || out.is_json() {
+        out.report("licenses", &report)?;
+    } else {
+        for package in &report.packages {
+            say!(out, "{} {}: {}", package.name, package.version, package.license.as_deref().unwrap_or("(not specified)"));
+        }
+    }
+    check_licenses(&report, &|event| out.event(&event))?;
+
+    if !json {
+        say!(out, "✓ Collected licenses of {} package(s) into {}", report.packages.len(), report.notices.display());
+    }
+    Ok(())
+}
+
+fn verify_repro_command(
+    manifest_path: PathBuf,
+    target: Option<String>,
+    profile: String,
+    features: Vec<String>,
+    no_default_features: bool,
     cbindgen_config: Option<PathBuf>,
+    out: Output,
 ) -> Result<()> {
-    // Get crate metadata
+    let build_profile = parse_profile(&profile)?;
+    let cargo_builder = CargoBuilder::new(&manifest_path)
+        .features(features.clone())
+        .no_default_features(no_default_features);
+    let crate_info = cargo_builder.get_metadata()
+        .context("Failed to get crate metadata")?;
+    let host_target = get_host_target()?;
+    let target = target.unwrap_or_else(|| host_target.clone());
+
+    say!(out, "Building {} twice for {} in .ghostbind/repro...", crate_info.name, target);
+    let report = ReproVerifier::new(&manifest_path, target.clone())
+        .cross_compile(target != host_target)
+        .profile(build_profile)
+        .features(features)
+        .no_default_features(no_default_features)
+        .cbindgen_config(cbindgen_config)
+        .verify(&crate_info)?;
+
+    say!(out);
+    for file in &report.files {
+        if file.matches() {
+            say!(out, "  ok      {}  {}", file.first, file.name);
+        } else {
+            say!(out, "  DIFFERS {}", file.name);
+            say!(out, "          first:  {}", file.first);
+            say!(out, "          second: {}", file.second);
+        }
+    }
+
+    if out.is_json() {
+        out.report("verify-repro", &report)?;
+    }
+
+    if !report.is_reproducible() {
+        return Err(Error::CheckFailed(format!("Build of {} for {} is not reproducible", crate_info.name, target)).into());
+    }
+
+    say!(out, "\nBuild of {} for {} is reproducible", crate_info.name, target);
+    Ok(())
+}
+
+fn lint_command(manifest_path: PathBuf, json: bool, out: Output) -> Result<()> {
     let cargo_builder = CargoBuilder::new(&manifest_path);
     let crate_info = cargo_builder.get_metadata()
         .context("Failed to get crate metadata")?;
 
-    // Generate headers
-    let header_generator = HeaderGenerator::new(cbindgen_config);
-    let headers = header_generator.generate_headers(&crate_info, target.as_deref())
-        .context("Failed to generate headers")?;
+    let linter = FfiLinter::new();
+    let diagnostics = linter.lint_files(&crate_info.lib_source_files()?)
+        .context("Failed to lint crate sources")?;
 
-    println!("Generated {} headers:", headers.len());
-    for header in &headers {
-        println!("  {}", header.header_path.display());
+    if json || out.is_json() {
+        out.report("lint", &diagnostics)?;
+    } else {
+        for diagnostic in &diagnostics {
+            say!(out,
+                "{}:{}:{}: warning[{}]: {} (in `{}`)",
+                diagnostic.file.display(),
+                diagnostic.line,
+                diagnostic.column,
+                diagnostic.rule.as_str(),
+                diagnostic.message,
+                diagnostic.function
+            );
+        }
     }
 
+    if !diagnostics.is_empty() {
+        return Err(Error::CheckFailed(format!("FFI lint found {} problem(s) in {}", diagnostics.len(), crate_info.name)).into());
+    }
+
+    if !json {
+        say!(out, "✓ No FFI safety problems found in {}", crate_info.name);
+    }
     Ok(())
 }
 
-fn doctor_command() -> Result<()> {
-    println!("Ghostbind Doctor - Checking system requirements...\n");
+fn parse_profile(profile: &str) -> Result<BuildProfile> {
+    match profile {
+        "debug" => Ok(BuildProfile::Debug),
+        "release" => Ok(BuildProfile::Release),
+        _ => Err(Error::InvalidConfig(format!("Invalid profile: {}. Use 'debug' or 'release'", profile)).into()),
+    }
+}
+
+fn parse_symbol_check_mode(mode: &str) -> Result<SymbolCheckMode> {
+    match mode {
+        "off" => Ok(SymbolCheckMode::Off),
+        "warn" => Ok(SymbolCheckMode::Warn),
+        "error" => Ok(SymbolCheckMode::Error),
+        _ => Err(Error::InvalidConfig(format!("Invalid symbol check mode: {}. Use 'off', 'warn' or 'error'", mode)).into()),
+    }
+}
+
+fn parse_manifest_format(format: &str) -> Result<ManifestFormat> {
+    match format.trim() {
+        "json" => Ok(ManifestFormat::Json),
+        "zon" => Ok(ManifestFormat::Zon),
+        "zig" => Ok(ManifestFormat::Zig),
+        _ => Err(Error::InvalidConfig(format!("Invalid manifest format: {}. Use 'json', 'zon' or 'zig'", format)).into()),
+    }
+}
+
+fn parse_sbom_format(format: &str) -> Result<SbomFormat> {
+    match format {
+        "cyclonedx" => Ok(SbomFormat::CycloneDx),
+        "spdx" => Ok(SbomFormat::Spdx),
+        _ => Err(Error::InvalidConfig(format!("Invalid SBOM format: {}. Use 'cyclonedx' or 'spdx'", format)).into()),
+    }
+}
+
+fn parse_header_language(language: &str) -> Result<HeaderLanguage> {
+    match language {
+        "c" => Ok(HeaderLanguage::C),
+        "c++" | "cxx" | "cpp" => Ok(HeaderLanguage::Cxx),
+        "cython" => Ok(HeaderLanguage::Cython),
+        _ => Err(Error::InvalidConfig(format!("Invalid language: {}. Use 'c', 'c++' or 'cython'", language)).into()),
+    }
+}
+
+fn parse_header_mode(header_mode: &str) -> Result<HeaderMode> {
+    match header_mode {
+        "per-target" => Ok(HeaderMode::PerTarget),
+        "merged" => Ok(HeaderMode::Merged),
+        _ => Err(Error::InvalidConfig(format!("Invalid header mode: {}. Use 'per-target' or 'merged'", header_mode)).into()),
+    }
+}
+
+fn cbindgen_config_command(manifest_path: PathBuf, lang: String, force: bool, diff: bool, out: Output) -> Result<()> {
+    let crate_info = CargoBuilder::new(&manifest_path).get_metadata()
+        .context("Failed to get crate metadata")?;
+    let generator = HeaderGenerator::new(None).language(parse_header_language(&lang)?);
+
+    if diff {
+        match generator.cbindgen_config_diff(&crate_info.manifest_dir, &crate_info.name)? {
+            Some(diff) => out.event(&Event::Note(diff.trim_end().to_string())),
+            None => out.event(&Event::CheckPassed("cbindgen.toml matches the template".to_string())),
+        }
+        return Ok(());
+    }
+
+    let report = generator.create_default_cbindgen_config(&crate_info.manifest_dir, &crate_info.name, force)?;
+    match report.status {
+        ConfigStatus::Created => out.event(&Event::Generated { what: "cbindgen config".to_string(), path: report.path }),
+        ConfigStatus::Unchanged => out.event(&Event::CheckPassed(format!("{} matches the template", report.path.display()))),
+        ConfigStatus::Overwritten => {
+            out.event(&Event::Generated { what: "cbindgen config".to_string(), path: report.path });
+            if let Some(diff) = &report.diff {
+                say!(out, "{}", diff.trim_end());
+            }
+        }
+        ConfigStatus::Kept => {
+            return Err(Error::InvalidConfig(format!(
+                "{} differs from the template; --diff shows the changes, --force replaces it",
+                report.path.display()
+            )).into());
+        }
+    }
+    Ok(())
+}
+
+fn init_command(
+    path: PathBuf,
+    name: Option<String>,
+    lib: bool,
+    zig: bool,
+    crate_dir: Option<PathBuf>,
+    out: Output,
+) -> Result<()> {
+    // Neither flag means both sides of the bridge
+    let (lib, zig) = if lib || zig { (lib, zig) } else { (true, true) };
+    let report = Scaffolder::new(&path)
+        .name(name)
+        .lib(lib)
+        .zig(zig)
+        .crate_dir(crate_dir)
+        .generate()?;
+
+    for file in &report.files {
+        out.event(&Event::Generated { what: "scaffold".to_string(), path: file.clone() });
+    }
+    say!(out, "\n✓ Created {}", report.name);
+    match (&report.crate_dir, &report.zig_dir) {
+        (_, Some(zig_dir)) if zig_dir == Path::new(".") => say!(out, "  zig build run"),
+        (_, Some(zig_dir)) => say!(out, "  cd {} && zig build run", zig_dir.display()),
+        (Some(crate_dir), None) => say!(out, "  ghostbind build --manifest-path {}", crate_dir.join("Cargo.toml").display()),
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+fn doctor_command(out: Output) -> Result<()> {
+    say!(out, "Ghostbind Doctor - Checking system requirements...\n");
 
     // Check Rust/Cargo
-    check_command_available("cargo", "Rust toolchain")?;
-    check_command_available("rustc", "Rust compiler")?;
+    check_command_available("cargo", "Rust toolchain", out)?;
+    check_command_available("rustc", "Rust compiler", out)?;
 
     // Check cbindgen
-    match which::which("cbindgen") {
-        Ok(path) => println!("✓ cbindgen found at: {}", path.display()),
-        Err(_) => {
-            println!("✗ cbindgen not found");
-            println!("  Install with: cargo install cbindgen");
+    if cfg!(feature = "embedded-cbindgen") {
+        out.event(&Event::CheckPassed("cbindgen embedded (built with the embedded-cbindgen feature)".to_string()));
+    } else {
+        match which::which("cbindgen") {
+            Ok(path) => out.event(&Event::CheckPassed(format!("cbindgen found at: {}", path.display()))),
+            Err(_) => {
+                out.event(&Event::Warning("cbindgen not found".to_string()));
+                say!(out, "  Install with: cargo install cbindgen");
+                say!(out, "  Or build ghostbind with: cargo install ghostbind --features embedded-cbindgen");
+            }
         }
     }
 
     // Check common system tools
     if cfg!(unix) {
-        check_command_available("cc", "C compiler (optional, for testing generated headers)")?;
+        check_command_available("cc", "C compiler (optional, for testing generated headers)", out)?;
     }
 
-    println!("\nTarget mapping support:");
+    say!(out, "\nTarget mapping support:");
     let target_mapping = TargetMapping::new();
     let supported_targets = target_mapping.supported_targets();
-    println!("  Supported Zig targets: {}", supported_targets.len());
+    say!(out, "  Supported Zig targets: {}", supported_targets.len());
     for target in supported_targets.iter().take(5) {
         if let Some(rust_target) = target_mapping.map_target(target) {
-            println!("    {} -> {}", target, rust_target);
+            say!(out, "    {} -> {}", target, rust_target);
         }
     }
     if supported_targets.len() > 5 {
-        println!("    ... and {} more", supported_targets.len() - 5);
+        say!(out, "    ... and {} more", supported_targets.len() - 5);
     }
 
-    println!("\n✓ Ghostbind doctor check complete");
+    say!(out);
+    out.event(&Event::CheckPassed("Ghostbind doctor check complete".to_string()));
 
     Ok(())
 }
 
-fn check_command_available(command: &str, description: &str) -> Result<()> {
+fn check_command_available(command: &str, description: &str, out: Output) -> Result<()> {
     match which::which(command) {
         Ok(path) => {
-            println!("✓ {} found at: {}", description, path.display());
+            out.event(&Event::CheckPassed(format!("{} found at: {}", description, path.display())));
             Ok(())
         }
         Err(_) => {
-            println!("✗ {} not found ({})", description, command);
-            Err(anyhow::anyhow!("{} is required but not found in PATH", description))
+            out.event(&Event::Error(format!("{} not found ({})", description, command)));
+            Err(Error::ToolMissing(format!("{} is required but not found in PATH", description)).into())
         }
     }
 }
-
-fn get_host_target() -> Result<String> {
-    // This is a simplified version - in a real implementation,
-    // you might want to detect the actual host target more accurately
-    if cfg!(target_os = "linux") && cfg!(target_arch = "x86_64") {
-        Ok("x86_64-unknown-linux-gnu".to_string())
-    } else if cfg!(target_os = "macos") && cfg!(target_arch = "x86_64") {
-        Ok("x86_64-apple-darwin".to_string())
-    } else if cfg!(target_os = "macos") && cfg!(target_arch = "aarch64") {
-        Ok("aarch64-apple-darwin".to_string())
-    } else if cfg!(target_os = "windows") && cfg!(target_arch = "x86_64") {
-        Ok("x86_64-pc-windows-msvc".to_string())
-    } else {
-        // Fallback - use rustc to get the host target
-        let output = std::process::Command::new("rustc")
-            .args(["--version", "--verbose"])
-            .output()
-            .context("Failed to run rustc to detect host target")?;
-
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        for line in output_str.lines() {
-            if line.starts_with("host: ") {
-                return Ok(line.strip_prefix("host: ").unwrap().to_string());
-            }
-        }
-
-        Err(anyhow::anyhow!("Could not detect host target"))
-    }
-}
\ No newline at end of file
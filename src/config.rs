@@ -0,0 +1,53 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::error::Error;
+
+/// The optional `ghostbind.toml` next to a crate's Cargo.toml.
+pub const CONFIG_FILE: &str = "ghostbind.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GhostbindConfig {
+    pub licenses: LicenseConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LicenseConfig {
+    /// SPDX license identifiers no shipped dependency may be (only) available under
+    pub deny: Vec<String>,
+}
+
+impl GhostbindConfig {
+    /// Reads `ghostbind.toml` from the crate directory, or the defaults when there is none.
+    pub fn load(crate_dir: &Path) -> Result<Self> {
+        let path = crate_dir.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content)
+            .map_err(|e| Error::InvalidConfig(format!("Invalid {}: {}", path.display(), e)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: GhostbindConfig = toml::from_str("[licenses]\ndeny = [\"GPL-3.0-only\"]\n").unwrap();
+        assert_eq!(config.licenses.deny, vec!["GPL-3.0-only"]);
+
+        let empty: GhostbindConfig = toml::from_str("").unwrap();
+        assert!(empty.licenses.deny.is_empty());
+
+        assert!(toml::from_str::<GhostbindConfig>("[licences]\n").is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::{anyhow, Context, Result};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::header_generation::GeneratedHeader;
+use crate::header_parser::{parse_header_file, CHeader, CTypeKind};
+
+/// Generates a C++ header on top of the C header that gives every opaque handle a
+/// `std::unique_ptr` alias whose deleter calls the handle's free function.
+///
+/// A handle is an opaque type `Foo` with a `void foo_free(Foo *)` function; any name
+/// ending in `_free` will do. Declarations under a preprocessor condition are left
+/// out, since the wrapper can't know whether they exist.
+pub struct CxxWrapperGenerator {
+    type_prefix: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedCxxWrapper {
+    pub crate_name: String,
+    pub wrapper_path: PathBuf,
+    /// The C names of the handles that got a `unique_ptr` alias
+    pub handles: Vec<String>,
+}
+
+/// An opaque type and the function that frees it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Handle {
+    type_name: String,
+    free_fn: String,
+}
+
+impl CxxWrapperGenerator {
+    pub fn new() -> Self {
+        Self { type_prefix: None }
+    }
+
+    /// The cbindgen `[export] prefix`, left off the alias names inside the namespace.
+    pub fn type_prefix(mut self, type_prefix: Option<String>) -> Self {
+        self.type_prefix = type_prefix;
+        self
+    }
+
+    /// Writes `<crate>_raii.hpp` next to the C header it includes.
+    pub fn generate(&self, header: &GeneratedHeader) -> Result<GeneratedCxxWrapper> {
+        let header_name = header.header_path.file_name()
+            .ok_or_else(|| anyhow!("Invalid header path: {}", header.header_path.display()))?
+            .to_string_lossy()
+            .to_string();
+
+        let c_header = parse_header_file(&header.header_path)
+            .with_context(|| format!("Failed to parse {}", header.header_path.display()))?;
+        let handles = find_handles(&c_header);
+
+        let source = render_wrapper(&header.crate_name, &header_name, &handles, self.type_prefix.as_deref().unwrap_or_default());
+        let wrapper_path = header.header_path.with_file_name(format!("{}_raii.hpp", header.crate_name));
+        fs::write(&wrapper_path, source)
+            .with_context(|| format!("Failed to write C++ wrappers to {}", wrapper_path.display()))?;
+
+        Ok(GeneratedCxxWrapper {
+            crate_name: header.crate_name.clone(),
+            wrapper_path,
+            handles: handles.into_iter().map(|handle| handle.type_name).collect(),
+        })
+    }
+}
+
+impl Default for CxxWrapperGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Opaque types paired with the first `*_free` function that takes only a pointer to them.
+fn find_handles(header: &CHeader) -> Vec<Handle> {
+    header.types.iter()
+        .filter(|ty| ty.kind == CTypeKind::Opaque && !ty.conditional)
+        .filter_map(|ty| {
+            let pointer = format!("{}*", ty.name);
+            let free_fn = header.functions.iter().find(|function| {
+                !function.conditional
+                    && function.name.ends_with("_free")
+                    && function.return_type == "void"
+                    && function.params.len() == 1
+                    && function.params[0].ty.trim_start_matches("struct ") == pointer
+            })?;
+            Some(Handle { type_name: ty.name.clone(), free_fn: free_fn.name.clone() })
+        })
+        .collect()
+}
+
+fn render_wrapper(crate_name: &str, header_name: &str, handles: &[Handle], prefix: &str) -> String {
+    let namespace = crate_name.replace('-', "_");
+    let guard = format!("{}_RAII_HPP", namespace.to_uppercase());
+
+    let mut out = String::new();
+    let _ = writeln!(out, "/* Generated by ghostbind from {}. Don't modify this manually. */", header_name);
+    let _ = writeln!(out, "#ifndef {}", guard);
+    let _ = writeln!(out, "#define {}\n", guard);
+    let _ = writeln!(out, "#include <memory>\n");
+    // Headers from configs without `cpp_compat` have no `extern "C"` guards of their own
+    let _ = writeln!(out, "extern \"C\" {{");
+    let _ = writeln!(out, "#include \"{}\"", header_name);
+    let _ = writeln!(out, "}}\n");
+    let _ = writeln!(out, "namespace {} {{", namespace);
+
+    for handle in handles {
+        let name = handle.type_name.strip_prefix(prefix)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&handle.type_name);
+        let _ = writeln!(out, "\nstruct {}Deleter {{", name);
+        let _ = writeln!(out, "  void operator()(::{} *ptr) const noexcept {{ ::{}(ptr); }}", handle.type_name, handle.free_fn);
+        let _ = writeln!(out, "}};");
+        let _ = writeln!(out, "using {}Ptr = std::unique_ptr<::{}, {}Deleter>;", name, handle.type_name, name);
+    }
+
+    let _ = writeln!(out, "\n}}  // namespace {}\n", namespace);
+    let _ = writeln!(out, "#endif /* {} */", guard);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header_parser::parse_header;
+
+    const HEADER: &str = r#"
+typedef struct MyCrateParser MyCrateParser;
+typedef struct MyCrateToken MyCrateToken;
+typedef struct MyCrateCache MyCrateCache;
+
+MyCrateParser *parser_new(void);
+void parser_free(MyCrateParser *parser);
+void token_free(MyCrateToken *token, bool recursive);
+#if defined(GHOSTBIND_CFG_UNIX)
+void cache_free(MyCrateCache *cache);
+#endif
+"#;
+
+    #[test]
+    fn test_handles_and_rendering() {
+        let handles = find_handles(&parse_header(HEADER));
+        assert_eq!(handles, vec![Handle { type_name: "MyCrateParser".to_string(), free_fn: "parser_free".to_string() }]);
+
+        let cxx = render_wrapper("my-crate", "my-crate.h", &handles, "MyCrate");
+        assert!(cxx.contains("extern \"C\" {\n#include \"my-crate.h\"\n}"));
+        assert!(cxx.contains("namespace my_crate {"));
+        assert!(cxx.contains("struct ParserDeleter {\n  void operator()(::MyCrateParser *ptr) const noexcept { ::parser_free(ptr); }\n};"));
+        assert!(cxx.contains("using ParserPtr = std::unique_ptr<::MyCrateParser, ParserDeleter>;"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+/// Exit code for failures without a more specific code.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code for invalid arguments or configuration (also used by clap for usage errors).
+pub const EXIT_CONFIG: i32 = 2;
+/// Exit code when cargo fails or produces no library.
+pub const EXIT_CARGO: i32 = 3;
+/// Exit code when a required tool (cbindgen, a C compiler) is missing.
+pub const EXIT_MISSING_TOOL: i32 = 4;
+/// Exit code when a check or verification finds problems.
+pub const EXIT_VERIFICATION: i32 = 5;
+
+/// The failures a [`Pipeline`](crate::Pipeline) run reports.
+///
+/// Steps that fail for other reasons surface as [`Error::Other`] with the full context chain.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An invalid option value or `ghostbind.toml`
+    #[error("{0}")]
+    InvalidConfig(String),
+
+    #[error("cargo failed: {stderr}")]
+    CargoFailed { stderr: String },
+
+    #[error("no library artifacts found for `{crate_name}` in {target_dir}. Make sure the crate produces a staticlib or cdylib")]
+    ArtifactNotFound { crate_name: String, target_dir: PathBuf },
+
+    #[error("cbindgen not found in PATH. Install it with: cargo install cbindgen")]
+    CbindgenMissing,
+
+    /// A tool other than cbindgen, such as a C compiler, isn't installed
+    #[error("{0}")]
+    ToolMissing(String),
+
+    #[error("target `{target}` is not supported: {reason}")]
+    TargetUnsupported { target: String, reason: String },
+
+    /// A requested check (headers, layouts, symbols, ABI, licenses) found problems
+    #[error("{0}")]
+    CheckFailed(String),
+
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl Error {
+    /// The process exit code for this error, one of the `EXIT_*` constants.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidConfig(_) | Error::TargetUnsupported { .. } => EXIT_CONFIG,
+            Error::CargoFailed { .. } | Error::ArtifactNotFound { .. } => EXIT_CARGO,
+            Error::CbindgenMissing | Error::ToolMissing(_) => EXIT_MISSING_TOOL,
+            Error::CheckFailed(_) => EXIT_VERIFICATION,
+            Error::Other(_) => EXIT_FAILURE,
+        }
+    }
+
+    /// A stable name for the kind of error, for machine-readable output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::InvalidConfig(_) => "invalid-config",
+            Error::CargoFailed { .. } => "cargo-failed",
+            Error::ArtifactNotFound { .. } => "artifact-not-found",
+            Error::CbindgenMissing => "cbindgen-missing",
+            Error::ToolMissing(_) => "tool-missing",
+            Error::TargetUnsupported { .. } => "target-unsupported",
+            Error::CheckFailed(_) => "check-failed",
+            Error::Other(_) => "other",
+        }
+    }
+
+    /// The typed error anywhere in an `anyhow` chain, if there is one.
+    pub fn find(error: &anyhow::Error) -> Option<&Error> {
+        error.chain().find_map(|cause| cause.downcast_ref::<Error>())
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    /// Recovers the typed error when a step raised one, even under added context.
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<Error>() {
+            Ok(error) => error,
+            Err(error) => Error::Other(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_from_anyhow_keeps_typed_errors() {
+        let typed: anyhow::Result<()> = Err(Error::CbindgenMissing.into());
+        let error: Error = typed.context("Failed to generate headers").unwrap_err().into();
+        assert!(matches!(error, Error::CbindgenMissing));
+
+        let untyped = anyhow::anyhow!("something else");
+        assert!(matches!(Error::from(untyped), Error::Other(_)));
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let cargo: anyhow::Result<()> = Err(Error::CargoFailed { stderr: String::new() }.into());
+        let error = cargo.context("Failed to build crate").unwrap_err();
+        assert_eq!(Error::find(&error).map(Error::exit_code), Some(EXIT_CARGO));
+
+        assert_eq!(Error::CheckFailed(String::new()).exit_code(), EXIT_VERIFICATION);
+        assert_eq!(Error::ToolMissing(String::new()).kind(), "tool-missing");
+        assert!(Error::find(&anyhow::anyhow!("untyped")).is_none());
+    }
+}
@@ -0,0 +1,243 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::Error;
+use crate::header_parser::{parse_header_file, CHeader};
+use crate::target_mapping::{get_host_target, TargetMapping};
+
+/// Compiles generated headers with a C compiler (and `zig translate-c` when available)
+/// to catch headers that cbindgen produced but that don't actually compile.
+pub struct HeaderChecker {
+    cache_dir: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderDiagnostic {
+    pub tool: String,
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub severity: String,
+    pub message: String,
+    /// The header item (named after the Rust item) the diagnostic points into
+    pub item: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HeaderCheckReport {
+    pub tools: Vec<String>,
+    pub diagnostics: Vec<HeaderDiagnostic>,
+}
+
+impl HeaderCheckReport {
+    pub fn error_count(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity != "warning").count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+}
+
+impl HeaderChecker {
+    pub fn new() -> Self {
+        Self {
+            cache_dir: PathBuf::from(".ghostbind/cache"),
+        }
+    }
+
+    pub fn check_headers(
+        &self,
+        crate_name: &str,
+        headers: &[PathBuf],
+        target_triple: Option<&str>,
+    ) -> Result<HeaderCheckReport> {
+        if headers.is_empty() {
+            return Err(anyhow!("No headers to check"));
+        }
+
+        let translation_unit = self.write_translation_unit(crate_name, headers, target_triple)?;
+        let zig_target = self.zig_target(target_triple);
+        let mut report = HeaderCheckReport::default();
+
+        // A C compiler is required; zig translate-c is an extra check when zig is installed
+        let mut cc = match (which::which("cc"), which::which("zig")) {
+            (Ok(cc), _) if zig_target.is_none() => Command::new(cc),
+            (_, Ok(zig)) => {
+                let mut cmd = Command::new(zig);
+                cmd.arg("cc");
+                if let Some(ref zig_target) = zig_target {
+                    cmd.arg("-target").arg(zig_target);
+                }
+                cmd
+            }
+            (Ok(cc), Err(_)) => Command::new(cc),
+            (Err(_), Err(_)) => {
+                return Err(Error::ToolMissing("No C compiler found. Install a C compiler (cc) or zig to check headers".to_string()).into());
+            }
+        };
+        cc.args(["-x", "c", "-std=c11", "-fsyntax-only", "-Wall", "-Werror"]);
+        cc.arg(&translation_unit);
+
+        let cc_name = if cc.get_program().to_string_lossy().ends_with("zig") { "zig cc" } else { "cc" };
+        self.run_tool(cc_name, &mut cc, headers, &mut report)?;
+
+        if let Ok(zig) = which::which("zig") {
+            let mut translate_c = Command::new(zig);
+            translate_c.arg("translate-c").arg("-lc");
+            if let Some(ref zig_target) = zig_target {
+                translate_c.arg("-target").arg(zig_target);
+            }
+            translate_c.arg(&translation_unit);
+            self.run_tool("zig translate-c", &mut translate_c, headers, &mut report)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Writes a C file that includes every header, in order.
+    fn write_translation_unit(&self, crate_name: &str, headers: &[PathBuf], target_triple: Option<&str>) -> Result<PathBuf> {
+        let check_dir = self.cache_dir.join(target_triple.unwrap_or("native")).join("check");
+        fs::create_dir_all(&check_dir)
+            .with_context(|| format!("Failed to create check directory: {}", check_dir.display()))?;
+
+        let mut source = String::new();
+        writeln!(source, "/* Generated by ghostbind to compile-check the headers of {} */", crate_name)?;
+        for header in headers {
+            let header = header.canonicalize()
+                .with_context(|| format!("Header file does not exist: {}", header.display()))?;
+            writeln!(source, "#include \"{}\"", header.display())?;
+        }
+
+        let path = check_dir.join(format!("{}_headers.c", crate_name));
+        fs::write(&path, source)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+
+    /// Host builds use the host compiler; anything else is checked with `zig cc -target`.
+    fn zig_target(&self, target_triple: Option<&str>) -> Option<String> {
+        let target_triple = target_triple?;
+        if target_triple == get_host_target().ok()? {
+            return None;
+        }
+
+        TargetMapping::new().rust_to_zig(target_triple).map(str::to_string)
+    }
+
+    fn run_tool(&self, tool: &str, cmd: &mut Command, headers: &[PathBuf], report: &mut HeaderCheckReport) -> Result<()> {
+        let output = cmd.output()
+            .with_context(|| format!("Failed to run {}", tool))?;
+        report.tools.push(tool.to_string());
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut diagnostics = parse_diagnostics(tool, &stderr);
+
+        if !output.status.success() && !diagnostics.iter().any(|d| d.severity != "warning") {
+            diagnostics.push(HeaderDiagnostic {
+                tool: tool.to_string(),
+                file: PathBuf::new(),
+                line: 0,
+                column: 0,
+                severity: "error".to_string(),
+                message: stderr.trim().to_string(),
+                item: None,
+            });
+        }
+
+        let mut parsed: HashMap<PathBuf, CHeader> = HashMap::new();
+        for diagnostic in &mut diagnostics {
+            let Some(header) = headers.iter().find(|h| same_file(h, &diagnostic.file)) else { continue };
+            if !parsed.contains_key(header) {
+                parsed.insert(header.clone(), parse_header_file(header)?);
+            }
+            diagnostic.item = parsed[header].item_at_line(diagnostic.line).map(str::to_string);
+        }
+
+        report.diagnostics.extend(diagnostics);
+        Ok(())
+    }
+}
+
+impl Default for HeaderChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses `file:line:col: severity: message` lines as printed by clang, gcc and zig.
+fn parse_diagnostics(tool: &str, output: &str) -> Vec<HeaderDiagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for line in output.lines() {
+        let Some((location, severity, message)) = ["fatal error", "error", "warning"].iter().find_map(|severity| {
+            let marker = format!(": {}: ", severity);
+            line.split_once(&marker).map(|(location, message)| (location, *severity, message))
+        }) else {
+            continue;
+        };
+
+        let mut parts = location.rsplitn(3, ':');
+        let (Some(column), Some(line_no), Some(file)) = (parts.next(), parts.next(), parts.next()) else { continue };
+        let (Ok(column), Ok(line_no)) = (column.parse(), line_no.parse()) else { continue };
+
+        diagnostics.push(HeaderDiagnostic {
+            tool: tool.to_string(),
+            file: PathBuf::from(file),
+            line: line_no,
+            column,
+            severity: if severity == "fatal error" { "error".to_string() } else { severity.to_string() },
+            message: message.trim().to_string(),
+            item: None,
+        });
+    }
+
+    diagnostics
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let output = "\
+In file included from check.c:2:
+/tmp/cache/headers/my_crate.h:12:3: error: unknown type name 'String'
+/tmp/cache/headers/my_crate.h:20:1: warning: declaration does not declare anything
+1 error generated.
+";
+
+        let diagnostics = parse_diagnostics("cc", output);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].file, PathBuf::from("/tmp/cache/headers/my_crate.h"));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (12, 3));
+        assert_eq!(diagnostics[0].severity, "error");
+        assert_eq!(diagnostics[0].message, "unknown type name 'String'");
+        assert_eq!(diagnostics[1].severity, "warning");
+    }
+
+    #[test]
+    fn test_report_error_count() {
+        let report = HeaderCheckReport {
+            tools: vec!["cc".to_string()],
+            diagnostics: parse_diagnostics("cc", "a.h:1:1: warning: unused\na.h:2:1: fatal error: 'x.h' file not found\n"),
+        };
+
+        assert_eq!(report.error_count(), 1);
+        assert!(report.has_errors());
+    }
+}
@@ -1,19 +1,134 @@
 use anyhow::{anyhow, Context, Result};
+use std::fmt::Write as _;
 use std::fs;
 use std::path::{Path, PathBuf};
+#[cfg(not(feature = "embedded-cbindgen"))]
 use std::process::Command;
 
-use crate::cargo_integration::CrateInfo;
+use crate::cargo_integration::{BuildProfile, CrateInfo};
+#[cfg(not(feature = "embedded-cbindgen"))]
+use crate::error::Error;
+use crate::reproducible::stabilize_header_version;
+use crate::target_cfg::{collect_cfg_atoms, TargetCfg};
 
+/// Runs cbindgen for a crate.
+///
+/// With the `embedded-cbindgen` feature cbindgen is linked in as a library;
+/// otherwise the `cbindgen` binary on PATH is used.
 pub struct HeaderGenerator {
     cbindgen_config: Option<PathBuf>,
     cache_dir: PathBuf,
+    features: Vec<String>,
+    no_default_features: bool,
+    profile: BuildProfile,
+    mode: HeaderMode,
+    language: HeaderLanguage,
+    languages: Vec<HeaderLanguage>,
+    reproducible: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct GeneratedHeader {
     pub crate_name: String,
     pub header_path: PathBuf,
+    pub language: HeaderLanguage,
+}
+
+/// How `#[cfg(...)]`-gated items are represented in generated headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderMode {
+    /// Only the items enabled for the build target and features are visible
+    PerTarget,
+    /// Platform-specific items are guarded by compiler-defined platform macros
+    Merged,
+}
+
+impl HeaderMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            HeaderMode::PerTarget => "per-target",
+            HeaderMode::Merged => "merged",
+        }
+    }
+}
+
+/// The language cbindgen writes bindings in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderLanguage {
+    C,
+    Cxx,
+    Cython,
+}
+
+impl HeaderLanguage {
+    /// The name cbindgen's `language` setting uses.
+    pub fn as_str(&self) -> &str {
+        match self {
+            HeaderLanguage::C => "C",
+            HeaderLanguage::Cxx => "C++",
+            HeaderLanguage::Cython => "Cython",
+        }
+    }
+
+    /// The tag the manifest records, which is also what cbindgen's `--lang` takes.
+    pub fn tag(&self) -> &str {
+        match self {
+            HeaderLanguage::C => "c",
+            HeaderLanguage::Cxx => "c++",
+            HeaderLanguage::Cython => "cython",
+        }
+    }
+
+    /// Parses a `language` setting the way cbindgen does, ignoring case.
+    pub fn from_cbindgen(language: &str) -> Option<Self> {
+        match language.to_ascii_lowercase().as_str() {
+            "c" => Some(HeaderLanguage::C),
+            "c++" | "cxx" | "cpp" => Some(HeaderLanguage::Cxx),
+            "cython" => Some(HeaderLanguage::Cython),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &str {
+        match self {
+            HeaderLanguage::C => "h",
+            HeaderLanguage::Cxx => "hpp",
+            HeaderLanguage::Cython => "pxd",
+        }
+    }
+}
+
+/// What [`HeaderGenerator::create_default_cbindgen_config`] did with cbindgen.toml.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigStatus {
+    Created,
+    /// The file already matches the template
+    Unchanged,
+    /// The file differs from the template and was left alone
+    Kept,
+    /// The file differed from the template and was replaced
+    Overwritten,
+}
+
+#[derive(Debug, Clone)]
+pub struct CbindgenConfigReport {
+    pub path: PathBuf,
+    pub status: ConfigStatus,
+    /// How the existing file differed from the template, for `Kept` and `Overwritten`
+    pub diff: Option<String>,
+}
+
+/// cbindgen `[defines]` entries plus the `#define`s that decide which of them hold.
+#[derive(Debug, Clone, Default)]
+struct CfgDefines {
+    defines: Vec<(String, String)>,
+    after_includes: String,
+}
+
+impl CfgDefines {
+    fn is_empty(&self) -> bool {
+        self.defines.is_empty()
+    }
 }
 
 impl HeaderGenerator {
@@ -23,34 +138,97 @@ impl HeaderGenerator {
         Self {
             cbindgen_config,
             cache_dir,
+            features: Vec::new(),
+            no_default_features: false,
+            profile: BuildProfile::Release,
+            mode: HeaderMode::PerTarget,
+            language: HeaderLanguage::C,
+            languages: Vec::new(),
+            reproducible: false,
         }
     }
 
+    /// The language of the default config, and of the bindings for crates without one.
+    pub fn language(mut self, language: HeaderLanguage) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Generates one header per language, overriding the config's `language`. Without
+    /// any, a single header is generated in the language the config asks for.
+    pub fn languages(mut self, languages: Vec<HeaderLanguage>) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    /// Drops the cbindgen version from the `include_version` line, so headers don't change
+    /// with the cbindgen that happened to generate them.
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
+    pub fn mode(mut self, mode: HeaderMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn features(mut self, features: Vec<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn no_default_features(mut self, no_default: bool) -> Self {
+        self.no_default_features = no_default;
+        self
+    }
+
+    pub fn profile(mut self, profile: BuildProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
     pub fn generate_headers(&self, crate_info: &CrateInfo, target_triple: Option<&str>) -> Result<Vec<GeneratedHeader>> {
         // Check if cbindgen is available
         self.check_cbindgen_available()?;
 
-        let mut headers = Vec::new();
+        // One header per crate and language; cfg-gated items follow the target and enabled features
+        if self.languages.is_empty() {
+            let header_path = self.get_header_path(&crate_info.name, target_triple, "h");
+            let language = self.config_language(crate_info)?;
+            return Ok(vec![self.generate_header_for_crate(crate_info, target_triple, header_path, language, None)?]);
+        }
 
-        // For now, generate one header per crate
-        // In the future, we might generate per-target headers
-        let header = self.generate_header_for_crate(crate_info, target_triple)?;
-        headers.push(header);
+        let mut headers = Vec::new();
+        for language in &self.languages {
+            let header_path = self.get_header_path(&crate_info.name, target_triple, language.extension());
+            headers.push(self.generate_header_for_crate(crate_info, target_triple, header_path, *language, Some(*language))?);
+        }
 
         Ok(headers)
     }
 
+    #[cfg(feature = "embedded-cbindgen")]
     fn check_cbindgen_available(&self) -> Result<()> {
-        which::which("cbindgen")
-            .map_err(|_| anyhow!(
-                "cbindgen not found in PATH. Install it with: cargo install cbindgen"
-            ))?;
         Ok(())
     }
 
-    fn generate_header_for_crate(&self, crate_info: &CrateInfo, target_triple: Option<&str>) -> Result<GeneratedHeader> {
-        let header_path = self.get_header_path(&crate_info.name, target_triple);
+    #[cfg(not(feature = "embedded-cbindgen"))]
+    fn check_cbindgen_available(&self) -> Result<()> {
+        which::which("cbindgen")
+            .map_err(|_| Error::CbindgenMissing)?;
+        Ok(())
+    }
 
+    /// Runs cbindgen into `header_path`. A `forced` language overrides the config's.
+    fn generate_header_for_crate(
+        &self,
+        crate_info: &CrateInfo,
+        target_triple: Option<&str>,
+        header_path: PathBuf,
+        language: HeaderLanguage,
+        forced: Option<HeaderLanguage>,
+    ) -> Result<GeneratedHeader> {
         // Create cache directory
         if let Some(header_parent) = header_path.parent() {
             fs::create_dir_all(header_parent)
@@ -67,90 +245,440 @@ impl HeaderGenerator {
             })
             .with_context(|| format!("Failed to resolve absolute path for header: {}", header_path.display()))?;
 
-        // Build cbindgen command
+        let cfg_defines = self.cfg_defines(crate_info, target_triple)?;
+        self.run_cbindgen(crate_info, &header_path_abs, &cfg_defines, forced)?;
+
+        // Verify the header was created
+        if !header_path_abs.exists() {
+            return Err(anyhow!("cbindgen completed but header file was not created: {}", header_path_abs.display()));
+        }
+
+        if self.reproducible {
+            stabilize_header_version(&header_path_abs)?;
+        }
+
+        Ok(GeneratedHeader {
+            crate_name: crate_info.name.clone(),
+            header_path,
+            language,
+        })
+    }
+
+    /// Maps every cfg predicate the crate uses to a `GHOSTBIND_CFG_*` macro and defines
+    /// the ones that hold, so cfg-gated items match what was actually built.
+    fn cfg_defines(&self, crate_info: &CrateInfo, target_triple: Option<&str>) -> Result<CfgDefines> {
+        let atoms = collect_cfg_atoms(&crate_info.lib_source_files()?)?;
+        if atoms.is_empty() {
+            return Ok(CfgDefines::default());
+        }
+
+        let target_cfg = TargetCfg::for_target(target_triple, &self.profile)?
+            .with_features(&crate_info.enabled_features);
+
+        let mut cfg_defines = CfgDefines::default();
+        match self.mode {
+            HeaderMode::PerTarget => {
+                let _ = writeln!(cfg_defines.after_includes, "/* cfg predicates enabled for {} */", target_triple.unwrap_or("the host"));
+            }
+            HeaderMode::Merged => {
+                let _ = writeln!(cfg_defines.after_includes, "/* cfg predicates, detected from the compiling platform */");
+            }
+        }
+
+        for atom in &atoms {
+            let macro_name = atom.macro_name();
+            cfg_defines.defines.push((atom.define_key(), macro_name.clone()));
+
+            // Features are fixed at build time; everything else is detected in merged mode when we can
+            let condition = match self.mode {
+                HeaderMode::Merged if atom.name != "feature" => atom.platform_condition(),
+                _ => None,
+            };
+
+            match condition {
+                Some(condition) => {
+                    let _ = writeln!(cfg_defines.after_includes, "#if {}\n#define {}\n#endif", condition, macro_name);
+                }
+                None if target_cfg.is_active(atom) => {
+                    let _ = writeln!(cfg_defines.after_includes, "#define {}", macro_name);
+                }
+                None => {}
+            }
+        }
+
+        Ok(cfg_defines)
+    }
+
+    #[cfg(feature = "embedded-cbindgen")]
+    fn run_cbindgen(&self, crate_info: &CrateInfo, output: &Path, cfg_defines: &CfgDefines, forced: Option<HeaderLanguage>) -> Result<()> {
+        let mut config = match self.cbindgen_config {
+            Some(ref config_path) => cbindgen::Config::from_file(config_path)
+                .map_err(|e| anyhow!("Failed to load cbindgen config {}: {}", config_path.display(), e))?,
+            None => cbindgen::Config::from_root_or_default(&crate_info.manifest_dir),
+        };
+
+        let language = forced.or_else(|| self.config_path(crate_info).is_none().then_some(self.language));
+        if let Some(language) = language {
+            config.language = match language {
+                HeaderLanguage::C => cbindgen::Language::C,
+                HeaderLanguage::Cxx => cbindgen::Language::Cxx,
+                HeaderLanguage::Cython => cbindgen::Language::Cython,
+            };
+        }
+
+        // Only consulted when the config opts into macro expansion, but keep it in sync with the build
+        config.parse.expand.default_features = !self.no_default_features;
+        config.parse.expand.features = Some(self.features.clone());
+        config.parse.expand.profile = match self.profile {
+            BuildProfile::Debug => cbindgen::Profile::Debug,
+            BuildProfile::Release => cbindgen::Profile::Release,
+        };
+
+        if !cfg_defines.is_empty() {
+            for (key, macro_name) in &cfg_defines.defines {
+                config.defines.entry(key.clone()).or_insert_with(|| macro_name.clone());
+            }
+            config.after_includes = Some(match config.after_includes.take() {
+                Some(existing) => format!("{}\n{}", existing, cfg_defines.after_includes),
+                None => cfg_defines.after_includes.clone(),
+            });
+        }
+
+        let mut builder = cbindgen::Builder::new()
+            .with_config(config)
+            .with_crate_and_name(&crate_info.manifest_dir, &crate_info.name);
+
+        if let Some(lockfile) = self.get_lockfile(crate_info) {
+            builder = builder.with_lockfile(lockfile);
+        }
+
+        let bindings = builder.generate()
+            .map_err(|e| anyhow!("cbindgen failed: {}", e))?;
+        bindings.write_to_file(output);
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "embedded-cbindgen"))]
+    fn run_cbindgen(&self, crate_info: &CrateInfo, output: &Path, cfg_defines: &CfgDefines, forced: Option<HeaderLanguage>) -> Result<()> {
+        // Hand cbindgen the metadata we already resolved instead of letting it run its own
+        let metadata_path = output.with_extension("metadata.json");
+        let metadata_json = serde_json::to_string(&crate_info.metadata)
+            .context("Failed to serialize cargo metadata")?;
+        fs::write(&metadata_path, metadata_json)
+            .with_context(|| format!("Failed to write cargo metadata to {}", metadata_path.display()))?;
+
+        // Point cbindgen at the crate directory so it resolves the package itself,
+        // rather than guessing it from a name that may differ from the lib name
         let mut cmd = Command::new("cbindgen");
-        cmd.arg("--crate").arg(&crate_info.name);
-        cmd.arg("--output").arg(&header_path_abs);
+        cmd.arg(&crate_info.manifest_dir);
+        cmd.arg("--output").arg(output);
+        cmd.arg("--metadata").arg(&metadata_path);
+
+        if let Some(lockfile) = self.get_lockfile(crate_info) {
+            cmd.arg("--lockfile").arg(lockfile);
+        }
+
+        if matches!(self.profile, BuildProfile::Release) {
+            cmd.arg("--profile").arg("release");
+        }
 
         // Use custom config if provided
-        if let Some(ref config_path) = self.cbindgen_config {
+        if let Some(config_path) = self.write_derived_config(crate_info, output, cfg_defines)? {
+            cmd.arg("--config").arg(config_path);
+        } else if let Some(ref config_path) = self.cbindgen_config {
             cmd.arg("--config").arg(config_path);
-        } else {
-            // Use sensible defaults
-            cmd.arg("--lang").arg("c");
+        }
+
+        // `--lang` takes precedence over the config's `language`
+        let language = forced.or_else(|| self.config_path(crate_info).is_none().then_some(self.language));
+        if let Some(language) = language {
+            cmd.arg("--lang").arg(language.tag());
         }
 
         // Set the working directory to the crate's manifest directory
         cmd.current_dir(&crate_info.manifest_dir);
 
-        println!("Generating header for crate '{}' with cbindgen...", crate_info.name);
-
         let output = cmd.output()
             .context("Failed to execute cbindgen")?;
 
+        let _ = fs::remove_file(&metadata_path);
+
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(anyhow!("cbindgen failed: {}", stderr));
         }
 
-        // Verify the header was created
-        if !header_path_abs.exists() {
-            return Err(anyhow!("cbindgen completed but header file was not created: {}", header_path_abs.display()));
+        Ok(())
+    }
+
+    /// Writes a copy of the effective cbindgen config with ghostbind's cfg defines merged in.
+    #[cfg(not(feature = "embedded-cbindgen"))]
+    fn write_derived_config(&self, crate_info: &CrateInfo, output: &Path, cfg_defines: &CfgDefines) -> Result<Option<PathBuf>> {
+        if cfg_defines.is_empty() {
+            return Ok(None);
         }
 
-        println!("Generated header: {}", header_path_abs.display());
+        let base_config = self.config_path(crate_info);
+        let mut config = match base_config {
+            Some(ref config_path) => {
+                let content = fs::read_to_string(config_path)
+                    .with_context(|| format!("Failed to read cbindgen config {}", config_path.display()))?;
+                toml::from_str::<toml::Table>(&content)
+                    .with_context(|| format!("Failed to parse cbindgen config {}", config_path.display()))?
+            }
+            None => toml::Table::new(),
+        };
+
+        if base_config.is_none() {
+            config.insert("language".to_string(), toml::Value::String(self.language.as_str().to_string()));
+        }
 
-        Ok(GeneratedHeader {
-            crate_name: crate_info.name.clone(),
-            header_path,
+        let defines = config.entry("defines")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("cbindgen config has a non-table `defines` entry"))?;
+        for (key, macro_name) in &cfg_defines.defines {
+            defines.entry(key.clone()).or_insert_with(|| toml::Value::String(macro_name.clone()));
+        }
+
+        let after_includes = match config.get("after_includes").and_then(|v| v.as_str()) {
+            Some(existing) => format!("{}\n{}", existing, cfg_defines.after_includes),
+            None => cfg_defines.after_includes.clone(),
+        };
+        config.insert("after_includes".to_string(), toml::Value::String(after_includes));
+
+        let config_path = output.with_extension("cbindgen.toml");
+        let content = toml::to_string(&config)
+            .context("Failed to serialize derived cbindgen config")?;
+        fs::write(&config_path, content)
+            .with_context(|| format!("Failed to write derived cbindgen config to {}", config_path.display()))?;
+
+        Ok(Some(config_path))
+    }
+
+    /// The config cbindgen reads: the explicit one, else cbindgen.toml in the crate.
+    fn config_path(&self, crate_info: &CrateInfo) -> Option<PathBuf> {
+        self.cbindgen_config.clone().or_else(|| {
+            let crate_config = crate_info.manifest_dir.join("cbindgen.toml");
+            crate_config.exists().then_some(crate_config)
         })
     }
 
-    fn get_header_path(&self, crate_name: &str, target_triple: Option<&str>) -> PathBuf {
+    /// The config cbindgen reads, parsed, if there is one.
+    fn read_config(&self, crate_info: &CrateInfo) -> Result<Option<toml::Table>> {
+        let Some(config_path) = self.config_path(crate_info) else {
+            return Ok(None);
+        };
+        let content = fs::read_to_string(&config_path)
+            .with_context(|| format!("Failed to read cbindgen config {}", config_path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse cbindgen config {}", config_path.display()))
+            .map(Some)
+    }
+
+    /// The language a header comes out in when none is forced: the config's, else the generator's.
+    fn config_language(&self, crate_info: &CrateInfo) -> Result<HeaderLanguage> {
+        let Some(config) = self.read_config(crate_info)? else {
+            return Ok(self.language);
+        };
+        match config.get("language").and_then(|language| language.as_str()) {
+            Some(language) => HeaderLanguage::from_cbindgen(language)
+                .ok_or_else(|| anyhow!("Unknown cbindgen language: {}", language)),
+            // cbindgen's own default
+            None => Ok(HeaderLanguage::Cxx),
+        }
+    }
+
+    /// The `[export] prefix` cbindgen puts in front of type names, if the config sets one.
+    pub fn type_prefix(&self, crate_info: &CrateInfo) -> Result<Option<String>> {
+        let Some(config) = self.read_config(crate_info)? else {
+            return Ok(None);
+        };
+
+        Ok(config.get("export")
+            .and_then(|export| export.get("prefix"))
+            .and_then(|prefix| prefix.as_str())
+            .map(str::to_string))
+    }
+
+    fn get_lockfile(&self, crate_info: &CrateInfo) -> Option<PathBuf> {
+        let lockfile = crate_info.workspace_root.join("Cargo.lock");
+        lockfile.exists().then_some(lockfile)
+    }
+
+    fn get_header_path(&self, crate_name: &str, target_triple: Option<&str>, extension: &str) -> PathBuf {
         let target_str = target_triple.unwrap_or("native");
 
         self.cache_dir
             .join(target_str)
             .join("headers")
-            .join(format!("{}.h", crate_name))
+            .join(format!("{}.{}", crate_name, extension))
     }
 
-    pub fn create_default_cbindgen_config(&self, crate_dir: &Path) -> Result<PathBuf> {
+    /// Writes cbindgen.toml for a crate from ghostbind's template. An existing file that
+    /// differs is only replaced with `force`; the report carries the difference either way.
+    pub fn create_default_cbindgen_config(&self, crate_dir: &Path, crate_name: &str, force: bool) -> Result<CbindgenConfigReport> {
         let config_path = crate_dir.join("cbindgen.toml");
+        let diff = self.cbindgen_config_diff(crate_dir, crate_name)?;
+
+        let status = match (config_path.exists(), &diff, force) {
+            (true, None, _) => ConfigStatus::Unchanged,
+            (true, Some(_), false) => ConfigStatus::Kept,
+            (exists, _, _) => {
+                fs::write(&config_path, self.default_cbindgen_config(crate_name))
+                    .with_context(|| format!("Failed to write default cbindgen config to {}", config_path.display()))?;
+                if exists { ConfigStatus::Overwritten } else { ConfigStatus::Created }
+            }
+        };
+
+        Ok(CbindgenConfigReport {
+            diff: diff.filter(|_| status != ConfigStatus::Created),
+            path: config_path,
+            status,
+        })
+    }
 
-        if config_path.exists() {
-            // Don't overwrite existing config
-            return Ok(config_path);
+    /// How the crate's cbindgen.toml differs from the template, or `None` if it matches.
+    pub fn cbindgen_config_diff(&self, crate_dir: &Path, crate_name: &str) -> Result<Option<String>> {
+        let config_path = crate_dir.join("cbindgen.toml");
+        let existing = if config_path.exists() {
+            fs::read_to_string(&config_path)
+                .with_context(|| format!("Failed to read cbindgen config {}", config_path.display()))?
+        } else {
+            String::new()
+        };
+
+        let template = self.default_cbindgen_config(crate_name);
+        if existing == template {
+            return Ok(None);
         }
+        Ok(Some(format!("--- {}\n+++ template\n{}", config_path.display(), line_diff(&existing, &template))))
+    }
 
-        let default_config = r#"
-language = "C"
-autogen_warning = "/* Warning, this file is autogenerated by cbindgen. Don't modify this manually. */"
-include_version = true
-namespace = "GlobalNamespace"
+    /// The template: an include guard, a type prefix and a must-use macro named after the
+    /// crate, in the generator's language. The prefix and `rename_variants` change the C
+    /// names of exported types and enum variants; struct fields keep their Rust names.
+    pub fn default_cbindgen_config(&self, crate_name: &str) -> String {
+        let ident = crate_name.replace('-', "_");
+        let upper = ident.to_uppercase();
+        let must_use = format!("{}_MUST_USE", upper);
+
+        let mut config = String::new();
+        let _ = writeln!(config, "# cbindgen config for {}, generated by ghostbind", crate_name);
+        let _ = writeln!(config, "language = \"{}\"", self.language.as_str());
+        match self.language {
+            HeaderLanguage::Cython => {
+                let _ = writeln!(config, "autogen_warning = \"# Warning, this file is autogenerated by cbindgen. Don't modify this manually.\"");
+                let _ = writeln!(config, "include_version = true");
+            }
+            HeaderLanguage::C | HeaderLanguage::Cxx => {
+                let _ = writeln!(config, "include_guard = \"{}_H\"", upper);
+                let _ = writeln!(config, "autogen_warning = \"/* Warning, this file is autogenerated by cbindgen. Don't modify this manually. */\"");
+                let _ = writeln!(config, "include_version = true");
+                if self.language == HeaderLanguage::C {
+                    // extern "C" guards, so C++ code can include the header too
+                    let _ = writeln!(config, "cpp_compat = true");
+                } else {
+                    let _ = writeln!(config, "namespace = \"{}\"", ident);
+                }
+                let _ = writeln!(config, "after_includes = \"\"\"");
+                let _ = writeln!(config, "#ifndef {}", must_use);
+                let _ = writeln!(config, "#if defined(__cplusplus) && __cplusplus >= 201703L");
+                let _ = writeln!(config, "#define {} [[nodiscard]]", must_use);
+                let _ = writeln!(config, "#elif defined(__GNUC__) || defined(__clang__)");
+                let _ = writeln!(config, "#define {} __attribute__((warn_unused_result))", must_use);
+                let _ = writeln!(config, "#else");
+                let _ = writeln!(config, "#define {}", must_use);
+                let _ = writeln!(config, "#endif");
+                let _ = writeln!(config, "#endif");
+                let _ = writeln!(config, "\"\"\"");
+            }
+        }
+        if self.language == HeaderLanguage::Cython {
+            let _ = writeln!(config, "\n[cython]");
+            let _ = writeln!(config, "header = '\"{}.h\"'", crate_name);
+        }
 
-[enum]
-rename_variants = "QualifiedScreamingSnakeCase"
+        let _ = writeln!(config, "\n[export]");
+        let _ = writeln!(config, "prefix = \"{}\"", pascal_case(&ident));
+        let _ = writeln!(config, "include = []");
+        let _ = writeln!(config, "exclude = []");
+        let _ = writeln!(config, "\n[enum]");
+        let _ = writeln!(config, "rename_variants = \"QualifiedScreamingSnakeCase\"");
+        let _ = writeln!(config, "\n[fn]");
+        let _ = writeln!(config, "rename_args = \"snake_case\"");
+        if self.language != HeaderLanguage::Cython {
+            let _ = writeln!(config, "must_use = \"{}\"", must_use);
+        }
+        config
+    }
+}
+
+/// The C headers, which ghostbind's checks parse and compile and Zig's `@cImport` reads.
+pub fn c_headers(headers: &[GeneratedHeader]) -> Vec<GeneratedHeader> {
+    headers.iter()
+        .filter(|header| header.language == HeaderLanguage::C)
+        .cloned()
+        .collect()
+}
 
-[fn]
-rename_args = "snake_case"
-must_use = "MUST_USE_FUNC"
+/// `my_crate` as `MyCrate`.
+fn pascal_case(ident: &str) -> String {
+    ident.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect()
+}
 
-[struct]
-rename_fields = "snake_case"
+/// A line diff of two small files: every line, prefixed with `-`, `+` or a space.
+fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
 
-[export]
-include = []
-exclude = []
-"#;
+    // Longest common subsequence lengths of every pair of suffixes
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
 
-        fs::write(&config_path, default_config)
-            .with_context(|| format!("Failed to write default cbindgen config to {}", config_path.display()))?;
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            let _ = writeln!(diff, " {}", old[i]);
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            let _ = writeln!(diff, "-{}", old[i]);
+            i += 1;
+        } else {
+            let _ = writeln!(diff, "+{}", new[j]);
+            j += 1;
+        }
+    }
+    diff
+}
 
-        println!("Created default cbindgen config: {}", config_path.display());
+/// The version of the cbindgen headers are generated with.
+#[cfg(feature = "embedded-cbindgen")]
+pub fn cbindgen_version() -> Option<String> {
+    Some("0.29 (embedded)".to_string())
+}
 
-        Ok(config_path)
+/// The version of the cbindgen headers are generated with, if it is on PATH.
+#[cfg(not(feature = "embedded-cbindgen"))]
+pub fn cbindgen_version() -> Option<String> {
+    let output = Command::new("cbindgen").arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
     }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 #[cfg(test)]
@@ -161,7 +689,7 @@ mod tests {
     fn test_header_path_generation() {
         let generator = HeaderGenerator::new(None);
 
-        let header_path = generator.get_header_path("my_crate", Some("x86_64-unknown-linux-gnu"));
+        let header_path = generator.get_header_path("my_crate", Some("x86_64-unknown-linux-gnu"), "h");
 
         assert!(header_path.to_string_lossy().contains("x86_64-unknown-linux-gnu"));
         assert!(header_path.to_string_lossy().contains("headers"));
@@ -172,9 +700,53 @@ mod tests {
     fn test_header_path_native_target() {
         let generator = HeaderGenerator::new(None);
 
-        let header_path = generator.get_header_path("my_crate", None);
+        let header_path = generator.get_header_path("my_crate", None, "hpp");
 
         assert!(header_path.to_string_lossy().contains("native"));
-        assert!(header_path.to_string_lossy().ends_with("my_crate.h"));
+        assert!(header_path.to_string_lossy().ends_with("my_crate.hpp"));
+    }
+
+    #[test]
+    fn test_default_cbindgen_config() {
+        let c: toml::Table = toml::from_str(&HeaderGenerator::new(None).default_cbindgen_config("my-crate")).unwrap();
+        assert_eq!(c["language"].as_str(), Some("C"));
+        assert_eq!(c["include_guard"].as_str(), Some("MY_CRATE_H"));
+        assert_eq!(c["cpp_compat"].as_bool(), Some(true));
+        assert_eq!(c["export"]["prefix"].as_str(), Some("MyCrate"));
+        assert_eq!(c["fn"]["must_use"].as_str(), Some("MY_CRATE_MUST_USE"));
+        assert!(c["after_includes"].as_str().unwrap().contains("#define MY_CRATE_MUST_USE __attribute__((warn_unused_result))"));
+        assert!(c.get("namespace").is_none());
+        assert!(c.get("struct").is_none());
+
+        let cxx: toml::Table = toml::from_str(&HeaderGenerator::new(None).language(HeaderLanguage::Cxx).default_cbindgen_config("my-crate")).unwrap();
+        assert_eq!(cxx["language"].as_str(), Some("C++"));
+        assert_eq!(cxx["namespace"].as_str(), Some("my_crate"));
+
+        let cython: toml::Table = toml::from_str(&HeaderGenerator::new(None).language(HeaderLanguage::Cython).default_cbindgen_config("my-crate")).unwrap();
+        assert_eq!(cython["cython"]["header"].as_str(), Some("\"my-crate.h\""));
+        assert!(cython.get("include_guard").is_none());
+        assert!(cython["fn"].get("must_use").is_none());
+    }
+
+    #[test]
+    fn test_c_headers() {
+        let header = |name: &str, language| GeneratedHeader {
+            crate_name: "my_crate".to_string(),
+            header_path: PathBuf::from(name),
+            language,
+        };
+        let both = [header("my_crate.h", HeaderLanguage::C), header("my_crate.hpp", HeaderLanguage::Cxx)];
+        let paths: Vec<_> = c_headers(&both).into_iter().map(|h| h.header_path).collect();
+        assert_eq!(paths, vec![PathBuf::from("my_crate.h")]);
+
+        let cxx_only = [header("my_crate.hpp", HeaderLanguage::Cxx)];
+        assert!(c_headers(&cxx_only).is_empty());
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert_eq!(line_diff("a\nb\nc\n", "a\nc\nd\n"), " a\n-b\n c\n+d\n");
+        assert_eq!(line_diff("", "a\n"), "+a\n");
+        assert_eq!(line_diff("x = 1\n", "x = 2\n"), "-x = 1\n+x = 2\n");
     }
 }
\ No newline at end of file
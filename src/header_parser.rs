@@ -0,0 +1,798 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The declarations of a cbindgen-generated header.
+///
+/// Only the subset of C that cbindgen emits is understood: top-level function
+/// prototypes, structs, unions, enums, opaque forward declarations and typedefs.
+#[derive(Debug, Clone, Default)]
+pub struct CHeader {
+    pub functions: Vec<CFunction>,
+    pub types: Vec<CType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CFunction {
+    pub name: String,
+    pub return_type: String,
+    pub params: Vec<CParam>,
+    pub line_start: usize,
+    pub line_end: usize,
+    /// Declared under a preprocessor condition that can't be decided from the header alone
+    pub conditional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CParam {
+    pub name: Option<String>,
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CTypeKind {
+    Struct,
+    Union,
+    Enum,
+    Opaque,
+    Typedef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CType {
+    pub name: String,
+    pub kind: CTypeKind,
+    /// Fields of a struct or union
+    pub fields: Vec<CField>,
+    /// Variants of an enum, with their explicit value if any
+    pub variants: Vec<(String, Option<i64>)>,
+    /// The aliased type of a plain typedef
+    pub aliased: Option<String>,
+    /// Declared as `typedef struct X {...} X;`, so usable without the `struct` keyword
+    pub typedef: bool,
+    pub line_start: usize,
+    pub line_end: usize,
+    /// Declared under a preprocessor condition that can't be decided from the header alone
+    pub conditional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CField {
+    pub name: String,
+    pub ty: String,
+    pub array_len: Option<usize>,
+}
+
+impl CHeader {
+    pub fn function(&self, name: &str) -> Option<&CFunction> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+
+    pub fn type_named(&self, name: &str) -> Option<&CType> {
+        // Prefer a definition with a body over a forward declaration or alias
+        self.types.iter()
+            .filter(|t| t.name == name)
+            .max_by_key(|t| matches!(t.kind, CTypeKind::Struct | CTypeKind::Union | CTypeKind::Enum))
+    }
+
+    /// The name of the declaration spanning the given 1-based line.
+    pub fn item_at_line(&self, line: usize) -> Option<&str> {
+        let functions = self.functions.iter()
+            .filter(|f| f.line_start <= line && line <= f.line_end)
+            .map(|f| f.name.as_str());
+        let types = self.types.iter()
+            .filter(|t| t.line_start <= line && line <= t.line_end)
+            .map(|t| t.name.as_str());
+
+        functions.chain(types).next()
+    }
+}
+
+impl CFunction {
+    /// A normalized prototype, e.g. `int32_t add(int32_t, int32_t)`.
+    pub fn signature(&self) -> String {
+        let params: Vec<&str> = self.params.iter().map(|p| p.ty.as_str()).collect();
+        let params = if params.is_empty() { "void".to_string() } else { params.join(", ") };
+        format!("{} {}({})", self.return_type, self.name, params)
+    }
+}
+
+pub fn parse_header_file(path: &Path) -> Result<CHeader> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read header {}", path.display()))?;
+    Ok(parse_header(&source))
+}
+
+pub fn parse_header(source: &str) -> CHeader {
+    let mut header = CHeader::default();
+
+    for decl in split_declarations(&strip_comments(source)) {
+        parse_declaration(&decl, &mut header);
+    }
+
+    header
+}
+
+struct Declaration {
+    text: String,
+    line_start: usize,
+    line_end: usize,
+    conditional: bool,
+}
+
+/// Replaces comments with spaces, keeping newlines so line numbers stay correct.
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('*')) => {
+                chars.next();
+                out.push_str("  ");
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    out.push(if c == '\n' { '\n' } else { ' ' });
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Splits the source into top-level declarations.
+///
+/// Preprocessor conditionals are followed: declarations in branches that are known to be
+/// inactive are dropped, and ones whose condition can't be decided are marked conditional.
+fn split_declarations(source: &str) -> Vec<Declaration> {
+    let mut decls = Vec::new();
+    let mut current = String::new();
+    let mut line_start = 0;
+    let mut conditional = false;
+    let mut depth = 0i32;
+    let mut directive = String::new();
+    let mut preprocessor = Preprocessor::default();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let trimmed = line.trim();
+
+        if !directive.is_empty() || trimmed.starts_with('#') {
+            directive.push_str(trimmed.trim_end_matches('\\'));
+            directive.push(' ');
+            if !trimmed.ends_with('\\') {
+                preprocessor.directive(&directive);
+                directive.clear();
+            }
+            continue;
+        }
+
+        let state = preprocessor.state();
+        if state == Some(false) {
+            continue;
+        }
+
+        for c in line.chars() {
+            if current.trim().is_empty() && !c.is_whitespace() {
+                line_start = line_no;
+                conditional = state.is_none();
+            }
+            current.push(c);
+
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ';' if depth == 0 => {
+                    decls.push(Declaration {
+                        text: current.trim().trim_end_matches(';').trim().to_string(),
+                        line_start,
+                        line_end: line_no,
+                        conditional,
+                    });
+                    current.clear();
+                }
+                _ => {}
+            }
+        }
+        current.push(' ');
+    }
+
+    decls
+}
+
+/// Evaluates `#if`/`#ifdef`/`#elif`/`#else`/`#endif` with three-valued logic.
+///
+/// Macros defined by the header itself are known, `GHOSTBIND_CFG_*` macros it doesn't define
+/// are known to be unset, and anything else in an `#if` (platform macros) is unknown.
+/// `#ifdef`/`#ifndef` treat unknown macros as unset, which covers include guards and
+/// `__cplusplus` blocks.
+#[derive(Default)]
+struct Preprocessor {
+    defines: HashMap<String, Option<bool>>,
+    frames: Vec<ConditionFrame>,
+}
+
+struct ConditionFrame {
+    branch: Option<bool>,
+    taken: Option<bool>,
+}
+
+impl Preprocessor {
+    fn state(&self) -> Option<bool> {
+        let mut state = Some(true);
+        for frame in &self.frames {
+            state = and(state, frame.branch);
+        }
+        state
+    }
+
+    fn directive(&mut self, line: &str) {
+        let directive = line.trim().trim_start_matches('#').trim();
+        let (keyword, rest) = directive.split_once(char::is_whitespace).unwrap_or((directive, ""));
+        let rest = rest.split("//").next().unwrap_or_default().trim();
+
+        match keyword {
+            "if" | "ifdef" | "ifndef" => {
+                let value = match keyword {
+                    "if" => self.eval(rest),
+                    "ifdef" => Some(self.is_defined(rest) == Some(true)),
+                    _ => Some(self.is_defined(rest) != Some(true)),
+                };
+                self.frames.push(ConditionFrame { branch: value, taken: value });
+            }
+            "elif" => {
+                let value = self.eval(rest);
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.branch = and(not(frame.taken), value);
+                    frame.taken = or(frame.taken, value);
+                }
+            }
+            "else" => {
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.branch = not(frame.taken);
+                    frame.taken = Some(true);
+                }
+            }
+            "endif" => {
+                self.frames.pop();
+            }
+            "define" => {
+                let name: String = rest.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+                let state = self.state();
+                let previous = self.defines.get(&name).copied().flatten();
+                let defined = match (state, previous) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), previous) => previous,
+                    (None, _) => None,
+                };
+                self.defines.insert(name, defined);
+            }
+            "undef" if self.state() == Some(true) => {
+                self.defines.insert(rest.to_string(), Some(false));
+            }
+            _ => {}
+        }
+    }
+
+    fn is_defined(&self, name: &str) -> Option<bool> {
+        match self.defines.get(name) {
+            Some(defined) => *defined,
+            None if name.starts_with("GHOSTBIND_CFG_") || name == "__cplusplus" => Some(false),
+            None => None,
+        }
+    }
+
+    /// Evaluates conditions built from `defined(...)`, `!`, `&&`, `||` and parentheses.
+    fn eval(&self, expr: &str) -> Option<bool> {
+        let tokens = tokenize_condition(expr)?;
+        let mut pos = 0;
+        let value = self.eval_or(&tokens, &mut pos)?;
+        (pos == tokens.len()).then_some(value)?
+    }
+
+    fn eval_or(&self, tokens: &[String], pos: &mut usize) -> Option<Option<bool>> {
+        let mut value = self.eval_and(tokens, pos)?;
+        while tokens.get(*pos).is_some_and(|t| t == "||") {
+            *pos += 1;
+            value = or(value, self.eval_and(tokens, pos)?);
+        }
+        Some(value)
+    }
+
+    fn eval_and(&self, tokens: &[String], pos: &mut usize) -> Option<Option<bool>> {
+        let mut value = self.eval_unary(tokens, pos)?;
+        while tokens.get(*pos).is_some_and(|t| t == "&&") {
+            *pos += 1;
+            value = and(value, self.eval_unary(tokens, pos)?);
+        }
+        Some(value)
+    }
+
+    fn eval_unary(&self, tokens: &[String], pos: &mut usize) -> Option<Option<bool>> {
+        let token = tokens.get(*pos)?.as_str();
+        *pos += 1;
+
+        match token {
+            "!" => Some(not(self.eval_unary(tokens, pos)?)),
+            "(" => {
+                let value = self.eval_or(tokens, pos)?;
+                (tokens.get(*pos)? == ")").then(|| *pos += 1)?;
+                Some(value)
+            }
+            "defined" => {
+                let parenthesized = tokens.get(*pos).is_some_and(|t| t == "(");
+                if parenthesized {
+                    *pos += 1;
+                }
+                let name = tokens.get(*pos)?;
+                *pos += 1;
+                if parenthesized {
+                    (tokens.get(*pos)? == ")").then(|| *pos += 1)?;
+                }
+                Some(self.is_defined(name))
+            }
+            "0" => Some(Some(false)),
+            "1" => Some(Some(true)),
+            _ => Some(None),
+        }
+    }
+}
+
+/// Splits a condition into tokens; returns `None` for anything beyond boolean logic.
+fn tokenize_condition(expr: &str) -> Option<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' | ')' => tokens.push(c.to_string()),
+            '!' if chars.peek() != Some(&'=') => tokens.push("!".to_string()),
+            '&' | '|' if chars.peek() == Some(&c) => {
+                chars.next();
+                tokens.push(format!("{}{}", c, c));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(&next) = chars.peek().filter(|n| n.is_alphanumeric() || **n == '_') {
+                    ident.push(next);
+                    chars.next();
+                }
+                tokens.push(ident);
+            }
+            _ => return None,
+        }
+    }
+
+    Some(tokens)
+}
+
+fn and(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+fn or(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+fn not(a: Option<bool>) -> Option<bool> {
+    a.map(|a| !a)
+}
+
+fn parse_declaration(decl: &Declaration, header: &mut CHeader) {
+    let text = normalize_whitespace(&decl.text);
+    let (is_typedef, rest) = match text.strip_prefix("typedef ") {
+        Some(rest) => (true, rest.trim()),
+        None => (false, text.as_str()),
+    };
+
+    let make_type = |name: String, kind: CTypeKind| CType {
+        name,
+        kind,
+        fields: Vec::new(),
+        variants: Vec::new(),
+        aliased: None,
+        typedef: is_typedef,
+        line_start: decl.line_start,
+        line_end: decl.line_end,
+        conditional: decl.conditional,
+    };
+
+    // struct/union/enum definitions and forward declarations
+    for (keyword, kind) in [("struct", CTypeKind::Struct), ("union", CTypeKind::Union), ("enum", CTypeKind::Enum)] {
+        let Some(after_keyword) = rest.strip_prefix(keyword).filter(|r| r.starts_with([' ', '{'])) else { continue };
+
+        if let (Some(open), Some(close)) = (after_keyword.find('{'), after_keyword.rfind('}')) {
+            let tag = after_keyword[..open].trim();
+            let body = &after_keyword[open + 1..close];
+            let alias = after_keyword[close + 1..].trim();
+            let name = if is_typedef && !alias.is_empty() { alias } else { tag };
+            if name.is_empty() {
+                return;
+            }
+
+            let mut ty = make_type(name.to_string(), kind.clone());
+            if kind == CTypeKind::Enum {
+                ty.variants = parse_enum_variants(body);
+            } else {
+                ty.fields = parse_fields(body);
+            }
+            header.types.push(ty);
+            return;
+        }
+
+        if !is_typedef {
+            // `struct Foo;` forward declaration
+            if !after_keyword.contains('(') {
+                header.types.push(make_type(after_keyword.trim().to_string(), CTypeKind::Opaque));
+                return;
+            }
+        } else if let Some((tag, alias)) = after_keyword.trim().split_once(' ')
+            && !alias.contains(['(', '*'])
+        {
+            // `typedef struct Foo Foo;` is how cbindgen declares opaque types
+            let kind = if tag == alias { CTypeKind::Opaque } else { CTypeKind::Typedef };
+            let mut ty = make_type(alias.trim().to_string(), kind);
+            ty.aliased = Some(format!("{} {}", keyword, tag));
+            header.types.push(ty);
+            return;
+        }
+    }
+
+    if is_typedef {
+        let param = parse_param(rest);
+        if let Some(name) = param.name {
+            let mut ty = make_type(name, CTypeKind::Typedef);
+            ty.aliased = Some(param.ty);
+            header.types.push(ty);
+        }
+        return;
+    }
+
+    if let Some(function) = parse_function(&text, decl) {
+        header.functions.push(function);
+    }
+}
+
+fn parse_function(text: &str, decl: &Declaration) -> Option<CFunction> {
+    let text = text.strip_prefix("extern ").unwrap_or(text);
+    let open = text.find('(')?;
+    let close = matching_paren(text, open)?;
+
+    let before = text[..open].trim();
+    let name_start = before.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |i| i + 1);
+    let name = &before[name_start..];
+    if name.is_empty() {
+        return None;
+    }
+
+    let return_type = normalize_type(strip_attribute_macros(&before[..name_start]));
+    let params_text = text[open + 1..close].trim();
+    let params = if params_text.is_empty() || params_text == "void" {
+        Vec::new()
+    } else {
+        split_top_level(params_text, ',').iter().map(|p| parse_param(p)).collect()
+    };
+
+    Some(CFunction {
+        name: name.to_string(),
+        return_type,
+        params,
+        line_start: decl.line_start,
+        line_end: decl.line_end,
+        conditional: decl.conditional,
+    })
+}
+
+fn parse_fields(body: &str) -> Vec<CField> {
+    split_top_level(body, ';')
+        .iter()
+        .map(|field| field.trim())
+        .filter(|field| !field.is_empty())
+        .filter_map(|field| {
+            let (decl, array_len) = match (field.rfind('['), field.rfind(']')) {
+                (Some(open), Some(close)) if close > open => {
+                    (field[..open].trim(), field[open + 1..close].trim().parse().ok())
+                }
+                _ => (field, None),
+            };
+            let param = parse_param(decl);
+            Some(CField {
+                name: param.name?,
+                ty: param.ty,
+                array_len,
+            })
+        })
+        .collect()
+}
+
+fn parse_enum_variants(body: &str) -> Vec<(String, Option<i64>)> {
+    split_top_level(body, ',')
+        .iter()
+        .map(|variant| variant.trim())
+        .filter(|variant| !variant.is_empty())
+        .map(|variant| match variant.split_once('=') {
+            Some((name, value)) => (name.trim().to_string(), parse_int(value.trim())),
+            None => (variant.to_string(), None),
+        })
+        .collect()
+}
+
+fn parse_int(value: &str) -> Option<i64> {
+    let value = value.trim_end_matches(['u', 'U', 'l', 'L']);
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    let parsed = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse().ok()?,
+    };
+    Some(if negative { -parsed } else { parsed })
+}
+
+/// Splits a declarator such as `const char *name` into its name and type.
+fn parse_param(param: &str) -> CParam {
+    let param = param.trim();
+
+    // Function pointers: `void (*name)(int32_t)`
+    if let Some(start) = param.find("(*") {
+        let end = param[start..].find(')').map_or(param.len(), |i| start + i);
+        let name = param[start + 2..end].trim().to_string();
+        let args = param[end + 1..].trim().trim_start_matches('(').trim_end_matches(')');
+        let args: Vec<String> = split_top_level(args, ',').iter().map(|a| parse_param(a).ty).collect();
+        let ty = format!("{}(*)({})", normalize_type(&param[..start]), args.join(", "));
+        return CParam {
+            name: (!name.is_empty()).then_some(name),
+            ty,
+        };
+    }
+
+    let name_start = param.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |i| i + 1);
+    let (ty, name) = param.split_at(name_start);
+
+    // A lone identifier or a keyword at the end is part of the type, not a name
+    if name.is_empty() || ty.trim().is_empty() || ["struct", "union", "enum"].iter().any(|kw| ty.trim_end().ends_with(kw)) || is_type_keyword(name) {
+        return CParam { name: None, ty: normalize_type(param) };
+    }
+
+    CParam {
+        name: Some(name.to_string()),
+        ty: normalize_type(ty),
+    }
+}
+
+fn is_type_keyword(word: &str) -> bool {
+    matches!(
+        word,
+        "void" | "char" | "short" | "int" | "long" | "float" | "double" | "signed" | "unsigned" | "const" | "volatile"
+    )
+}
+
+/// Collapses whitespace and attaches `*` to the type, e.g. `char * const` -> `char* const`.
+/// Drops what precedes the return type without being part of it: macros such as the
+/// `MY_CRATE_MUST_USE` that cbindgen's `[fn] must_use` adds, and `[[...]]` attributes.
+fn strip_attribute_macros(mut ty: &str) -> &str {
+    loop {
+        ty = ty.trim();
+        if let Some(rest) = ty.strip_prefix("[[")
+            && let Some(end) = rest.find("]]")
+        {
+            ty = &rest[end + 2..];
+            continue;
+        }
+
+        // An all-caps word is a macro only when a type follows it, not `HANDLE *` or `FOO const`
+        let Some((first, rest)) = ty.split_once(char::is_whitespace) else { return ty };
+        let is_macro = first.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+            && first.starts_with(|c: char| c.is_ascii_uppercase());
+        let rest = rest.trim_start();
+        let type_follows = rest.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && !rest.starts_with("const")
+            && !rest.starts_with("volatile");
+        if !is_macro || !type_follows {
+            return ty;
+        }
+        ty = rest;
+    }
+}
+
+fn normalize_type(ty: &str) -> String {
+    let spaced = normalize_whitespace(&ty.replace('*', " * "));
+    spaced.replace(" *", "*").trim().to_string()
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn matching_paren(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_top_level(text: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+
+    for c in text.chars() {
+        match c {
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth -= 1,
+            _ => {}
+        }
+        if c == separator && depth == 0 {
+            parts.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.trim().is_empty() {
+        parts.push(current);
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = r#"/* Generated with cbindgen:0.29.0 */
+
+#include <stdint.h>
+
+#define GHOSTBIND_CFG_UNIX
+
+typedef enum ErrorCode {
+  Success = 0,
+  InvalidInput = 1,
+  NotFound,
+} ErrorCode;
+
+typedef struct Handle Handle;
+
+/**
+ * A simple struct to test FFI
+ */
+typedef struct Point {
+  double x;
+  double y;
+  uint8_t tag[4];
+} Point;
+
+typedef void (*Callback)(int32_t value);
+
+int32_t add(int32_t a, int32_t b);
+
+double distance(struct Point p1,
+                struct Point p2);
+
+void free_string(char *s);
+
+#if defined(GHOSTBIND_CFG_UNIX)
+int32_t unix_only(void);
+#endif
+
+#if defined(GHOSTBIND_CFG_TARGET_OS_WINDOWS)
+int32_t windows_only(void);
+#endif
+"#;
+
+    #[test]
+    fn test_parse_functions() {
+        let header = parse_header(HEADER);
+
+        let names: Vec<&str> = header.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["add", "distance", "free_string", "unix_only"]);
+
+        let distance = header.function("distance").unwrap();
+        assert_eq!(distance.signature(), "double distance(struct Point, struct Point)");
+        assert_eq!((distance.line_start, distance.line_end), (28, 29));
+
+        let free_string = header.function("free_string").unwrap();
+        assert_eq!(free_string.params[0], CParam { name: Some("s".to_string()), ty: "char*".to_string() });
+        assert!(header.function("unix_only").unwrap().params.is_empty());
+
+        let must_use = parse_header("MY_CRATE_MUST_USE int32_t parse(const char *s);\n[[nodiscard]] HANDLE *open_handle(void);\n");
+        assert_eq!(must_use.functions[0].signature(), "int32_t parse(const char*)");
+        assert_eq!(must_use.functions[1].return_type, "HANDLE*");
+    }
+
+    #[test]
+    fn test_parse_types() {
+        let header = parse_header(HEADER);
+
+        let point = header.type_named("Point").unwrap();
+        assert_eq!(point.kind, CTypeKind::Struct);
+        assert_eq!(point.fields.len(), 3);
+        assert_eq!(point.fields[2], CField { name: "tag".to_string(), ty: "uint8_t".to_string(), array_len: Some(4) });
+        assert!(point.typedef);
+
+        let error_code = header.type_named("ErrorCode").unwrap();
+        assert_eq!(error_code.kind, CTypeKind::Enum);
+        assert_eq!(error_code.variants[2], ("NotFound".to_string(), None));
+
+        assert_eq!(header.type_named("Handle").unwrap().kind, CTypeKind::Opaque);
+        assert_eq!(header.type_named("Callback").unwrap().aliased.as_deref(), Some("void(*)(int32_t)"));
+    }
+
+    #[test]
+    fn test_preprocessor_conditions() {
+        let header = parse_header(HEADER);
+
+        assert!(!header.function("unix_only").unwrap().conditional);
+        assert!(header.function("windows_only").is_none());
+
+        let merged = parse_header(r#"
+#ifndef MY_CRATE_H
+#define MY_CRATE_H
+#if defined(__linux__)
+#define GHOSTBIND_CFG_UNIX
+#endif
+#ifdef __cplusplus
+extern "C" {
+#endif
+#if defined(GHOSTBIND_CFG_UNIX)
+int32_t unix_only(void);
+#else
+int32_t other(void);
+#endif
+int32_t always(void);
+#ifdef __cplusplus
+}
+#endif
+#endif
+"#);
+
+        assert!(merged.function("unix_only").unwrap().conditional);
+        assert!(merged.function("other").unwrap().conditional);
+        assert!(!merged.function("always").unwrap().conditional);
+        assert_eq!(merged.functions.len(), 3);
+    }
+
+    #[test]
+    fn test_item_at_line() {
+        let header = parse_header(HEADER);
+
+        assert_eq!(header.item_at_line(21), Some("Point"));
+        assert_eq!(header.item_at_line(29), Some("distance"));
+        assert_eq!(header.item_at_line(3), None);
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::artifact_discovery::LibraryNames;
+use crate::manifest::BuildManifest;
+use crate::package_config::PackageConfigGenerator;
+
+/// Installs a built crate into a standard prefix layout:
+///
+/// - `lib/` - the library under its platform name (`lib<crate>.a`, `<crate>.lib`, ...)
+/// - `bin/` - the DLL of a Windows cdylib
+/// - `include/<crate>/` - the headers
+/// - `share/pkgconfig/`, `share/cmake/<crate>/` - pkg-config and CMake package files
+/// - `share/doc/<crate>/` - THIRD_PARTY_NOTICES and the SBOM, when the build wrote them
+/// - `share/ghostbind/` - the manifest, pointing at the installed files
+pub struct Installer {
+    prefix: PathBuf,
+    destdir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InstallReport {
+    /// Every file written, including the DESTDIR
+    pub files: Vec<PathBuf>,
+    pub manifest: PathBuf,
+}
+
+impl Installer {
+    pub fn new(prefix: impl AsRef<Path>) -> Self {
+        Self {
+            prefix: prefix.as_ref().to_path_buf(),
+            destdir: None,
+        }
+    }
+
+    /// Stages the install under `destdir` while the installed files still refer to the prefix.
+    pub fn destdir(mut self, destdir: Option<PathBuf>) -> Self {
+        self.destdir = destdir;
+        self
+    }
+
+    pub fn install(&self, manifest: &BuildManifest) -> Result<InstallReport> {
+        let prefix = std::path::absolute(&self.prefix)
+            .with_context(|| format!("Invalid prefix: {}", self.prefix.display()))?;
+        let root = staging_root(&prefix, self.destdir.as_deref());
+        let mut report = InstallReport::default();
+        let mut installed = manifest.clone();
+
+        let names = LibraryNames::for_target(&manifest.crate_name, &manifest.kind, &manifest.rustc_target);
+        let library_dir = if names.import_library.is_some() { "bin" } else { "lib" };
+        installed.artifact = prefix.join(library_dir).join(&names.library);
+        copy_file(&manifest.artifact, &root.join(library_dir).join(&names.library), &mut report)?;
+
+        if let Some(import_library) = &names.import_library {
+            // Cached next to the DLL by artifact discovery
+            let source = manifest.artifact.with_file_name(import_library);
+            copy_file(&source, &root.join("lib").join(import_library), &mut report)?;
+        }
+
+        let include_dir = Path::new("include").join(&manifest.crate_name);
+        installed.headers.clear();
+        for header in &manifest.headers {
+            let file_name = header.file_name()
+                .with_context(|| format!("Invalid header path: {}", header.display()))?;
+            copy_file(header, &root.join(&include_dir).join(file_name), &mut report)?;
+            installed.headers.push(prefix.join(&include_dir).join(file_name));
+        }
+        for (header_file, installed_file) in manifest.header_files.iter().zip(&mut installed.header_files) {
+            let file_name = header_file.path.file_name()
+                .with_context(|| format!("Invalid header path: {}", header_file.path.display()))?;
+            if !manifest.headers.contains(&header_file.path) {
+                copy_file(&header_file.path, &root.join(&include_dir).join(file_name), &mut report)?;
+            }
+            installed_file.path = prefix.join(&include_dir).join(file_name);
+        }
+        // Next to the C header it includes
+        if let Some(wrappers) = &manifest.cxx_wrappers {
+            let file_name = wrappers.file_name()
+                .with_context(|| format!("Invalid C++ wrapper path: {}", wrappers.display()))?;
+            copy_file(wrappers, &root.join(&include_dir).join(file_name), &mut report)?;
+            installed.cxx_wrappers = Some(prefix.join(&include_dir).join(file_name));
+        }
+
+        let share_dir = Path::new("share").join("ghostbind");
+        if let Some(wrappers) = &manifest.zig_wrappers {
+            let file_name = wrappers.file_name()
+                .with_context(|| format!("Invalid Zig wrapper path: {}", wrappers.display()))?;
+            copy_file(wrappers, &root.join(&share_dir).join(file_name), &mut report)?;
+            installed.zig_wrappers = Some(prefix.join(&share_dir).join(file_name));
+        }
+
+        let doc_dir = Path::new("share").join("doc").join(&manifest.crate_name);
+        if let Some(notices) = &manifest.third_party_notices {
+            copy_file(notices, &root.join(&doc_dir).join("THIRD_PARTY_NOTICES"), &mut report)?;
+            installed.third_party_notices = Some(prefix.join(&doc_dir).join("THIRD_PARTY_NOTICES"));
+        }
+        if let Some(sbom) = &manifest.sbom {
+            let file_name = sbom.file_name()
+                .with_context(|| format!("Invalid SBOM path: {}", sbom.display()))?;
+            copy_file(sbom, &root.join(&doc_dir).join(file_name), &mut report)?;
+            installed.sbom = Some(prefix.join(&doc_dir).join(file_name));
+        }
+
+        // Relocatable, so they can be written straight into the staging root
+        let package_files = PackageConfigGenerator::new()
+            .prefix(root.clone())
+            .generate(manifest)?;
+        report.files.extend([package_files.pkg_config, package_files.cmake_config, package_files.cmake_version]);
+
+        let manifest_path = root.join(&share_dir).join(format!("{}-manifest.json", manifest.crate_name));
+        fs::create_dir_all(root.join(&share_dir))
+            .with_context(|| format!("Failed to create directory: {}", root.join(&share_dir).display()))?;
+        let content = serde_json::to_string_pretty(&installed)
+            .context("Failed to serialize manifest to JSON")?;
+        fs::write(&manifest_path, content)
+            .with_context(|| format!("Failed to write manifest to {}", manifest_path.display()))?;
+        report.files.push(manifest_path.clone());
+        report.manifest = manifest_path;
+
+        Ok(report)
+    }
+}
+
+/// `$DESTDIR/$prefix`, or the prefix itself without a DESTDIR.
+fn staging_root(prefix: &Path, destdir: Option<&Path>) -> PathBuf {
+    match destdir {
+        Some(destdir) => {
+            let relative: PathBuf = prefix.components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .collect();
+            destdir.join(relative)
+        }
+        None => prefix.to_path_buf(),
+    }
+}
+
+fn copy_file(source: &Path, destination: &Path, report: &mut InstallReport) -> Result<()> {
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    fs::copy(source, destination)
+        .with_context(|| format!("Failed to copy {} to {}", source.display(), destination.display()))?;
+
+    report.files.push(destination.to_path_buf());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staging_root() {
+        let prefix = Path::new("/usr/local");
+
+        assert_eq!(staging_root(prefix, None), PathBuf::from("/usr/local"));
+        assert_eq!(staging_root(prefix, Some(Path::new("/tmp/stage"))), PathBuf::from("/tmp/stage/usr/local"));
+    }
+}
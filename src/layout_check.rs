@@ -0,0 +1,486 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use syn::visit::Visit;
+
+use crate::cargo_integration::{BuildProfile, CargoBuilder, CrateInfo};
+use crate::error::Error;
+use crate::header_parser::{parse_header_file, CHeader, CType, CTypeKind};
+
+/// Checks that the C view of each exported `#[repr(C)]` type matches what rustc computes.
+///
+/// A generated Rust program prints `size_of`/`align_of`/`offset_of` for every type as C11
+/// static assertions, which are then compiled against the generated headers.
+pub struct LayoutVerifier {
+    cache_dir: PathBuf,
+    type_prefix: Option<String>,
+    features: Vec<String>,
+    no_default_features: bool,
+    profile: BuildProfile,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LayoutReport {
+    /// Types whose layouts were compared
+    pub checked: Vec<String>,
+    /// Types that exist in the headers but can't be named from outside the crate
+    pub skipped: Vec<String>,
+    pub mismatches: Vec<String>,
+}
+
+/// A `#[repr(C)]` Rust type that also appears in the headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ExportedType {
+    /// Path from the crate root, e.g. `geometry::Point`
+    rust_path: String,
+    /// How C code names it, e.g. `Point` or `struct Point`
+    c_name: String,
+    fields: Vec<String>,
+}
+
+impl LayoutVerifier {
+    pub fn new() -> Self {
+        Self {
+            cache_dir: PathBuf::from(".ghostbind/cache"),
+            type_prefix: None,
+            features: Vec::new(),
+            no_default_features: false,
+            profile: BuildProfile::Release,
+        }
+    }
+
+    /// The cbindgen `[export] prefix`, which the headers put in front of type names.
+    pub fn type_prefix(mut self, type_prefix: Option<String>) -> Self {
+        self.type_prefix = type_prefix;
+        self
+    }
+
+    pub fn features(mut self, features: Vec<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn no_default_features(mut self, no_default: bool) -> Self {
+        self.no_default_features = no_default;
+        self
+    }
+
+    /// The profile the crate is built with, so `cfg(debug_assertions)` fields match the artifact.
+    pub fn profile(mut self, profile: BuildProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Verifies layouts for a host build; the harness goes in the cache directory of `target_triple`.
+    pub fn verify(&self, crate_info: &CrateInfo, headers: &[PathBuf], target_triple: Option<&str>) -> Result<LayoutReport> {
+        let mut header = CHeader::default();
+        for path in headers {
+            let parsed = parse_header_file(path)?;
+            header.types.extend(parsed.types);
+        }
+
+        let sources = crate_info.lib_source_files()?;
+        let lib_target = crate_info.lib_target()?;
+        let src_dir = lib_target.src_path.parent()
+            .ok_or_else(|| anyhow!("Library source {} has no parent directory", lib_target.src_path.display()))?;
+        let prefix = self.type_prefix.as_deref().unwrap_or_default();
+        let (types, skipped) = exported_types(&sources, src_dir, &header, prefix)?;
+
+        let mut report = LayoutReport {
+            skipped,
+            ..Default::default()
+        };
+        if types.is_empty() {
+            return Ok(report);
+        }
+
+        let layout_dir = self.cache_dir.join(target_triple.unwrap_or("native")).join("layout");
+        fs::create_dir_all(&layout_dir)
+            .with_context(|| format!("Failed to create layout directory: {}", layout_dir.display()))?;
+        let layout_dir = layout_dir.canonicalize()?;
+
+        let lib_name = lib_target.name.replace('-', "_");
+        let target_dir = layout_dir.join("target");
+        let rlib = CargoBuilder::new(crate_info.manifest_dir.join("Cargo.toml"))
+            .profile(self.profile.clone())
+            .features(self.features.clone())
+            .no_default_features(self.no_default_features)
+            .build_rlib(&lib_name, &target_dir)?;
+
+        let asserts = self.run_rust_harness(&layout_dir, &lib_name, &rlib, &target_dir, &types)?;
+        report.mismatches = self.compile_c_harness(&layout_dir, &crate_info.name, headers, &asserts)?;
+        report.checked = types.into_iter().map(|t| t.rust_path).collect();
+
+        Ok(report)
+    }
+
+    fn run_rust_harness(
+        &self,
+        layout_dir: &Path,
+        lib_name: &str,
+        rlib: &Path,
+        target_dir: &Path,
+        types: &[ExportedType],
+    ) -> Result<String> {
+        let source_path = layout_dir.join("layout.rs");
+        fs::write(&source_path, rust_harness(lib_name, types))
+            .with_context(|| format!("Failed to write {}", source_path.display()))?;
+
+        let binary = layout_dir.join("layout");
+        let output = Command::new("rustc")
+            .args(["--edition", "2021", "--crate-name", "ghostbind_layout"])
+            .arg(&source_path)
+            .arg("--extern").arg(format!("{}={}", lib_name, rlib.display()))
+            .arg("-L").arg(format!("dependency={}", target_dir.join(self.profile.as_str()).join("deps").display()))
+            .arg("-o").arg(&binary)
+            .output()
+            .context("Failed to run rustc")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("Failed to compile the Rust layout harness: {}", stderr));
+        }
+
+        let output = Command::new(&binary).output()
+            .with_context(|| format!("Failed to run {}", binary.display()))?;
+        if !output.status.success() {
+            return Err(anyhow!("Rust layout harness failed: {}", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Compiles the static assertions and returns the ones that failed.
+    fn compile_c_harness(&self, layout_dir: &Path, crate_name: &str, headers: &[PathBuf], asserts: &str) -> Result<Vec<String>> {
+        let mut source = String::new();
+        writeln!(source, "/* Generated by ghostbind to verify the layouts of {} */", crate_name)?;
+        writeln!(source, "#include <stddef.h>")?;
+        for header in headers {
+            let header = header.canonicalize()
+                .with_context(|| format!("Header file does not exist: {}", header.display()))?;
+            writeln!(source, "#include \"{}\"", header.display())?;
+        }
+        source.push_str(asserts);
+
+        let source_path = layout_dir.join("layout.c");
+        fs::write(&source_path, source)
+            .with_context(|| format!("Failed to write {}", source_path.display()))?;
+
+        let mut cc = match (which::which("cc"), which::which("zig")) {
+            (Ok(cc), _) => Command::new(cc),
+            (Err(_), Ok(zig)) => {
+                let mut cmd = Command::new(zig);
+                cmd.arg("cc");
+                cmd
+            }
+            (Err(_), Err(_)) => return Err(Error::ToolMissing("No C compiler found. Install a C compiler (cc) or zig to verify layouts".to_string()).into()),
+        };
+        let output = cc.args(["-x", "c", "-std=c11", "-fsyntax-only"])
+            .arg(&source_path)
+            .output()
+            .context("Failed to run the C compiler")?;
+
+        if output.status.success() {
+            return Ok(Vec::new());
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mismatches: Vec<String> = stderr.lines()
+            .filter_map(static_assert_message)
+            .collect();
+
+        if mismatches.is_empty() {
+            return Err(anyhow!("Failed to compile the C layout harness: {}", stderr));
+        }
+        Ok(mismatches)
+    }
+}
+
+impl Default for LayoutVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Extracts our message from a failed static assertion as reported by gcc or clang:
+///
+/// - gcc: `error: static assertion failed: "Point: size is 16 in Rust"`
+/// - clang: `error: static assertion failed due to requirement 'sizeof(Point) == 24': Point: size is 16 in Rust`
+fn static_assert_message(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("static assertion failed")?;
+    let message = match (rest.find('"'), rest.rfind('"')) {
+        (Some(start), Some(end)) if start < end => &rest[start + 1..end],
+        _ => rest.split_once("': ").map_or(rest.trim_start_matches(':'), |(_, message)| message),
+    };
+    Some(message.trim().to_string())
+}
+
+/// A Rust program that prints one C static assertion per size, alignment and field offset.
+fn rust_harness(lib_name: &str, types: &[ExportedType]) -> String {
+    let mut source = String::new();
+    source.push_str("// Generated by ghostbind to verify struct layouts\n");
+    source.push_str("use std::mem::{align_of, offset_of, size_of};\n\nfn main() {\n");
+
+    for ty in types {
+        let path = format!("{}::{}", lib_name, ty.rust_path);
+        let _ = writeln!(
+            source,
+            "    println!(\"_Static_assert(sizeof({c}) == {{0}}, \\\"{name}: size is {{0}} in Rust\\\");\", size_of::<{path}>());",
+            c = ty.c_name,
+            name = ty.rust_path,
+        );
+        let _ = writeln!(
+            source,
+            "    println!(\"_Static_assert(_Alignof({c}) == {{0}}, \\\"{name}: alignment is {{0}} in Rust\\\");\", align_of::<{path}>());",
+            c = ty.c_name,
+            name = ty.rust_path,
+        );
+        for field in &ty.fields {
+            let _ = writeln!(
+                source,
+                "    println!(\"_Static_assert(offsetof({c}, {field}) == {{0}}, \\\"{name}.{field}: offset is {{0}} in Rust\\\");\", offset_of!({path}, {field}));",
+                c = ty.c_name,
+                name = ty.rust_path,
+            );
+        }
+    }
+
+    source.push_str("}\n");
+    source
+}
+
+/// Finds public `#[repr(C)]` types reachable from the crate root that the headers declare.
+fn exported_types(sources: &[PathBuf], src_dir: &Path, header: &CHeader, prefix: &str) -> Result<(Vec<ExportedType>, Vec<String>)> {
+    let mut collector = ReprCCollector::default();
+
+    for source_file in sources {
+        let source = fs::read_to_string(source_file)
+            .with_context(|| format!("Failed to read {}", source_file.display()))?;
+        let file = syn::parse_file(&source)
+            .with_context(|| format!("Failed to parse {}", source_file.display()))?;
+        collector.module = module_path(source_file, src_dir);
+        collector.visit_file(&file);
+    }
+
+    let mut types = Vec::new();
+    let mut skipped = Vec::new();
+    for item in collector.items {
+        let Some(c_type) = header.type_named(&format!("{}{}", prefix, item.name))
+            .filter(|t| matches!(t.kind, CTypeKind::Struct | CTypeKind::Union | CTypeKind::Enum))
+        else {
+            continue;
+        };
+
+        let reachable = item.module.iter().enumerate()
+            .all(|(depth, _)| collector.public_modules.get(&item.module[..=depth].join("::")).copied().unwrap_or(false));
+        let rust_path = item.module.iter().chain([&item.name]).cloned().collect::<Vec<_>>().join("::");
+        if !reachable {
+            skipped.push(rust_path);
+            continue;
+        }
+
+        // offset_of! on unions is unstable, so only struct fields are compared
+        let fields = if c_type.kind == CTypeKind::Struct {
+            item.fields.into_iter().filter(|f| c_type.fields.iter().any(|c| &c.name == f)).collect()
+        } else {
+            Vec::new()
+        };
+
+        types.push(ExportedType {
+            rust_path,
+            c_name: c_name(c_type, header),
+            fields,
+        });
+    }
+
+    Ok((types, skipped))
+}
+
+/// The expression C code uses to name a header type.
+fn c_name(ty: &CType, header: &CHeader) -> String {
+    let aliased = header.types.iter().any(|t| t.name == ty.name && t.kind == CTypeKind::Typedef);
+    if ty.typedef || aliased {
+        return ty.name.clone();
+    }
+
+    let keyword = match ty.kind {
+        CTypeKind::Union => "union",
+        CTypeKind::Enum => "enum",
+        _ => "struct",
+    };
+    format!("{} {}", keyword, ty.name)
+}
+
+/// The module path of a source file: `src/lib.rs` is the root, `src/a/b.rs` and `src/a/b/mod.rs` are `a::b`.
+fn module_path(source_file: &Path, src_dir: &Path) -> Vec<String> {
+    let Ok(relative) = source_file.strip_prefix(src_dir) else { return Vec::new() };
+    let mut parts: Vec<String> = relative.with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    if parts.last().is_some_and(|last| last == "mod" || last == "lib") {
+        parts.pop();
+    }
+    parts
+}
+
+struct ReprCItem {
+    module: Vec<String>,
+    name: String,
+    fields: Vec<String>,
+}
+
+#[derive(Default)]
+struct ReprCCollector {
+    module: Vec<String>,
+    items: Vec<ReprCItem>,
+    /// `a::b` -> whether `mod b` inside `a` is `pub`
+    public_modules: HashMap<String, bool>,
+}
+
+impl ReprCCollector {
+    fn record(&mut self, vis: &syn::Visibility, ident: &syn::Ident, generics: &syn::Generics, attrs: &[syn::Attribute], fields: Vec<String>) {
+        let is_repr_c = attrs.iter()
+            .filter(|attr| attr.path().is_ident("repr"))
+            .any(|attr| {
+                let mut repr_c = false;
+                let _ = attr.parse_nested_meta(|meta| {
+                    repr_c |= meta.path.is_ident("C");
+                    if meta.input.peek(syn::token::Paren) {
+                        let _content;
+                        syn::parenthesized!(_content in meta.input);
+                    }
+                    Ok(())
+                });
+                repr_c
+            });
+
+        if is_repr_c && matches!(vis, syn::Visibility::Public(_)) && generics.params.is_empty() {
+            self.items.push(ReprCItem {
+                module: self.module.clone(),
+                name: ident.to_string(),
+                fields,
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ReprCCollector {
+    fn visit_item_struct(&mut self, item: &'ast syn::ItemStruct) {
+        let fields = item.fields.iter()
+            .filter(|f| matches!(f.vis, syn::Visibility::Public(_)))
+            .filter_map(|f| f.ident.as_ref().map(|i| i.to_string()))
+            .collect();
+        self.record(&item.vis, &item.ident, &item.generics, &item.attrs, fields);
+    }
+
+    fn visit_item_enum(&mut self, item: &'ast syn::ItemEnum) {
+        self.record(&item.vis, &item.ident, &item.generics, &item.attrs, Vec::new());
+    }
+
+    fn visit_item_union(&mut self, item: &'ast syn::ItemUnion) {
+        self.record(&item.vis, &item.ident, &item.generics, &item.attrs, Vec::new());
+    }
+
+    fn visit_item_mod(&mut self, item: &'ast syn::ItemMod) {
+        self.module.push(item.ident.to_string());
+        self.public_modules.insert(self.module.join("::"), matches!(item.vis, syn::Visibility::Public(_)));
+        if let Some((_, items)) = &item.content {
+            for nested in items {
+                self.visit_item(nested);
+            }
+        }
+        self.module.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header_parser::parse_header;
+
+    #[test]
+    fn test_exported_types() {
+        let dir = std::env::temp_dir().join(format!("ghostbind-layout-{}", std::process::id()));
+        fs::create_dir_all(dir.join("shapes")).unwrap();
+        fs::write(dir.join("lib.rs"), r#"
+pub mod shapes;
+mod hidden { #[repr(C)] pub struct Secret { pub x: u8 } }
+
+#[repr(C)]
+pub struct Point { pub x: f64, pub y: f64 }
+
+pub struct NotC { pub x: f64 }
+"#).unwrap();
+        fs::write(dir.join("shapes").join("mod.rs"), "#[repr(C)] pub struct Rect { pub w: u32, h: u32 }\n").unwrap();
+
+        let header = parse_header(r#"
+typedef struct Point { double x; double y; } Point;
+struct Rect { uint32_t w; uint32_t h; };
+typedef struct Secret { uint8_t x; } Secret;
+typedef struct NotC NotC;
+"#);
+        let sources = vec![dir.join("lib.rs"), dir.join("shapes").join("mod.rs")];
+        let (types, skipped) = exported_types(&sources, &dir, &header, "").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(types, vec![
+            ExportedType { rust_path: "Point".to_string(), c_name: "Point".to_string(), fields: vec!["x".to_string(), "y".to_string()] },
+            ExportedType { rust_path: "shapes::Rect".to_string(), c_name: "struct Rect".to_string(), fields: vec!["w".to_string()] },
+        ]);
+        assert_eq!(skipped, vec!["hidden::Secret".to_string()]);
+    }
+
+    #[test]
+    fn test_exported_types_with_prefix() {
+        let dir = std::env::temp_dir().join(format!("ghostbind-layout-prefix-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.rs"), "#[repr(C)] pub struct Point { pub x: f64, pub y: f64 }\n").unwrap();
+
+        // The unprefixed name is some other type, not the crate's
+        let header = parse_header(r#"
+typedef struct MyCratePoint { double x; double y; } MyCratePoint;
+typedef struct Point { int x; } Point;
+"#);
+        let (types, skipped) = exported_types(&[dir.join("lib.rs")], &dir, &header, "MyCrate").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(types, vec![
+            ExportedType { rust_path: "Point".to_string(), c_name: "MyCratePoint".to_string(), fields: vec!["x".to_string(), "y".to_string()] },
+        ]);
+        assert!(skipped.is_empty());
+
+        let harness = rust_harness("my_crate", &types);
+        assert!(harness.contains("size_of::<my_crate::Point>()"));
+        assert!(harness.contains("offsetof(MyCratePoint, y)"));
+    }
+
+    #[test]
+    fn test_static_assert_message() {
+        let gcc = r#"layout.c:4:1: error: static assertion failed: "Point: size is 16 in Rust""#;
+        let clang = "layout.c:4:1: error: static assertion failed due to requirement 'sizeof(Point) == 24': Point: size is 16 in Rust";
+
+        assert_eq!(static_assert_message(gcc).as_deref(), Some("Point: size is 16 in Rust"));
+        assert_eq!(static_assert_message(clang).as_deref(), Some("Point: size is 16 in Rust"));
+        assert_eq!(static_assert_message("    4 | _Static_assert(sizeof(Point) == 24, \"x\");"), None);
+    }
+
+    #[test]
+    fn test_rust_harness() {
+        let types = vec![ExportedType {
+            rust_path: "Point".to_string(),
+            c_name: "Point".to_string(),
+            fields: vec!["x".to_string()],
+        }];
+
+        let harness = rust_harness("my_crate", &types);
+
+        assert!(harness.contains("size_of::<my_crate::Point>()"));
+        assert!(harness.contains("offset_of!(my_crate::Point, x)"));
+        assert!(harness.contains(r#"_Static_assert(offsetof(Point, x) == {0}, \"Point.x: offset is {0} in Rust\");"#));
+    }
+}
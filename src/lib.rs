@@ -1,8 +1,33 @@
+pub mod config;
+pub mod error;
 pub mod target_mapping;
+pub mod target_cfg;
 pub mod cargo_integration;
 pub mod artifact_discovery;
 pub mod header_generation;
+pub mod header_parser;
+pub mod header_check;
+pub mod symbol_check;
+pub mod abi;
+pub mod lint;
+pub mod licenses;
+pub mod sbom;
+pub mod provenance;
+pub mod reproducible;
+pub mod layout_check;
+pub mod smoke_test;
 pub mod manifest;
+pub mod package_config;
+pub mod install;
+pub mod package;
+pub mod zig_module;
+pub mod zig_wrappers;
+pub mod cxx_wrappers;
+pub mod scaffold;
+pub mod pipeline;
+pub mod watch;
 pub mod cli;
 
-pub use manifest::BuildManifest;
\ No newline at end of file
+pub use error::Error;
+pub use manifest::BuildManifest;
+pub use pipeline::{BuildOutcome, Event, Pipeline};
\ No newline at end of file
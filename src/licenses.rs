@@ -0,0 +1,224 @@
+use anyhow::{Context, Result};
+use cargo_metadata::{Metadata, Package};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cargo_integration::LinkedDependencies;
+
+/// Collects the licenses of every dependency linked into a crate's artifact and writes
+/// them out as a THIRD_PARTY_NOTICES file and a JSON summary.
+pub struct LicenseCollector {
+    cache_dir: PathBuf,
+    deny: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageLicense {
+    pub name: String,
+    pub version: String,
+    /// The SPDX license expression from Cargo.toml
+    pub license: Option<String>,
+    pub license_files: Vec<PathBuf>,
+    pub repository: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LicenseReport {
+    pub crate_name: String,
+    pub rustc_target: String,
+    pub packages: Vec<PackageLicense>,
+    /// `name version` of packages whose license is only available under denied terms
+    pub denied: Vec<String>,
+    /// `name version` of packages that declare no license at all
+    pub unknown: Vec<String>,
+    #[serde(skip)]
+    pub notices: PathBuf,
+    #[serde(skip)]
+    pub summary: PathBuf,
+}
+
+impl LicenseCollector {
+    pub fn new() -> Self {
+        Self {
+            cache_dir: PathBuf::from(".ghostbind/cache"),
+            deny: Vec::new(),
+        }
+    }
+
+    pub fn deny(mut self, deny: Vec<String>) -> Self {
+        self.deny = deny;
+        self
+    }
+
+    /// `metadata` should be resolved for `rustc_target` so only dependencies of that platform
+    /// are included.
+    pub fn generate(&self, crate_name: &str, metadata: &Metadata, rustc_target: &str) -> Result<LicenseReport> {
+        let linked = LinkedDependencies::resolve(crate_name, metadata)?;
+
+        let mut report = LicenseReport {
+            crate_name: crate_name.to_string(),
+            rustc_target: rustc_target.to_string(),
+            ..LicenseReport::default()
+        };
+        for package in linked.packages {
+            let id = format!("{} {}", package.name, package.version);
+            match &package.license {
+                Some(license) if is_denied(license, &self.deny) => report.denied.push(id),
+                None if package.license_file.is_none() => report.unknown.push(id),
+                _ => {}
+            }
+            report.packages.push(package_license(package)?);
+        }
+
+        let licenses_dir = self.cache_dir.join(rustc_target).join("licenses").join(crate_name);
+        fs::create_dir_all(&licenses_dir)
+            .with_context(|| format!("Failed to create licenses directory: {}", licenses_dir.display()))?;
+
+        report.notices = licenses_dir.join("THIRD_PARTY_NOTICES");
+        fs::write(&report.notices, notices_text(&report)?)
+            .with_context(|| format!("Failed to write {}", report.notices.display()))?;
+
+        report.summary = licenses_dir.join("licenses.json");
+        let json = serde_json::to_string_pretty(&report).context("Failed to serialize license summary")?;
+        fs::write(&report.summary, json)
+            .with_context(|| format!("Failed to write {}", report.summary.display()))?;
+
+        Ok(report)
+    }
+}
+
+impl Default for LicenseCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn package_license(package: &Package) -> Result<PackageLicense> {
+    let mut files = BTreeSet::new();
+    if let Some(license_file) = package.license_file() {
+        files.insert(license_file.into_std_path_buf());
+    }
+    if let Some(dir) = package.manifest_path.parent() {
+        files.extend(license_files(dir.as_std_path())?);
+    }
+
+    Ok(PackageLicense {
+        name: package.name.clone(),
+        version: package.version.to_string(),
+        license: package.license.clone(),
+        license_files: files.into_iter().collect(),
+        repository: package.repository.clone(),
+    })
+}
+
+/// LICENSE, LICENCE, COPYING and NOTICE files in a directory, sorted.
+pub fn license_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut licenses = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_uppercase();
+        let is_license = ["LICENSE", "LICENCE", "COPYING", "NOTICE"].iter().any(|prefix| name.starts_with(prefix));
+        if is_license && path.is_file() {
+            licenses.push(path);
+        }
+    }
+    licenses.sort();
+    Ok(licenses)
+}
+
+fn notices_text(report: &LicenseReport) -> Result<String> {
+    let rule = "=".repeat(80);
+    let mut text = String::new();
+    writeln!(text, "Third-party software notices for {} ({})\n", report.crate_name, report.rustc_target)?;
+    writeln!(text, "{} includes the following {} third-party package(s).\n", report.crate_name, report.packages.len())?;
+
+    for package in &report.packages {
+        writeln!(text, "{}\n{} {}", rule, package.name, package.version)?;
+        writeln!(text, "License: {}", package.license.as_deref().unwrap_or("(not specified)"))?;
+        if let Some(repository) = &package.repository {
+            writeln!(text, "Repository: {}", repository)?;
+        }
+        for file in &package.license_files {
+            let content = fs::read_to_string(file)
+                .with_context(|| format!("Failed to read license file {}", file.display()))?;
+            writeln!(text, "\n--- {} ---\n\n{}", file.file_name().unwrap_or_default().to_string_lossy(), content.trim_end())?;
+        }
+        writeln!(text)?;
+    }
+
+    Ok(text)
+}
+
+/// Whether an SPDX expression leaves no choice but a denied license: `OR` needs one
+/// allowed side, `AND` needs both. Identifiers compare case-insensitively, ignoring `+`
+/// and `WITH` exceptions; the legacy `/` separator means `OR`.
+pub fn is_denied(expression: &str, deny: &[String]) -> bool {
+    if deny.is_empty() {
+        return false;
+    }
+
+    let spaced = expression.replace('(', " ( ").replace(')', " ) ").replace('/', " OR ");
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+    let mut pos = 0;
+    !allowed_or(&tokens, &mut pos, deny)
+}
+
+fn allowed_or(tokens: &[&str], pos: &mut usize, deny: &[String]) -> bool {
+    let mut allowed = allowed_and(tokens, pos, deny);
+    while tokens.get(*pos).is_some_and(|t| t.eq_ignore_ascii_case("OR")) {
+        *pos += 1;
+        allowed |= allowed_and(tokens, pos, deny);
+    }
+    allowed
+}
+
+fn allowed_and(tokens: &[&str], pos: &mut usize, deny: &[String]) -> bool {
+    let mut allowed = allowed_license(tokens, pos, deny);
+    while tokens.get(*pos).is_some_and(|t| t.eq_ignore_ascii_case("AND")) {
+        *pos += 1;
+        allowed &= allowed_license(tokens, pos, deny);
+    }
+    allowed
+}
+
+fn allowed_license(tokens: &[&str], pos: &mut usize, deny: &[String]) -> bool {
+    let Some(token) = tokens.get(*pos) else { return true };
+    *pos += 1;
+
+    if *token == "(" {
+        let allowed = allowed_or(tokens, pos, deny);
+        if tokens.get(*pos) == Some(&")") {
+            *pos += 1;
+        }
+        return allowed;
+    }
+
+    if tokens.get(*pos).is_some_and(|t| t.eq_ignore_ascii_case("WITH")) {
+        *pos += 2;
+    }
+    let id = token.trim_end_matches('+');
+    !deny.iter().any(|d| d.trim_end_matches('+').eq_ignore_ascii_case(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_denied() {
+        let deny = vec!["GPL-3.0-only".to_string(), "AGPL-3.0-only".to_string()];
+
+        assert!(!is_denied("MIT OR Apache-2.0", &deny));
+        assert!(is_denied("GPL-3.0-only", &deny));
+        assert!(is_denied("gpl-3.0-only", &deny));
+        assert!(!is_denied("GPL-3.0-only OR MIT", &deny));
+        assert!(is_denied("MIT AND GPL-3.0-only", &deny));
+        assert!(is_denied("(MIT OR GPL-3.0-only) AND AGPL-3.0-only", &deny));
+        assert!(!is_denied("(MIT OR GPL-3.0-only) AND Apache-2.0 WITH LLVM-exception", &deny));
+        assert!(!is_denied("MIT/GPL-3.0-only", &deny));
+        assert!(!is_denied("GPL-3.0-only", &[]));
+    }
+}
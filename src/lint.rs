@@ -0,0 +1,469 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+/// Checks exported `extern "C"` functions against the rules in docs/FFI_SAFETY.md.
+pub struct FfiLinter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// A Rust-only type (String, Vec, &str, ...) in an exported signature
+    NonFfiType,
+    /// A struct or enum without a C-compatible `#[repr]` passed by value
+    NonReprC,
+    /// Code that can panic without a surrounding `catch_unwind`
+    UnguardedPanic,
+    /// A raw pointer parameter that is used without a null check
+    UncheckedPointer,
+}
+
+impl LintRule {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LintRule::NonFfiType => "non-ffi-type",
+            LintRule::NonReprC => "non-repr-c",
+            LintRule::UnguardedPanic => "unguarded-panic",
+            LintRule::UncheckedPointer => "unchecked-pointer",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintDiagnostic {
+    pub rule: LintRule,
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    /// The exported function the diagnostic belongs to
+    pub function: String,
+    pub message: String,
+}
+
+/// Types that are never FFI-safe by value or behind a reference.
+const NON_FFI_TYPES: &[&str] = &[
+    "String", "str", "Vec", "VecDeque", "HashMap", "HashSet", "BTreeMap", "BTreeSet", "Result", "Rc", "Arc",
+    "Cow", "PathBuf", "Path", "OsString", "OsStr", "CString", "CStr",
+];
+
+/// Panicking macros and methods that need a `catch_unwind` around them.
+const PANICKING_MACROS: &[&str] = &["panic", "unreachable", "todo", "unimplemented", "assert", "assert_eq", "assert_ne"];
+const PANICKING_METHODS: &[&str] = &["unwrap", "expect"];
+
+impl FfiLinter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn lint_files(&self, sources: &[PathBuf]) -> Result<Vec<LintDiagnostic>> {
+        let mut files = Vec::new();
+        for source_file in sources {
+            let source = fs::read_to_string(source_file)
+                .with_context(|| format!("Failed to read {}", source_file.display()))?;
+            let file = syn::parse_file(&source)
+                .with_context(|| format!("Failed to parse {}", source_file.display()))?;
+            files.push((source_file.clone(), file));
+        }
+
+        // Type representations are collected crate-wide before any signature is checked
+        let mut reprs = ReprCollector::default();
+        for (_, file) in &files {
+            reprs.visit_file(file);
+        }
+
+        let mut diagnostics = Vec::new();
+        for (path, file) in &files {
+            let mut visitor = ExportVisitor {
+                path,
+                reprs: &reprs.reprs,
+                diagnostics: &mut diagnostics,
+            };
+            visitor.visit_file(file);
+        }
+
+        diagnostics.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+        Ok(diagnostics)
+    }
+}
+
+impl Default for FfiLinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records whether each locally defined struct, enum and union has a C-compatible repr.
+#[derive(Default)]
+struct ReprCollector {
+    reprs: HashMap<String, bool>,
+}
+
+impl ReprCollector {
+    fn record(&mut self, ident: &syn::Ident, attrs: &[syn::Attribute]) {
+        self.reprs.insert(ident.to_string(), has_c_repr(attrs));
+    }
+}
+
+impl<'ast> Visit<'ast> for ReprCollector {
+    fn visit_item_struct(&mut self, item: &'ast syn::ItemStruct) {
+        self.record(&item.ident, &item.attrs);
+    }
+
+    fn visit_item_enum(&mut self, item: &'ast syn::ItemEnum) {
+        self.record(&item.ident, &item.attrs);
+    }
+
+    fn visit_item_union(&mut self, item: &'ast syn::ItemUnion) {
+        self.record(&item.ident, &item.attrs);
+    }
+}
+
+fn has_c_repr(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+        .any(|attr| {
+            let mut c_compatible = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                let ident = meta.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+                c_compatible |= matches!(
+                    ident.as_str(),
+                    "C" | "transparent" | "u8" | "u16" | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" | "usize" | "isize"
+                );
+                // Skip arguments such as `align(8)`
+                if meta.input.peek(syn::token::Paren) {
+                    let _content;
+                    syn::parenthesized!(_content in meta.input);
+                }
+                Ok(())
+            });
+            c_compatible
+        })
+}
+
+struct ExportVisitor<'a> {
+    path: &'a Path,
+    reprs: &'a HashMap<String, bool>,
+    diagnostics: &'a mut Vec<LintDiagnostic>,
+}
+
+impl ExportVisitor<'_> {
+    fn push(&mut self, rule: LintRule, span: proc_macro2::Span, function: &str, message: String) {
+        let start = span.start();
+        self.diagnostics.push(LintDiagnostic {
+            rule,
+            file: self.path.to_path_buf(),
+            line: start.line,
+            column: start.column + 1,
+            function: function.to_string(),
+            message,
+        });
+    }
+
+    fn lint_function(&mut self, item: &syn::ItemFn) {
+        let name = item.sig.ident.to_string();
+
+        for input in &item.sig.inputs {
+            if let syn::FnArg::Typed(arg) = input {
+                self.lint_type(&arg.ty, &name, "parameter");
+            }
+        }
+        if let syn::ReturnType::Type(_, ty) = &item.sig.output {
+            self.lint_type(ty, &name, "return type");
+        }
+
+        let mut body = BodyVisitor::default();
+        body.visit_block(&item.block);
+
+        if !body.catch_unwind
+            && let Some((span, what)) = body.panics.first()
+        {
+            self.push(
+                LintRule::UnguardedPanic,
+                *span,
+                &name,
+                format!("`{}` can panic across the FFI boundary; wrap the body in `std::panic::catch_unwind`", what),
+            );
+        }
+
+        // Callers of an `unsafe fn` are responsible for passing valid pointers
+        if item.sig.unsafety.is_none() {
+            for input in &item.sig.inputs {
+                let syn::FnArg::Typed(arg) = input else { continue };
+                let (syn::Type::Ptr(_), syn::Pat::Ident(pat)) = (&*arg.ty, &*arg.pat) else { continue };
+                let param = pat.ident.to_string();
+                if body.used.contains(&param) && !body.null_checked.contains(&param) {
+                    self.push(
+                        LintRule::UncheckedPointer,
+                        pat.ident.span(),
+                        &name,
+                        format!("raw pointer `{}` is used without a null check", param),
+                    );
+                }
+            }
+        }
+    }
+
+    fn lint_type(&mut self, ty: &syn::Type, function: &str, position: &str) {
+        match ty {
+            syn::Type::Path(type_path) => {
+                let Some(segment) = type_path.path.segments.last() else { return };
+                let ident = segment.ident.to_string();
+
+                if NON_FFI_TYPES.contains(&ident.as_str()) {
+                    self.push(
+                        LintRule::NonFfiType,
+                        ty.span(),
+                        function,
+                        format!("{} uses `{}`, which is not FFI-safe", position, ident),
+                    );
+                } else if ident == "Option" && !option_is_nullable_pointer(segment) {
+                    self.push(
+                        LintRule::NonFfiType,
+                        ty.span(),
+                        function,
+                        format!("{} uses `Option` around a type without a null niche", position),
+                    );
+                } else if ident == "Box" && let Some(inner) = generic_argument(segment) && is_unsized(inner) {
+                    self.push(
+                        LintRule::NonFfiType,
+                        ty.span(),
+                        function,
+                        format!("{} boxes an unsized type, which is a fat pointer", position),
+                    );
+                } else if self.reprs.get(&ident) == Some(&false) {
+                    self.push(
+                        LintRule::NonReprC,
+                        ty.span(),
+                        function,
+                        format!("{} passes `{}` by value, but it has no `#[repr(C)]`", position, ident),
+                    );
+                }
+            }
+            syn::Type::Reference(reference) if is_unsized(&reference.elem) => {
+                self.push(
+                    LintRule::NonFfiType,
+                    ty.span(),
+                    function,
+                    format!("{} is a reference to an unsized type, which is a fat pointer", position),
+                );
+            }
+            syn::Type::Ptr(pointer) if is_unsized(&pointer.elem) => {
+                self.push(
+                    LintRule::NonFfiType,
+                    ty.span(),
+                    function,
+                    format!("{} is a pointer to an unsized type, which is a fat pointer", position),
+                );
+            }
+            syn::Type::Reference(reference) => self.lint_type(&reference.elem, function, position),
+            syn::Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+                self.push(LintRule::NonFfiType, ty.span(), function, format!("{} uses a tuple, which is not FFI-safe", position));
+            }
+            syn::Type::Slice(_) | syn::Type::TraitObject(_) | syn::Type::ImplTrait(_) => {
+                self.push(LintRule::NonFfiType, ty.span(), function, format!("{} uses an unsized or opaque Rust type", position));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ExportVisitor<'_> {
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        if is_exported(item) {
+            self.lint_function(item);
+        }
+        syn::visit::visit_item_fn(self, item);
+    }
+}
+
+/// `extern "C"` functions with `#[unsafe(no_mangle)]` (or the pre-2024 `#[no_mangle]`).
+fn is_exported(item: &syn::ItemFn) -> bool {
+    let extern_c = item.sig.abi.as_ref().is_some_and(|abi| {
+        abi.name.as_ref().is_none_or(|name| matches!(name.value().as_str(), "C" | "C-unwind"))
+    });
+    let no_mangle = item.attrs.iter().any(|attr| {
+        attr.path().is_ident("no_mangle")
+            || (attr.path().is_ident("unsafe") && attr.parse_args::<syn::Path>().is_ok_and(|p| p.is_ident("no_mangle")))
+    });
+
+    extern_c && no_mangle
+}
+
+fn generic_argument(segment: &syn::PathSegment) -> Option<&syn::Type> {
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    args.args.iter().find_map(|arg| match arg {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+/// `Option<&T>`, `Option<Box<T>>`, `Option<NonNull<T>>` and `Option<extern fn>` are represented as nullable pointers.
+fn option_is_nullable_pointer(segment: &syn::PathSegment) -> bool {
+    match generic_argument(segment) {
+        Some(syn::Type::Reference(_)) | Some(syn::Type::BareFn(_)) => true,
+        Some(syn::Type::Path(inner)) => inner.path.segments.last()
+            .is_some_and(|s| matches!(s.ident.to_string().as_str(), "Box" | "NonNull" | "NonZeroU32" | "NonZeroU64" | "NonZeroUsize")),
+        _ => false,
+    }
+}
+
+fn is_unsized(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Slice(_) | syn::Type::TraitObject(_) => true,
+        syn::Type::Path(path) => path.path.segments.last()
+            .is_some_and(|s| matches!(s.ident.to_string().as_str(), "str" | "Path" | "OsStr" | "CStr")),
+        _ => false,
+    }
+}
+
+/// Collects what a function body does with panics and pointers.
+#[derive(Default)]
+struct BodyVisitor {
+    panics: Vec<(proc_macro2::Span, String)>,
+    catch_unwind: bool,
+    used: BTreeSet<String>,
+    null_checked: BTreeSet<String>,
+}
+
+impl BodyVisitor {
+    fn check_null(&mut self, expr: &syn::Expr) {
+        if let syn::Expr::Path(path) = expr
+            && let Some(ident) = path.path.get_ident()
+        {
+            self.null_checked.insert(ident.to_string());
+        }
+    }
+}
+
+fn is_null_expr(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::Call(call) => matches!(&*call.func, syn::Expr::Path(path)
+            if path.path.segments.last().is_some_and(|s| s.ident == "null" || s.ident == "null_mut")),
+        _ => false,
+    }
+}
+
+impl<'ast> Visit<'ast> for BodyVisitor {
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if let Some(ident) = mac.path.get_ident().map(|i| i.to_string())
+            && PANICKING_MACROS.contains(&ident.as_str())
+        {
+            self.panics.push((mac.span(), format!("{}!", ident)));
+        }
+        syn::visit::visit_macro(self, mac);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        let method = call.method.to_string();
+        if PANICKING_METHODS.contains(&method.as_str()) {
+            self.panics.push((call.method.span(), format!(".{}()", method)));
+        }
+        if matches!(method.as_str(), "is_null" | "as_ref" | "as_mut") {
+            self.check_null(&call.receiver);
+        }
+        syn::visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(path) = &*call.func
+            && let Some(last) = path.path.segments.last()
+        {
+            if last.ident == "catch_unwind" {
+                self.catch_unwind = true;
+            }
+            // NonNull::new(ptr) returns None for null
+            if last.ident == "new" && path.path.segments.iter().any(|s| s.ident == "NonNull") {
+                call.args.iter().for_each(|arg| self.check_null(arg));
+            }
+        }
+        syn::visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_binary(&mut self, binary: &'ast syn::ExprBinary) {
+        if matches!(binary.op, syn::BinOp::Eq(_) | syn::BinOp::Ne(_)) {
+            if is_null_expr(&binary.right) {
+                self.check_null(&binary.left);
+            }
+            if is_null_expr(&binary.left) {
+                self.check_null(&binary.right);
+            }
+        }
+        syn::visit::visit_expr_binary(self, binary);
+    }
+
+    fn visit_expr_path(&mut self, path: &'ast syn::ExprPath) {
+        if let Some(ident) = path.path.get_ident() {
+            self.used.insert(ident.to_string());
+        }
+        syn::visit::visit_expr_path(self, path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(source: &str) -> Vec<LintDiagnostic> {
+        let dir = std::env::temp_dir().join(format!("ghostbind-lint-{}-{}", std::process::id(), source.len()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lib.rs");
+        fs::write(&path, source).unwrap();
+        let diagnostics = FfiLinter::new().lint_files(&[path]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        diagnostics
+    }
+
+    #[test]
+    fn test_signature_rules() {
+        let diagnostics = lint(r#"
+pub struct Plain { x: i32 }
+
+#[repr(C)]
+pub struct Point { x: f64, y: f64 }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn name(input: &str) -> String { input.to_string() }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn take(plain: Plain, point: Point, cb: Option<extern "C" fn()>) {}
+
+// Not exported, so not linted
+pub extern "C" fn internal(v: Vec<u8>) {}
+"#);
+
+        let rules: Vec<(LintRule, usize)> = diagnostics.iter().map(|d| (d.rule, d.line)).collect();
+        assert_eq!(rules, vec![(LintRule::NonFfiType, 8), (LintRule::NonFfiType, 8), (LintRule::NonReprC, 11)]);
+        assert_eq!(diagnostics[2].function, "take");
+    }
+
+    #[test]
+    fn test_body_rules() {
+        let diagnostics = lint(r#"
+#[unsafe(no_mangle)]
+pub extern "C" fn first(data: *const u8, len: usize) -> u8 {
+    let slice = unsafe { std::slice::from_raw_parts(data, len) };
+    *slice.first().unwrap()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn guarded(data: *const u8) -> u8 {
+    if data.is_null() {
+        return 0;
+    }
+    std::panic::catch_unwind(|| unsafe { *data }).unwrap_or(0)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn caller_checked(data: *const u8) -> u8 {
+    unsafe { *data }
+}
+"#);
+
+        let rules: Vec<(LintRule, usize)> = diagnostics.iter().map(|d| (d.rule, d.line)).collect();
+        assert_eq!(rules, vec![(LintRule::UncheckedPointer, 3), (LintRule::UnguardedPanic, 5)]);
+    }
+}
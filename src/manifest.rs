@@ -1,33 +1,133 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write as _;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::abi::AbiSnapshot;
 use crate::artifact_discovery::DiscoveredArtifact;
-use crate::header_generation::GeneratedHeader;
+use crate::error::Error;
+use crate::header_generation::{c_headers, GeneratedHeader, HeaderLanguage};
+use crate::provenance::Provenance;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildManifest {
     pub crate_name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub crate_version: String,
     pub kind: String,
     pub artifact: PathBuf,
+    /// The headers C and Zig consumers include
     pub headers: Vec<PathBuf>,
+    /// Every generated header, tagged with its language
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub header_files: Vec<HeaderFile>,
     pub rustc_target: String,
     pub link_libs: Vec<String>,
     pub link_search: Vec<PathBuf>,
+    /// Apple frameworks to link, e.g. `CoreFoundation`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frameworks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zig_wrappers: Option<PathBuf>,
+    /// C++ header with `unique_ptr` aliases for the opaque handles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cxx_wrappers: Option<PathBuf>,
+    /// Licenses of the dependencies bundled into the artifact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub third_party_notices: Option<PathBuf>,
+    /// CycloneDX or SPDX document describing the artifact and its dependencies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sbom: Option<PathBuf>,
+    /// Toolchain, sources and settings the build came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    /// The C ABI of this build, kept here because the next build overwrites the cached
+    /// artifact and headers that `ghostbind abi-diff` would otherwise read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abi: Option<AbiSnapshot>,
+}
+
+impl BuildManifest {
+    /// The C headers, which ghostbind's checks parse and compile. Manifests written
+    /// before headers were tagged with a language only list C headers.
+    pub fn c_headers(&self) -> Result<Vec<PathBuf>> {
+        if self.header_files.is_empty() {
+            return Ok(self.headers.clone());
+        }
+
+        let headers: Vec<PathBuf> = self.header_files.iter()
+            .filter(|header| header.language == HeaderLanguage::C.tag())
+            .map(|header| header.path.clone())
+            .collect();
+        if headers.is_empty() {
+            return Err(Error::InvalidConfig(format!(
+                "The manifest for {} has no C header; build with `--header-lang c`",
+                self.crate_name
+            )).into());
+        }
+        Ok(headers)
+    }
+
+    /// The manifest as the ZON and Zig formats write it, without the ABI snapshot that
+    /// only ghostbind reads.
+    pub fn zig_value(&self) -> Result<Value> {
+        let mut value = serde_json::to_value(self)
+            .context("Failed to serialize manifest")?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove("abi");
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeaderFile {
+    pub path: PathBuf,
+    /// `c`, `c++` or `cython`
+    pub language: String,
+}
+
+/// The files a manifest is written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// `<crate>-manifest.json`, read by ghostbind's own commands
+    Json,
+    /// `<crate>-manifest.zon`, a Zig object notation literal
+    Zon,
+    /// `<crate>_manifest.zig`, comptime constants for `@import`
+    Zig,
+}
+
+impl ManifestFormat {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ManifestFormat::Json => "json",
+            ManifestFormat::Zon => "zon",
+            ManifestFormat::Zig => "zig",
+        }
+    }
 }
 
 pub struct ManifestGenerator {
     cache_dir: PathBuf,
+    formats: Vec<ManifestFormat>,
 }
 
 impl ManifestGenerator {
     pub fn new() -> Self {
         Self {
             cache_dir: PathBuf::from(".ghostbind/cache"),
+            formats: vec![ManifestFormat::Json],
         }
     }
 
+    pub fn formats(mut self, formats: Vec<ManifestFormat>) -> Self {
+        self.formats = formats;
+        self
+    }
+
     pub fn generate_manifest(
         &self,
         crate_name: &str,
@@ -37,49 +137,84 @@ impl ManifestGenerator {
     ) -> Result<BuildManifest> {
         let manifest = BuildManifest {
             crate_name: crate_name.to_string(),
+            crate_version: String::new(),
             kind: artifact.kind.as_str().to_string(),
             artifact: artifact.cached_path.clone(),
-            headers: headers.iter().map(|h| h.header_path.clone()).collect(),
+            headers: included_headers(headers),
+            header_files: headers.iter()
+                .map(|h| HeaderFile { path: h.header_path.clone(), language: h.language.tag().to_string() })
+                .collect(),
             rustc_target: rustc_target.to_string(),
             link_libs: self.get_system_link_libs(rustc_target),
             link_search: Vec::new(), // Will be populated later if needed
+            frameworks: Vec::new(),
+            zig_wrappers: None,
+            cxx_wrappers: None,
+            third_party_notices: None,
+            sbom: None,
+            provenance: None,
+            abi: None,
         };
 
         Ok(manifest)
     }
 
+    /// Writes the manifest in every configured format and returns the path of the first.
     pub fn write_manifest(
         &self,
         manifest: &BuildManifest,
         target_triple: Option<&str>,
     ) -> Result<PathBuf> {
-        let manifest_path = self.get_manifest_path(&manifest.crate_name, target_triple);
+        self.write_manifests(manifest, target_triple)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No manifest format selected"))
+    }
 
-        // Create cache directory
-        if let Some(manifest_parent) = manifest_path.parent() {
-            fs::create_dir_all(manifest_parent)
-                .with_context(|| format!("Failed to create manifest directory: {}", manifest_parent.display()))?;
-        }
+    /// Writes the manifest in every configured format and returns the paths written.
+    pub fn write_manifests(
+        &self,
+        manifest: &BuildManifest,
+        target_triple: Option<&str>,
+    ) -> Result<Vec<PathBuf>> {
+        let value = manifest.zig_value()?;
+        let mut written = Vec::new();
 
-        // Serialize manifest to JSON
-        let manifest_json = serde_json::to_string_pretty(manifest)
-            .context("Failed to serialize manifest to JSON")?;
+        for format in &self.formats {
+            let manifest_path = self.get_manifest_path(&manifest.crate_name, target_triple, *format);
 
-        // Write to file
-        fs::write(&manifest_path, manifest_json)
-            .with_context(|| format!("Failed to write manifest to {}", manifest_path.display()))?;
+            // Create cache directory
+            if let Some(manifest_parent) = manifest_path.parent() {
+                fs::create_dir_all(manifest_parent)
+                    .with_context(|| format!("Failed to create manifest directory: {}", manifest_parent.display()))?;
+            }
+
+            let content = match format {
+                ManifestFormat::Json => serde_json::to_string_pretty(manifest)
+                    .context("Failed to serialize manifest to JSON")?,
+                ManifestFormat::Zon => to_zon(&value),
+                ManifestFormat::Zig => to_zig(&value),
+            };
 
-        println!("Generated manifest: {}", manifest_path.display());
+            fs::write(&manifest_path, content)
+                .with_context(|| format!("Failed to write manifest to {}", manifest_path.display()))?;
+
+            written.push(manifest_path);
+        }
 
-        Ok(manifest_path)
+        Ok(written)
     }
 
-    fn get_manifest_path(&self, crate_name: &str, target_triple: Option<&str>) -> PathBuf {
+    fn get_manifest_path(&self, crate_name: &str, target_triple: Option<&str>, format: ManifestFormat) -> PathBuf {
         let target_str = target_triple.unwrap_or("native");
+        let file_name = match format {
+            ManifestFormat::Json => format!("{}-manifest.json", crate_name),
+            ManifestFormat::Zon => format!("{}-manifest.zon", crate_name),
+            // Zig source files are imported by name, so keep it a valid identifier
+            ManifestFormat::Zig => format!("{}_manifest.zig", crate_name.replace('-', "_")),
+        };
 
-        self.cache_dir
-            .join(target_str)
-            .join(format!("{}-manifest.json", crate_name))
+        self.cache_dir.join(target_str).join(file_name)
     }
 
     fn get_system_link_libs(&self, rustc_target: &str) -> Vec<String> {
@@ -133,11 +268,12 @@ impl ManifestGenerator {
     }
 
     pub fn read_manifest(&self, manifest_path: &Path) -> Result<BuildManifest> {
+        // A missing or malformed manifest is a bad argument, not a failed build
         let manifest_content = fs::read_to_string(manifest_path)
-            .with_context(|| format!("Failed to read manifest from {}", manifest_path.display()))?;
+            .map_err(|e| Error::InvalidConfig(format!("Failed to read manifest from {}: {}", manifest_path.display(), e)))?;
 
         let manifest: BuildManifest = serde_json::from_str(&manifest_content)
-            .with_context(|| format!("Failed to parse manifest JSON from {}", manifest_path.display()))?;
+            .map_err(|e| Error::InvalidConfig(format!("Failed to parse manifest JSON from {}: {}", manifest_path.display(), e)))?;
 
         Ok(manifest)
     }
@@ -165,6 +301,130 @@ impl ManifestGenerator {
     }
 }
 
+/// The headers consumers add to their include path: the C ones, or all of them when
+/// none is C.
+fn included_headers(headers: &[GeneratedHeader]) -> Vec<PathBuf> {
+    let c = c_headers(headers);
+    let included = if c.is_empty() { headers.to_vec() } else { c };
+    included.into_iter().map(|header| header.header_path).collect()
+}
+
+/// Renders a manifest as a ZON literal, e.g. `.{ .crate_name = "my_crate", ... }`.
+pub fn to_zon(value: &Value) -> String {
+    let mut out = String::new();
+    write_zon(&mut out, value, 0);
+    out.push('\n');
+    out
+}
+
+fn write_zon(out: &mut String, value: &Value, indent: usize) {
+    let pad = "    ".repeat(indent + 1);
+    match value {
+        Value::Object(map) => {
+            let fields: Vec<_> = map.iter().filter(|(_, v)| !v.is_null()).collect();
+            if fields.is_empty() {
+                out.push_str(".{}");
+                return;
+            }
+            out.push_str(".{\n");
+            for (key, value) in fields {
+                let _ = write!(out, "{}.{} = ", pad, zig_identifier(key));
+                write_zon(out, value, indent + 1);
+                out.push_str(",\n");
+            }
+            let _ = write!(out, "{}}}", "    ".repeat(indent));
+        }
+        Value::Array(items) => {
+            if items.is_empty() {
+                out.push_str(".{}");
+                return;
+            }
+            out.push_str(".{\n");
+            for item in items {
+                out.push_str(&pad);
+                write_zon(out, item, indent + 1);
+                out.push_str(",\n");
+            }
+            let _ = write!(out, "{}}}", "    ".repeat(indent));
+        }
+        _ => out.push_str(&zig_scalar(value)),
+    }
+}
+
+/// Renders a manifest as a Zig source file of `pub const` declarations.
+pub fn to_zig(value: &Value) -> String {
+    let mut out = String::from("// Generated by ghostbind. Do not edit.\n\n");
+
+    if let Value::Object(map) = value {
+        for (key, value) in map {
+            let _ = write!(out, "pub const {}", zig_identifier(key));
+            match value {
+                Value::Array(items) if items.iter().all(Value::is_string) => {
+                    out.push_str(" = [_][]const u8{");
+                    for item in items {
+                        let _ = write!(out, "\n    {},", zig_scalar(item));
+                    }
+                    out.push_str(if items.is_empty() { "};\n" } else { "\n};\n" });
+                }
+                Value::Null => out.push_str(": ?[]const u8 = null;\n"),
+                Value::Object(_) | Value::Array(_) => {
+                    out.push_str(" = ");
+                    write_zon(&mut out, value, 0);
+                    out.push_str(";\n");
+                }
+                _ => {
+                    let _ = writeln!(out, " = {};", zig_scalar(value));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+fn zig_scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => zig_string(s),
+        Value::Null => "null".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn zig_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{{{:x}}}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+const ZIG_RESERVED: &[&str] = &[
+    "addrspace", "align", "allowzero", "and", "anyframe", "anytype", "asm", "break", "callconv", "catch",
+    "comptime", "const", "continue", "defer", "else", "enum", "errdefer", "error", "export", "extern", "fn",
+    "for", "if", "inline", "linksection", "noalias", "noinline", "nosuspend", "opaque", "or", "orelse",
+    "packed", "pub", "resume", "return", "struct", "suspend", "switch", "test", "threadlocal", "try", "union",
+    "unreachable", "var", "volatile", "while", "type", "bool", "void", "true", "false", "null", "undefined",
+];
+
+/// Names that aren't plain identifiers, or are Zig keywords, are written as `@"name"`.
+fn zig_identifier(name: &str) -> String {
+    let plain = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !ZIG_RESERVED.contains(&name);
+    if plain { name.to_string() } else { format!("@{}", zig_string(name)) }
+}
+
 impl Default for ManifestGenerator {
     fn default() -> Self {
         Self::new()
@@ -175,7 +435,7 @@ impl Default for ManifestGenerator {
 mod tests {
     use super::*;
     use crate::artifact_discovery::{ArtifactKind, DiscoveredArtifact};
-    use crate::header_generation::GeneratedHeader;
+    use crate::header_generation::{GeneratedHeader, HeaderLanguage};
 
     #[test]
     fn test_manifest_generation() {
@@ -185,13 +445,22 @@ mod tests {
             name: "test_crate".to_string(),
             kind: ArtifactKind::StaticLib,
             original_path: PathBuf::from("/tmp/libtest_crate.a"),
-            cached_path: PathBuf::from(".ghostbind/cache/native/release/test_crate.a"),
+            cached_path: PathBuf::from(".ghostbind/cache/native/release/libtest_crate.a"),
+            import_library: None,
         };
 
-        let headers = vec![GeneratedHeader {
-            crate_name: "test_crate".to_string(),
-            header_path: PathBuf::from(".ghostbind/cache/native/headers/test_crate.h"),
-        }];
+        let headers = vec![
+            GeneratedHeader {
+                crate_name: "test_crate".to_string(),
+                header_path: PathBuf::from(".ghostbind/cache/native/headers/test_crate.h"),
+                language: HeaderLanguage::C,
+            },
+            GeneratedHeader {
+                crate_name: "test_crate".to_string(),
+                header_path: PathBuf::from(".ghostbind/cache/native/headers/test_crate.hpp"),
+                language: HeaderLanguage::Cxx,
+            },
+        ];
 
         let manifest = generator.generate_manifest(
             "test_crate",
@@ -205,6 +474,37 @@ mod tests {
         assert_eq!(manifest.rustc_target, "x86_64-unknown-linux-gnu");
         assert!(manifest.link_libs.contains(&"pthread".to_string()));
         assert!(manifest.link_libs.contains(&"dl".to_string()));
+        assert_eq!(manifest.headers, vec![PathBuf::from(".ghostbind/cache/native/headers/test_crate.h")]);
+        assert_eq!(manifest.header_files[1], HeaderFile {
+            path: PathBuf::from(".ghostbind/cache/native/headers/test_crate.hpp"),
+            language: "c++".to_string(),
+        });
+        assert_eq!(manifest.c_headers().unwrap(), vec![PathBuf::from(".ghostbind/cache/native/headers/test_crate.h")]);
+    }
+
+    #[test]
+    fn test_zon_and_zig_output() {
+        let value = serde_json::json!({
+            "crate_name": "my-crate",
+            "headers": ["C:\\cache\\my_crate.h"],
+            "link_search": [],
+            "zig_wrappers": null,
+        });
+
+        let zon = to_zon(&value);
+        assert!(zon.starts_with(".{\n    .crate_name = \"my-crate\",\n"));
+        assert!(zon.contains("    .headers = .{\n        \"C:\\\\cache\\\\my_crate.h\",\n    },\n"));
+        assert!(zon.contains(".link_search = .{},"));
+        assert!(!zon.contains("zig_wrappers"));
+
+        let zig = to_zig(&value);
+        assert!(zig.contains("pub const crate_name = \"my-crate\";\n"));
+        assert!(zig.contains("pub const link_search = [_][]const u8{};\n"));
+        assert!(zig.contains("pub const zig_wrappers: ?[]const u8 = null;\n"));
+
+        assert_eq!(zig_identifier("link_libs"), "link_libs");
+        assert_eq!(zig_identifier("type"), "@\"type\"");
+        assert_eq!(zig_identifier("1st"), "@\"1st\"");
     }
 
     #[test]
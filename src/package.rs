@@ -0,0 +1,273 @@
+use anyhow::{anyhow, Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::artifact_discovery::LibraryNames;
+use crate::licenses::license_files;
+use crate::manifest::{to_zon, BuildManifest};
+use crate::target_mapping::TargetMapping;
+
+/// Packs a built crate into `<crate>-<version>-<zig-target>.tar.gz` for users without a
+/// Rust toolchain, along with the hash `zig fetch` computes for it.
+pub struct Packager {
+    output_dir: PathBuf,
+    license_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageReport {
+    pub archive: PathBuf,
+    /// The package hash `zig fetch` reports, for `.hash` in build.zig.zon
+    pub zig_hash: String,
+    pub hash_file: PathBuf,
+    /// Paths inside the package, relative to its root directory
+    pub files: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl Packager {
+    pub fn new() -> Self {
+        Self {
+            output_dir: PathBuf::from(".ghostbind/dist"),
+            license_dir: None,
+        }
+    }
+
+    pub fn output_dir(mut self, output_dir: PathBuf) -> Self {
+        self.output_dir = output_dir;
+        self
+    }
+
+    /// Directory to take LICENSE, COPYING and NOTICE files from, usually the crate's.
+    pub fn license_dir(mut self, license_dir: Option<PathBuf>) -> Self {
+        self.license_dir = license_dir;
+        self
+    }
+
+    pub fn package(&self, manifest: &BuildManifest) -> Result<PackageReport> {
+        let mapping = TargetMapping::new();
+        let zig_target = mapping.rust_to_zig(&manifest.rustc_target).unwrap_or(&manifest.rustc_target);
+        let version = if manifest.crate_version.is_empty() { "0.0.0" } else { &manifest.crate_version };
+        let root = format!("{}-{}-{}", manifest.crate_name, version, zig_target);
+
+        let mut warnings = Vec::new();
+        let files = self.collect_files(manifest, &mut warnings)?;
+
+        fs::create_dir_all(&self.output_dir)
+            .with_context(|| format!("Failed to create output directory: {}", self.output_dir.display()))?;
+        let archive = self.output_dir.join(format!("{}.tar.gz", root));
+        write_archive(&archive, &root, &files)?;
+
+        let zig_hash = zig_package_hash(&files);
+        let hash_file = self.output_dir.join(format!("{}.zig-hash", root));
+        fs::write(&hash_file, format!("{}\n", zig_hash))
+            .with_context(|| format!("Failed to write {}", hash_file.display()))?;
+
+        Ok(PackageReport {
+            archive,
+            zig_hash,
+            hash_file,
+            files: files.keys().cloned().collect(),
+            warnings,
+        })
+    }
+
+    /// The package contents by path, using the same lib/include layout as `ghostbind install`.
+    fn collect_files(&self, manifest: &BuildManifest, warnings: &mut Vec<String>) -> Result<BTreeMap<String, Vec<u8>>> {
+        let mut files = BTreeMap::new();
+        let mut packaged = manifest.clone();
+
+        let names = LibraryNames::for_target(&manifest.crate_name, &manifest.kind, &manifest.rustc_target);
+        let library_dir = if names.import_library.is_some() { "bin" } else { "lib" };
+        let library = format!("{}/{}", library_dir, names.library);
+        files.insert(library.clone(), read(&manifest.artifact)?);
+        packaged.artifact = PathBuf::from(library);
+
+        if let Some(import_library) = &names.import_library {
+            files.insert(format!("lib/{}", import_library), read(&manifest.artifact.with_file_name(import_library))?);
+        }
+
+        packaged.headers.clear();
+        for header in &manifest.headers {
+            let path = format!("include/{}/{}", manifest.crate_name, file_name(header)?);
+            files.insert(path.clone(), read(header)?);
+            packaged.headers.push(PathBuf::from(path));
+        }
+        for header_file in &mut packaged.header_files {
+            let path = format!("include/{}/{}", manifest.crate_name, file_name(&header_file.path)?);
+            files.insert(path.clone(), read(&header_file.path)?);
+            header_file.path = PathBuf::from(path);
+        }
+        if let Some(wrappers) = &manifest.cxx_wrappers {
+            let path = format!("include/{}/{}", manifest.crate_name, file_name(wrappers)?);
+            files.insert(path.clone(), read(wrappers)?);
+            packaged.cxx_wrappers = Some(PathBuf::from(path));
+        }
+
+        if let Some(wrappers) = &manifest.zig_wrappers {
+            let path = file_name(wrappers)?;
+            files.insert(path.clone(), read(wrappers)?);
+            packaged.zig_wrappers = Some(PathBuf::from(path));
+        }
+
+        if let Some(notices) = &manifest.third_party_notices {
+            files.insert("THIRD_PARTY_NOTICES".to_string(), read(notices)?);
+            packaged.third_party_notices = Some(PathBuf::from("THIRD_PARTY_NOTICES"));
+        }
+
+        if let Some(sbom) = &manifest.sbom {
+            let path = file_name(sbom)?;
+            files.insert(path.clone(), read(sbom)?);
+            packaged.sbom = Some(PathBuf::from(path));
+        }
+
+        let value = packaged.zig_value()?;
+        let json = serde_json::to_string_pretty(&packaged).context("Failed to serialize manifest to JSON")?;
+        files.insert(format!("{}-manifest.json", manifest.crate_name), json.into_bytes());
+        files.insert(format!("{}-manifest.zon", manifest.crate_name), to_zon(&value).into_bytes());
+
+        let licenses = match &self.license_dir {
+            Some(dir) => license_files(dir)?,
+            None => Vec::new(),
+        };
+        if licenses.is_empty() {
+            warnings.push("no LICENSE files found to include in the package".to_string());
+        }
+        for license in licenses {
+            files.insert(file_name(&license)?, read(&license)?);
+        }
+
+        files.insert("SHA256SUMS".to_string(), sha256sums(&files).into_bytes());
+
+        Ok(files)
+    }
+}
+
+impl Default for Packager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow!("Invalid file path: {}", path.display()))
+}
+
+fn sha256sums(files: &BTreeMap<String, Vec<u8>>) -> String {
+    let mut sums = String::new();
+    for (path, contents) in files {
+        let _ = writeln!(sums, "{}  {}", hex::encode(Sha256::digest(contents)), path);
+    }
+    sums
+}
+
+/// Writes the files under a single `root/` directory with fixed ownership, modes and
+/// timestamps, so the archive only changes when its contents do.
+fn write_archive(archive: &Path, root: &str, files: &BTreeMap<String, Vec<u8>>) -> Result<()> {
+    let file = fs::File::create(archive)
+        .with_context(|| format!("Failed to create {}", archive.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    for (path, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_entry_type(tar::EntryType::Regular);
+        builder.append_data(&mut header, format!("{}/{}", root, path), contents.as_slice())
+            .with_context(|| format!("Failed to add {} to {}", path, archive.display()))?;
+    }
+
+    builder.into_inner()
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| format!("Failed to write {}", archive.display()))?;
+    Ok(())
+}
+
+/// The hash Zig 0.14's `zig fetch` computes for a package without a build.zig.zon:
+/// `N-V-` (no name or version) followed by the URL-safe base64 of the placeholder
+/// package id 0xffff, the total file size and the first 25 bytes of the SHA-256 over
+/// the SHA-256 of each file's path, executable bit and contents, in path order.
+pub fn zig_package_hash(files: &BTreeMap<String, Vec<u8>>) -> String {
+    let mut package_hasher = Sha256::new();
+    let mut total_size: u64 = 0;
+    for (path, contents) in files {
+        let mut file_hasher = Sha256::new();
+        file_hasher.update(path.as_bytes());
+        // Zig hashes every file as non-executable
+        file_hasher.update([0, 0]);
+        file_hasher.update(contents);
+        package_hasher.update(file_hasher.finalize());
+        total_size += contents.len() as u64;
+    }
+
+    let mut hash_plus = Vec::with_capacity(33);
+    hash_plus.extend_from_slice(&0xffff_u32.to_le_bytes());
+    hash_plus.extend_from_slice(&u32::try_from(total_size).unwrap_or(u32::MAX).to_le_bytes());
+    hash_plus.extend_from_slice(&package_hasher.finalize()[..25]);
+    format!("N-V-{}", base64_url(&hash_plus))
+}
+
+/// Unpadded URL-safe base64, as Zig's `std.base64.url_safe_no_pad`.
+fn base64_url(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zig_package_hash() {
+        let mut files = BTreeMap::new();
+        files.insert("b.txt".to_string(), b"b".to_vec());
+        files.insert("a.txt".to_string(), b"a".to_vec());
+
+        // Package id 0xffff, the total size of 2 bytes, then the truncated digest;
+        // computed outside ghostbind with Python's hashlib and base64
+        let hash = zig_package_hash(&files);
+        assert_eq!(hash, "N-V-__8AAAIAAADsjhWz5OHNDQoXJ2Guhu1uxCyeZEtdkqma");
+        assert_eq!(hash.len(), 4 + 44);
+    }
+
+    #[test]
+    fn test_base64_url() {
+        assert_eq!(base64_url(b""), "");
+        assert_eq!(base64_url(b"f"), "Zg");
+        assert_eq!(base64_url(b"fo"), "Zm8");
+        assert_eq!(base64_url(b"foo"), "Zm9v");
+        assert_eq!(base64_url(&[0xfb, 0xff, 0xbf]), "-_-_");
+    }
+
+    #[test]
+    fn test_sha256sums() {
+        let mut files = BTreeMap::new();
+        files.insert("lib/libfoo.a".to_string(), Vec::new());
+
+        assert_eq!(
+            sha256sums(&files),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  lib/libfoo.a\n"
+        );
+    }
+}
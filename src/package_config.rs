@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::artifact_discovery::LibraryNames;
+use crate::manifest::BuildManifest;
+
+/// Generates a pkg-config file and a CMake package config for a built crate, so C and
+/// CMake projects can link it with `pkg-config --libs` or `find_package`.
+///
+/// The files are relocatable: they find the prefix from their own location, assuming the
+/// standard layout of `lib/`, `include/<crate>/`, `share/pkgconfig/` and `share/cmake/<crate>/`.
+pub struct PackageConfigGenerator {
+    cache_dir: PathBuf,
+    prefix: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageConfigFiles {
+    /// The prefix the files were written under
+    pub prefix: PathBuf,
+    pub pkg_config: PathBuf,
+    pub cmake_config: PathBuf,
+    pub cmake_version: PathBuf,
+}
+
+impl PackageConfigGenerator {
+    pub fn new() -> Self {
+        Self {
+            cache_dir: PathBuf::from(".ghostbind/cache"),
+            prefix: None,
+        }
+    }
+
+    /// Writes into `prefix` instead of `.ghostbind/cache/<target>/prefix`.
+    pub fn prefix(mut self, prefix: PathBuf) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    pub fn generate(&self, manifest: &BuildManifest) -> Result<PackageConfigFiles> {
+        let prefix = self.prefix.clone()
+            .unwrap_or_else(|| self.cache_dir.join(&manifest.rustc_target).join("prefix"));
+
+        let pkgconfig_dir = prefix.join("share").join("pkgconfig");
+        let cmake_dir = prefix.join("share").join("cmake").join(&manifest.crate_name);
+        for dir in [&pkgconfig_dir, &cmake_dir] {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+
+        let pkg_config = pkgconfig_dir.join(format!("{}.pc", manifest.crate_name));
+        fs::write(&pkg_config, pkg_config_source(manifest))
+            .with_context(|| format!("Failed to write {}", pkg_config.display()))?;
+
+        let cmake_config = cmake_dir.join(format!("{}Config.cmake", manifest.crate_name));
+        fs::write(&cmake_config, cmake_config_source(manifest))
+            .with_context(|| format!("Failed to write {}", cmake_config.display()))?;
+
+        let cmake_version = cmake_dir.join(format!("{}ConfigVersion.cmake", manifest.crate_name));
+        fs::write(&cmake_version, cmake_version_source(manifest))
+            .with_context(|| format!("Failed to write {}", cmake_version.display()))?;
+
+        Ok(PackageConfigFiles {
+            prefix,
+            pkg_config,
+            cmake_config,
+            cmake_version,
+        })
+    }
+}
+
+impl Default for PackageConfigGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn version(manifest: &BuildManifest) -> &str {
+    if manifest.crate_version.is_empty() { "0.0.0" } else { &manifest.crate_version }
+}
+
+fn pkg_config_source(manifest: &BuildManifest) -> String {
+    let mut private = Vec::new();
+    for dir in &manifest.link_search {
+        private.push(format!("-L{}", dir.display()));
+    }
+    for lib in &manifest.link_libs {
+        private.push(format!("-l{}", lib));
+    }
+    for framework in &manifest.frameworks {
+        private.push(format!("-framework {}", framework));
+    }
+
+    let mut pc = String::new();
+    // share/pkgconfig is two levels below the prefix
+    pc.push_str("prefix=${pcfiledir}/../..\n");
+    pc.push_str("libdir=${prefix}/lib\n");
+    let _ = writeln!(pc, "includedir=${{prefix}}/include/{}\n", manifest.crate_name);
+    let _ = writeln!(pc, "Name: {}", manifest.crate_name);
+    let _ = writeln!(pc, "Description: C bindings for the {} Rust crate", manifest.crate_name);
+    let _ = writeln!(pc, "Version: {}", version(manifest));
+    pc.push_str("Cflags: -I${includedir}\n");
+    let _ = writeln!(pc, "Libs: -L${{libdir}} -l{}", manifest.crate_name.replace('-', "_"));
+    if !private.is_empty() {
+        let _ = writeln!(pc, "Libs.private: {}", private.join(" "));
+    }
+    pc
+}
+
+fn cmake_config_source(manifest: &BuildManifest) -> String {
+    let name = &manifest.crate_name;
+    let target = format!("{0}::{0}", name);
+    let names = LibraryNames::for_target(name, &manifest.kind, &manifest.rustc_target);
+    let shared = manifest.kind == "cdylib";
+
+    let mut interface_libs: Vec<String> = manifest.link_libs.clone();
+    interface_libs.extend(manifest.frameworks.iter().map(|f| format!("-framework {}", f)));
+
+    let mut cmake = String::new();
+    let _ = writeln!(cmake, "# Generated by ghostbind. Do not edit.");
+    // share/cmake/<crate> is three levels below the prefix
+    cmake.push_str("get_filename_component(_ghostbind_prefix \"${CMAKE_CURRENT_LIST_DIR}/../../..\" ABSOLUTE)\n\n");
+    let _ = writeln!(cmake, "if(NOT TARGET {})", target);
+    let _ = writeln!(cmake, "  add_library({} {} IMPORTED)", target, if shared { "SHARED" } else { "STATIC" });
+    let _ = writeln!(cmake, "  set_target_properties({} PROPERTIES", target);
+    match &names.import_library {
+        Some(import_library) => {
+            let _ = writeln!(cmake, "    IMPORTED_LOCATION \"${{_ghostbind_prefix}}/bin/{}\"", names.library);
+            let _ = writeln!(cmake, "    IMPORTED_IMPLIB \"${{_ghostbind_prefix}}/lib/{}\"", import_library);
+        }
+        None => {
+            let _ = writeln!(cmake, "    IMPORTED_LOCATION \"${{_ghostbind_prefix}}/lib/{}\"", names.library);
+        }
+    }
+    let _ = writeln!(cmake, "    INTERFACE_INCLUDE_DIRECTORIES \"${{_ghostbind_prefix}}/include/{}\"", name);
+    if !manifest.link_search.is_empty() {
+        let dirs: Vec<String> = manifest.link_search.iter().map(|d| d.display().to_string()).collect();
+        let _ = writeln!(cmake, "    INTERFACE_LINK_DIRECTORIES \"{}\"", dirs.join(";"));
+    }
+    if !interface_libs.is_empty() {
+        let _ = writeln!(cmake, "    INTERFACE_LINK_LIBRARIES \"{}\"", interface_libs.join(";"));
+    }
+    cmake.push_str("  )\nendif()\n\nunset(_ghostbind_prefix)\n");
+    cmake
+}
+
+fn cmake_version_source(manifest: &BuildManifest) -> String {
+    // Any request for an equal or older version is satisfied, like CMake's AnyNewerVersion
+    format!(
+        "set(PACKAGE_VERSION \"{}\")\n\n\
+         if(PACKAGE_FIND_VERSION VERSION_GREATER PACKAGE_VERSION)\n  set(PACKAGE_VERSION_COMPATIBLE FALSE)\n\
+         else()\n  set(PACKAGE_VERSION_COMPATIBLE TRUE)\n  \
+         if(PACKAGE_FIND_VERSION VERSION_EQUAL PACKAGE_VERSION)\n    set(PACKAGE_VERSION_EXACT TRUE)\n  endif()\nendif()\n",
+        version(manifest)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(kind: &str, rustc_target: &str) -> BuildManifest {
+        BuildManifest {
+            crate_name: "my-crate".to_string(),
+            crate_version: "1.2.3".to_string(),
+            kind: kind.to_string(),
+            artifact: PathBuf::from("my-crate.a"),
+            headers: vec![PathBuf::from("my-crate.h")],
+            header_files: Vec::new(),
+            rustc_target: rustc_target.to_string(),
+            link_libs: vec!["pthread".to_string(), "dl".to_string()],
+            link_search: Vec::new(),
+            frameworks: Vec::new(),
+            zig_wrappers: None,
+            cxx_wrappers: None,
+            third_party_notices: None,
+            sbom: None,
+            provenance: None,
+            abi: None,
+        }
+    }
+
+    #[test]
+    fn test_pkg_config_and_cmake() {
+        let pc = pkg_config_source(&manifest("staticlib", "x86_64-unknown-linux-gnu"));
+        assert!(pc.contains("includedir=${prefix}/include/my-crate\n"));
+        assert!(pc.contains("Version: 1.2.3\n"));
+        assert!(pc.contains("Libs: -L${libdir} -lmy_crate\n"));
+        assert!(pc.contains("Libs.private: -lpthread -ldl\n"));
+
+        let cmake = cmake_config_source(&manifest("cdylib", "x86_64-pc-windows-msvc"));
+        assert!(cmake.contains("add_library(my-crate::my-crate SHARED IMPORTED)"));
+        assert!(cmake.contains("IMPORTED_LOCATION \"${_ghostbind_prefix}/bin/my_crate.dll\""));
+        assert!(cmake.contains("IMPORTED_IMPLIB \"${_ghostbind_prefix}/lib/my_crate.dll.lib\""));
+        assert!(cmake.contains("INTERFACE_LINK_LIBRARIES \"pthread;dl\""));
+    }
+}
@@ -0,0 +1,800 @@
+use anyhow::{anyhow, Context};
+use cargo_metadata::Metadata;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::abi::{AbiSnapshot, ChangeKind, ABI_SNAPSHOT_FILE};
+use crate::artifact_discovery::{ArtifactDiscovery, DiscoveredArtifact};
+use crate::cargo_integration::{BuildProfile, CargoBuilder, CrateInfo};
+use crate::config::{GhostbindConfig, CONFIG_FILE};
+use crate::error::Error;
+use crate::header_check::HeaderChecker;
+use crate::cxx_wrappers::CxxWrapperGenerator;
+use crate::header_generation::{c_headers, ConfigStatus, GeneratedHeader, HeaderGenerator, HeaderLanguage, HeaderMode};
+use crate::layout_check::LayoutVerifier;
+use crate::licenses::{LicenseCollector, LicenseReport};
+use crate::manifest::{BuildManifest, ManifestFormat, ManifestGenerator};
+use crate::package_config::PackageConfigGenerator;
+use crate::provenance::Provenance;
+use crate::reproducible::normalize_archive;
+use crate::sbom::{SbomFormat, SbomGenerator};
+use crate::symbol_check::{SymbolCheckMode, SymbolChecker};
+use crate::target_cfg::TargetCfg;
+use crate::target_mapping::{get_host_target, TargetMapping};
+use crate::zig_wrappers::ZigWrapperGenerator;
+
+/// Something that happened during a [`Pipeline`] run. The `Display` form is the line
+/// `ghostbind build` prints for it.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Event {
+    TargetSelected { rust_target: String },
+    CrateResolved { name: String, targets: usize },
+    CargoStarted { command: String },
+    CrateBuilt { name: String },
+    ArtifactsFound { count: usize },
+    ArtifactCached { kind: String, from: PathBuf, to: PathBuf },
+    HeaderGenerationStarted { crate_name: String },
+    HeaderGenerated { path: PathBuf },
+    ManifestWritten { path: PathBuf },
+    /// Any other file was written; `what` names it, e.g. `CycloneDX SBOM`
+    Generated { what: String, path: PathBuf },
+    Installed { path: PathBuf },
+    PackageWritten { archive: PathBuf, zig_hash: String },
+    /// `ghostbind watch` saw these files change and is rebuilding
+    ChangesDetected { paths: Vec<PathBuf> },
+    /// A check passed
+    CheckPassed(String),
+    /// A problem that doesn't fail the build; also collected in [`BuildOutcome::warnings`]
+    Warning(String),
+    /// A problem that fails the check it came from
+    Error(String),
+    /// Any other line of check output
+    Note(String),
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::TargetSelected { rust_target } => write!(f, "Building crate with target: {}", rust_target),
+            Event::CrateResolved { name, targets } => write!(f, "Found crate: {} with {} targets", name, targets),
+            Event::CargoStarted { command } => write!(f, "Running {}", command),
+            Event::CrateBuilt { .. } => write!(f, "Crate built successfully"),
+            Event::ArtifactsFound { count } => write!(f, "Found {} artifacts", count),
+            Event::ArtifactCached { kind, from, to } => {
+                write!(f, "Cached {} artifact: {} -> {}", kind, from.display(), to.display())
+            }
+            Event::HeaderGenerationStarted { crate_name } => {
+                write!(f, "Generating header for crate '{}' with cbindgen...", crate_name)
+            }
+            Event::HeaderGenerated { path } => write!(f, "Generated header: {}", path.display()),
+            Event::ManifestWritten { path } => write!(f, "Generated manifest: {}", path.display()),
+            Event::Generated { what, path } => write!(f, "Generated {}: {}", what, path.display()),
+            Event::Installed { path } => write!(f, "Installed {}", path.display()),
+            Event::PackageWritten { archive, .. } => write!(f, "Generated package: {}", archive.display()),
+            Event::ChangesDetected { paths } => match paths.as_slice() {
+                [path] => write!(f, "Changed: {}", path.display()),
+                [path, rest @ ..] => write!(f, "Changed: {} and {} more", path.display(), rest.len()),
+                [] => write!(f, "Changed"),
+            },
+            Event::CheckPassed(message) => write!(f, "✓ {}", message),
+            Event::Warning(message) => write!(f, "warning: {}", message),
+            Event::Error(message) => write!(f, "error: {}", message),
+            Event::Note(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Event {
+    /// The event as a JSON object with an `event` name, for `--message-format json`.
+    pub fn to_json(&self) -> Value {
+        match self {
+            Event::TargetSelected { rust_target } => json!({ "event": "target-resolved", "rust_target": rust_target }),
+            Event::CrateResolved { name, targets } => json!({ "event": "crate-resolved", "name": name, "targets": targets }),
+            Event::CargoStarted { command } => json!({ "event": "cargo-started", "command": command }),
+            Event::CrateBuilt { name } => json!({ "event": "cargo-finished", "name": name }),
+            Event::ArtifactsFound { count } => json!({ "event": "artifacts-found", "count": count }),
+            Event::ArtifactCached { kind, from, to } => {
+                json!({ "event": "artifact-cached", "kind": kind, "from": from, "to": to })
+            }
+            Event::HeaderGenerationStarted { crate_name } => json!({ "event": "header-started", "crate_name": crate_name }),
+            Event::HeaderGenerated { path } => json!({ "event": "header-generated", "path": path }),
+            Event::ManifestWritten { path } => json!({ "event": "manifest-written", "path": path }),
+            Event::Generated { what, path } => json!({ "event": "file-generated", "what": what, "path": path }),
+            Event::Installed { path } => json!({ "event": "installed", "path": path }),
+            Event::PackageWritten { archive, zig_hash } => {
+                json!({ "event": "package-written", "archive": archive, "zig_hash": zig_hash })
+            }
+            Event::ChangesDetected { paths } => json!({ "event": "changes-detected", "paths": paths }),
+            Event::CheckPassed(message) => json!({ "event": "check-passed", "message": message }),
+            Event::Warning(message) => json!({ "event": "diagnostic", "level": "warning", "message": message }),
+            Event::Error(message) => json!({ "event": "diagnostic", "level": "error", "message": message }),
+            Event::Note(message) => json!({ "event": "diagnostic", "level": "note", "message": message }),
+        }
+    }
+}
+
+type EventHandler = Box<dyn Fn(&Event)>;
+
+/// What a successful [`Pipeline`] run produced.
+#[derive(Debug, Clone)]
+pub struct BuildOutcome {
+    pub rust_target: String,
+    pub artifacts: Vec<DiscoveredArtifact>,
+    pub headers: Vec<GeneratedHeader>,
+    pub manifest: BuildManifest,
+    /// The first manifest format written
+    pub manifest_path: PathBuf,
+    pub warnings: Vec<String>,
+}
+
+/// Builds a crate and produces everything `ghostbind build` does: cached artifacts,
+/// headers, the manifest and whichever checks and extra files are enabled.
+///
+/// ```no_run
+/// let outcome = ghostbind::Pipeline::new("my_crate/Cargo.toml")
+///     .zig_target(Some("aarch64-linux-gnu".to_string()))
+///     .on_event(|event| eprintln!("{}", event))
+///     .run()?;
+/// println!("{}", outcome.manifest_path.display());
+/// # Ok::<(), ghostbind::Error>(())
+/// ```
+pub struct Pipeline {
+    manifest_path: PathBuf,
+    zig_target: Option<String>,
+    rust_target: Option<String>,
+    profile: BuildProfile,
+    features: Vec<String>,
+    no_default_features: bool,
+    cbindgen_config: Option<PathBuf>,
+    generate_cbindgen_config: bool,
+    header_mode: HeaderMode,
+    header_languages: Vec<HeaderLanguage>,
+    zig_wrappers: bool,
+    cxx_wrappers: bool,
+    check_header: bool,
+    symbol_check: SymbolCheckMode,
+    verify_layout: bool,
+    abi_check: bool,
+    abi_update: bool,
+    manifest_formats: Vec<ManifestFormat>,
+    pkg_config: bool,
+    licenses: bool,
+    sbom: Option<SbomFormat>,
+    reproducible: bool,
+    on_event: Option<EventHandler>,
+}
+
+impl Pipeline {
+    pub fn new(manifest_path: impl AsRef<Path>) -> Self {
+        Self {
+            manifest_path: manifest_path.as_ref().to_path_buf(),
+            zig_target: None,
+            rust_target: None,
+            profile: BuildProfile::Release,
+            features: Vec::new(),
+            no_default_features: false,
+            cbindgen_config: None,
+            generate_cbindgen_config: false,
+            header_mode: HeaderMode::PerTarget,
+            header_languages: Vec::new(),
+            zig_wrappers: false,
+            cxx_wrappers: false,
+            check_header: false,
+            symbol_check: SymbolCheckMode::Warn,
+            verify_layout: false,
+            abi_check: false,
+            abi_update: false,
+            manifest_formats: vec![ManifestFormat::Json],
+            pkg_config: false,
+            licenses: false,
+            sbom: None,
+            reproducible: false,
+            on_event: None,
+        }
+    }
+
+    /// Zig target triple, mapped to the Rust one.
+    pub fn zig_target(mut self, zig_target: Option<String>) -> Self {
+        self.zig_target = zig_target;
+        self
+    }
+
+    /// Rust target triple, taking precedence over the Zig target. Defaults to the host.
+    pub fn rust_target(mut self, rust_target: Option<String>) -> Self {
+        self.rust_target = rust_target;
+        self
+    }
+
+    pub fn profile(mut self, profile: BuildProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn features(mut self, features: Vec<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn no_default_features(mut self, no_default: bool) -> Self {
+        self.no_default_features = no_default;
+        self
+    }
+
+    pub fn cbindgen_config(mut self, cbindgen_config: Option<PathBuf>) -> Self {
+        self.cbindgen_config = cbindgen_config;
+        self
+    }
+
+    pub fn generate_cbindgen_config(mut self, generate: bool) -> Self {
+        self.generate_cbindgen_config = generate;
+        self
+    }
+
+    pub fn header_mode(mut self, header_mode: HeaderMode) -> Self {
+        self.header_mode = header_mode;
+        self
+    }
+
+    /// One header per language instead of a single one in the cbindgen config's language.
+    pub fn header_languages(mut self, languages: Vec<HeaderLanguage>) -> Self {
+        self.header_languages = languages;
+        self
+    }
+
+    pub fn zig_wrappers(mut self, zig_wrappers: bool) -> Self {
+        self.zig_wrappers = zig_wrappers;
+        self
+    }
+
+    /// A C++ header with `unique_ptr` aliases for the opaque handles in the C header.
+    pub fn cxx_wrappers(mut self, cxx_wrappers: bool) -> Self {
+        self.cxx_wrappers = cxx_wrappers;
+        self
+    }
+
+    pub fn check_header(mut self, check_header: bool) -> Self {
+        self.check_header = check_header;
+        self
+    }
+
+    pub fn symbol_check(mut self, mode: SymbolCheckMode) -> Self {
+        self.symbol_check = mode;
+        self
+    }
+
+    pub fn verify_layout(mut self, verify_layout: bool) -> Self {
+        self.verify_layout = verify_layout;
+        self
+    }
+
+    pub fn abi_check(mut self, abi_check: bool) -> Self {
+        self.abi_check = abi_check;
+        self
+    }
+
+    pub fn abi_update(mut self, abi_update: bool) -> Self {
+        self.abi_update = abi_update;
+        self
+    }
+
+    pub fn manifest_formats(mut self, formats: Vec<ManifestFormat>) -> Self {
+        self.manifest_formats = formats;
+        self
+    }
+
+    pub fn pkg_config(mut self, pkg_config: bool) -> Self {
+        self.pkg_config = pkg_config;
+        self
+    }
+
+    pub fn licenses(mut self, licenses: bool) -> Self {
+        self.licenses = licenses;
+        self
+    }
+
+    pub fn sbom(mut self, sbom: Option<SbomFormat>) -> Self {
+        self.sbom = sbom;
+        self
+    }
+
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
+    /// Called for every [`Event`]; without a callback the pipeline is silent.
+    pub fn on_event(mut self, on_event: impl Fn(&Event) + 'static) -> Self {
+        self.on_event = Some(Box::new(on_event));
+        self
+    }
+
+    pub fn run(&self) -> Result<BuildOutcome, Error> {
+        let warnings = RefCell::new(Vec::new());
+        let emit = |event: Event| {
+            if let Event::Warning(message) = &event {
+                warnings.borrow_mut().push(message.clone());
+            }
+            if let Some(on_event) = &self.on_event {
+                on_event(&event);
+            }
+        };
+
+        let mut outcome = self.run_steps(&emit)?;
+        outcome.warnings = warnings.take();
+        Ok(outcome)
+    }
+
+    fn run_steps(&self, emit: &dyn Fn(Event)) -> Result<BuildOutcome, Error> {
+        let rust_target = self.resolve_target()?;
+        emit(Event::TargetSelected { rust_target: rust_target.clone() });
+
+        let mut cargo_builder = CargoBuilder::new(&self.manifest_path)
+            .profile(self.profile.clone())
+            .features(self.features.clone())
+            .no_default_features(self.no_default_features)
+            .reproducible(self.reproducible);
+
+        let is_cross_compile = rust_target != get_host_target()?;
+        if is_cross_compile {
+            cargo_builder = cargo_builder.target(rust_target.clone());
+        }
+
+        let crate_info = cargo_builder.get_metadata()
+            .context("Failed to get crate metadata")?;
+        emit(Event::CrateResolved { name: crate_info.name.clone(), targets: crate_info.targets.len() });
+
+        if self.generate_cbindgen_config {
+            let report = HeaderGenerator::new(None)
+                .create_default_cbindgen_config(&crate_info.manifest_dir, &crate_info.name, false)?;
+            match report.status {
+                ConfigStatus::Created => {
+                    emit(Event::Generated { what: "default cbindgen config".to_string(), path: report.path });
+                }
+                ConfigStatus::Kept => emit(Event::Warning(format!(
+                    "{} differs from ghostbind's template; run `ghostbind cbindgen-config --diff` to compare or --force to replace it",
+                    report.path.display()
+                ))),
+                ConfigStatus::Unchanged | ConfigStatus::Overwritten => {}
+            }
+        }
+
+        emit(Event::CargoStarted { command: cargo_builder.command_line()? });
+        cargo_builder.build()
+            .context("Failed to build crate")?;
+        emit(Event::CrateBuilt { name: crate_info.name.clone() });
+
+        let source_date_epoch = if self.reproducible { Some(cargo_builder.source_date_epoch()?) } else { None };
+        let provenance = Provenance::collect(&crate_info, &cargo_builder, &self.profile, self.no_default_features, source_date_epoch)
+            .context("Failed to record build provenance")?;
+
+        let artifact_discovery = ArtifactDiscovery::new(
+            &crate_info.target_directory,
+            Some(rust_target.clone()),
+            self.profile.clone(),
+        ).cross_compile(is_cross_compile);
+
+        let artifacts = artifact_discovery.discover_artifacts(&crate_info)
+            .context("Failed to discover artifacts")?;
+        if artifacts.is_empty() {
+            return Err(Error::ArtifactNotFound {
+                crate_name: crate_info.name.clone(),
+                target_dir: crate_info.target_directory.clone(),
+            });
+        }
+        emit(Event::ArtifactsFound { count: artifacts.len() });
+
+        artifact_discovery.cache_artifacts(&artifacts)
+            .context("Failed to cache artifacts")?;
+        for artifact in &artifacts {
+            if self.reproducible {
+                normalize_archive(&artifact.cached_path)?;
+            }
+            emit(Event::ArtifactCached {
+                kind: artifact.kind.as_str().to_string(),
+                from: artifact.original_path.clone(),
+                to: artifact.cached_path.clone(),
+            });
+        }
+
+        // Generate headers with the same feature set the crate was built with
+        emit(Event::HeaderGenerationStarted { crate_name: crate_info.name.clone() });
+        let header_generator = HeaderGenerator::new(self.cbindgen_config.clone())
+            .features(self.features.clone())
+            .no_default_features(self.no_default_features)
+            .profile(self.profile.clone())
+            .mode(self.header_mode)
+            .languages(self.header_languages.clone())
+            .reproducible(self.reproducible);
+        let headers = header_generator.generate_headers(&crate_info, Some(&rust_target))
+            .context("Failed to generate headers")?;
+        for header in &headers {
+            emit(Event::HeaderGenerated { path: header.header_path.clone() });
+        }
+        // The checks and Zig wrappers only understand C
+        let checked_headers = c_headers(&headers);
+        let header_paths: Vec<PathBuf> = checked_headers.iter().map(|h| h.header_path.clone()).collect();
+        let type_prefix = header_generator.type_prefix(&crate_info)?;
+
+        if header_paths.is_empty() {
+            let requested: Vec<&str> = [
+                (self.check_header, "--check-header"),
+                (self.verify_layout, "--verify-layout"),
+                (self.symbol_check == SymbolCheckMode::Error, "--symbol-check error"),
+                (self.zig_wrappers, "--zig-wrappers"),
+            ]
+            .into_iter()
+            .filter_map(|(enabled, flag)| enabled.then_some(flag))
+            .collect();
+            if !requested.is_empty() {
+                return Err(Error::InvalidConfig(format!(
+                    "A C header is required by {}, but none was generated; add `c` to --header-lang",
+                    requested.join(", ")
+                )));
+            }
+            if self.symbol_check == SymbolCheckMode::Warn {
+                emit(Event::Note("skipping the symbol check, which compares C headers with the artifact".to_string()));
+            }
+        }
+
+        if self.check_header {
+            check_headers(&crate_info.name, &header_paths, Some(&rust_target), emit)?;
+        }
+
+        if self.verify_layout {
+            if is_cross_compile {
+                emit(Event::Warning(format!("--verify-layout only runs for host builds, skipping for {}", rust_target)));
+            } else {
+                let layout_verifier = LayoutVerifier::new()
+                    .type_prefix(type_prefix.clone())
+                    .features(self.features.clone())
+                    .no_default_features(self.no_default_features)
+                    .profile(self.profile.clone());
+                check_layouts(&crate_info, &header_paths, Some(&rust_target), &layout_verifier, emit)?;
+            }
+        }
+
+        if self.symbol_check != SymbolCheckMode::Off && !header_paths.is_empty() {
+            for artifact in &artifacts {
+                check_symbols(&crate_info.lib_target()?.name, &artifact.cached_path, &header_paths, self.symbol_check, emit)?;
+            }
+        }
+
+        // Generate manifest for the first (primary) artifact
+        let primary_artifact = &artifacts[0];
+        let manifest_generator = ManifestGenerator::new()
+            .formats(self.manifest_formats.clone());
+        let mut manifest = manifest_generator.generate_manifest(
+            &crate_info.name,
+            primary_artifact,
+            &headers,
+            &rust_target,
+        ).context("Failed to generate manifest")?;
+        manifest.crate_version = crate_info.version.clone();
+        manifest.provenance = Some(provenance);
+
+        if self.zig_wrappers {
+            let wrapper = ZigWrapperGenerator::new()
+                .type_prefix(type_prefix.clone())
+                .generate(&crate_info, &checked_headers, Some(&rust_target))
+                .context("Failed to generate Zig wrappers")?;
+            emit(Event::Generated { what: "Zig wrappers".to_string(), path: wrapper.wrapper_path.clone() });
+            manifest.zig_wrappers = Some(wrapper.wrapper_path);
+        }
+
+        if self.cxx_wrappers {
+            let c_header = headers.iter()
+                .find(|header| header.language == HeaderLanguage::C)
+                .ok_or_else(|| Error::InvalidConfig("C++ wrappers are built on the C header, but no C header was generated".to_string()))?;
+            let wrapper = CxxWrapperGenerator::new()
+                .type_prefix(type_prefix)
+                .generate(c_header)
+                .context("Failed to generate C++ wrappers")?;
+            if wrapper.handles.is_empty() {
+                emit(Event::Warning(format!("no opaque type in {} has a matching `*_free` function", c_header.header_path.display())));
+            }
+            emit(Event::Generated { what: "C++ wrappers".to_string(), path: wrapper.wrapper_path.clone() });
+            manifest.cxx_wrappers = Some(wrapper.wrapper_path);
+        }
+
+        // The dependency graph as linked for this target
+        let platform_metadata = if self.licenses || self.sbom.is_some() {
+            Some(cargo_builder.resolve_for_platform(&rust_target)?)
+        } else {
+            None
+        };
+
+        if let (true, Some(metadata)) = (self.licenses, &platform_metadata) {
+            let report = collect_licenses(&crate_info, metadata, &rust_target)?;
+            emit(Event::Generated { what: "third-party notices".to_string(), path: report.notices.clone() });
+            check_licenses(&report, emit)?;
+            manifest.third_party_notices = Some(report.notices);
+        }
+
+        if let (Some(format), Some(metadata)) = (self.sbom, &platform_metadata) {
+            let generator = SbomGenerator::new(format)
+                .profile(self.profile.as_str())
+                .features(crate_info.enabled_features.clone())
+                .source_date_epoch(source_date_epoch);
+            for artifact in &artifacts {
+                let mut artifact_manifest = manifest.clone();
+                artifact_manifest.artifact = artifact.cached_path.clone();
+                artifact_manifest.kind = artifact.kind.as_str().to_string();
+                let sbom_path = generator.generate(&artifact_manifest, metadata)
+                    .context("Failed to generate SBOM")?;
+                emit(Event::Generated { what: format!("{} SBOM", format.as_str()), path: sbom_path.clone() });
+                if artifact.cached_path == manifest.artifact {
+                    manifest.sbom = Some(sbom_path);
+                }
+            }
+        }
+
+        if self.pkg_config {
+            let files = PackageConfigGenerator::new().generate(&manifest)
+                .context("Failed to generate pkg-config and CMake files")?;
+            emit(Event::Generated { what: "pkg-config file".to_string(), path: files.pkg_config });
+            emit(Event::Generated { what: "CMake package config".to_string(), path: files.cmake_config });
+        }
+
+        // Builds without a C header have no C ABI to record
+        if manifest.c_headers().is_ok() {
+            let target_cfg = TargetCfg::for_target(Some(&rust_target), &self.profile)?;
+            manifest.abi = Some(AbiSnapshot::from_manifest(&manifest, &target_cfg).context("Failed to read the build's ABI")?);
+        }
+
+        if self.abi_check || self.abi_update {
+            let snapshot = manifest.abi.clone()
+                .ok_or_else(|| Error::InvalidConfig("The ABI snapshot is taken from the C header, but no C header was generated".to_string()))?;
+            let snapshot_path = crate_info.manifest_dir.join(ABI_SNAPSHOT_FILE);
+            if self.abi_update {
+                snapshot.write(&snapshot_path)?;
+                emit(Event::Generated { what: "ABI snapshot".to_string(), path: snapshot_path });
+            } else {
+                check_abi_snapshot(&snapshot, &snapshot_path, emit)?;
+            }
+        }
+
+        let manifest_paths = manifest_generator.write_manifests(&manifest, Some(&rust_target))
+            .context("Failed to write manifest")?;
+        for path in &manifest_paths {
+            emit(Event::ManifestWritten { path: path.clone() });
+        }
+        let manifest_path = manifest_paths.into_iter().next()
+            .ok_or_else(|| anyhow!("No manifest format selected"))?;
+
+        Ok(BuildOutcome {
+            rust_target,
+            artifacts,
+            headers,
+            manifest,
+            manifest_path,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// The Rust target to build for: the override, else the mapped Zig target, else the host.
+    fn resolve_target(&self) -> Result<String, Error> {
+        if let Some(rust_target) = &self.rust_target {
+            return Ok(rust_target.clone());
+        }
+
+        let Some(zig_target) = &self.zig_target else {
+            return Ok(get_host_target()?);
+        };
+
+        let target_mapping = TargetMapping::new();
+        if let Some(rust_target) = target_mapping.map_target(zig_target) {
+            return Ok(rust_target.to_string());
+        }
+
+        // Unmapped targets are passed through, which only works when they name a Rust target
+        if let Some(known) = rustc_targets()
+            && !known.iter().any(|t| t == zig_target)
+        {
+            return Err(Error::TargetUnsupported {
+                target: zig_target.clone(),
+                reason: "it has no Rust equivalent. Pass the Rust triple with --rust-target".to_string(),
+            });
+        }
+        Ok(zig_target.clone())
+    }
+}
+
+/// The targets rustc knows about, when it can list them.
+fn rustc_targets() -> Option<Vec<String>> {
+    let output = Command::new("rustc").args(["--print", "target-list"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect())
+}
+
+pub(crate) fn check_headers(crate_name: &str, headers: &[PathBuf], target: Option<&str>, emit: &dyn Fn(Event)) -> Result<(), Error> {
+    let header_checker = HeaderChecker::new();
+    let report = header_checker.check_headers(crate_name, headers, target)
+        .context("Failed to check headers")?;
+
+    for diagnostic in &report.diagnostics {
+        let item = diagnostic.item.as_deref()
+            .map(|item| format!(" (in `{}`)", item))
+            .unwrap_or_default();
+        let message = if diagnostic.line > 0 {
+            format!(
+                "{}:{}:{}{}: {} [{}]",
+                diagnostic.file.display(),
+                diagnostic.line,
+                diagnostic.column,
+                item,
+                diagnostic.message,
+                diagnostic.tool
+            )
+        } else {
+            format!("{} [{}]", diagnostic.message, diagnostic.tool)
+        };
+        match diagnostic.severity.as_str() {
+            "warning" => emit(Event::Warning(message)),
+            _ => emit(Event::Error(message)),
+        }
+    }
+
+    if report.has_errors() {
+        return Err(Error::CheckFailed(format!("Header check failed with {} error(s)", report.error_count())));
+    }
+
+    emit(Event::CheckPassed(format!("Headers compile cleanly ({})", report.tools.join(", "))));
+    Ok(())
+}
+
+pub(crate) fn check_layouts(
+    crate_info: &CrateInfo,
+    headers: &[PathBuf],
+    target: Option<&str>,
+    layout_verifier: &LayoutVerifier,
+    emit: &dyn Fn(Event),
+) -> Result<(), Error> {
+    let report = layout_verifier.verify(crate_info, headers, target)
+        .context("Failed to verify layouts")?;
+
+    for skipped in &report.skipped {
+        emit(Event::Warning(format!("skipping layout check for `{}`, which is not reachable from the crate root", skipped)));
+    }
+    for mismatch in &report.mismatches {
+        emit(Event::Error(format!("layout mismatch: {}", mismatch)));
+    }
+
+    if !report.mismatches.is_empty() {
+        return Err(Error::CheckFailed(format!("Layout verification failed with {} mismatch(es)", report.mismatches.len())));
+    }
+
+    emit(Event::CheckPassed(format!("Layouts match for {} type(s)", report.checked.len())));
+    Ok(())
+}
+
+pub(crate) fn check_symbols(
+    lib_name: &str,
+    artifact: &Path,
+    headers: &[PathBuf],
+    mode: SymbolCheckMode,
+    emit: &dyn Fn(Event),
+) -> Result<(), Error> {
+    let symbol_checker = SymbolChecker::new();
+    let report = symbol_checker.check(lib_name, artifact, headers)
+        .context("Failed to check exported symbols")?;
+
+    let report_problem = |message: String| match mode {
+        SymbolCheckMode::Error => emit(Event::Error(message)),
+        _ => emit(Event::Warning(message)),
+    };
+    let artifact_name = artifact.file_name().unwrap_or_default().to_string_lossy();
+    for symbol in &report.missing {
+        report_problem(format!("`{}` is declared in the header but not exported by {}", symbol, artifact_name));
+    }
+    for symbol in &report.undeclared {
+        report_problem(format!("`{}` is exported by {} but not declared in any header", symbol, artifact_name));
+    }
+
+    if report.is_clean() {
+        emit(Event::CheckPassed(format!("Header declarations match the symbols exported by {}", artifact_name)));
+    } else if mode == SymbolCheckMode::Error {
+        return Err(Error::CheckFailed(format!(
+            "Symbol check failed: {} missing, {} undeclared",
+            report.missing.len(),
+            report.undeclared.len()
+        )));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn check_abi_snapshot(snapshot: &AbiSnapshot, snapshot_path: &Path, emit: &dyn Fn(Event)) -> Result<(), Error> {
+    if !snapshot_path.exists() {
+        return Err(Error::CheckFailed(format!(
+            "No ABI snapshot at {}. Run `ghostbind build --abi-update` to create it",
+            snapshot_path.display()
+        )));
+    }
+
+    let committed = AbiSnapshot::read(snapshot_path)?;
+    if committed.rustc_target != snapshot.rustc_target {
+        emit(Event::Warning(format!(
+            "{} was taken for {}, comparing with a {} build",
+            ABI_SNAPSHOT_FILE, committed.rustc_target, snapshot.rustc_target
+        )));
+    }
+
+    let diff = committed.diff(snapshot);
+    if diff.changes.is_empty() {
+        emit(Event::CheckPassed(format!("C ABI matches {}", snapshot_path.display())));
+        return Ok(());
+    }
+
+    for change in &diff.changes {
+        emit(Event::Note(format!("{}: {}: {}", change.kind.as_str(), change.item, change.description)));
+    }
+
+    Err(Error::CheckFailed(format!(
+        "C ABI differs from {} ({} breaking, {} additive, {} compatible change(s)). Run `ghostbind build --abi-update` if the change is intended",
+        snapshot_path.display(),
+        diff.count(ChangeKind::Breaking),
+        diff.count(ChangeKind::Additive),
+        diff.count(ChangeKind::Compatible)
+    )))
+}
+
+pub(crate) fn collect_licenses(crate_info: &CrateInfo, metadata: &Metadata, rust_target: &str) -> anyhow::Result<LicenseReport> {
+    let config = GhostbindConfig::load(&crate_info.manifest_dir)?;
+
+    LicenseCollector::new()
+        .deny(config.licenses.deny)
+        .generate(&crate_info.name, metadata, rust_target)
+        .context("Failed to collect licenses")
+}
+
+pub(crate) fn check_licenses(report: &LicenseReport, emit: &dyn Fn(Event)) -> Result<(), Error> {
+    for package in &report.unknown {
+        emit(Event::Warning(format!("{} does not declare a license", package)));
+    }
+
+    if !report.denied.is_empty() {
+        for package in &report.denied {
+            emit(Event::Error(format!("{} is only available under licenses denied in {}", package, CONFIG_FILE)));
+        }
+        return Err(Error::CheckFailed(format!("{} dependency license(s) denied", report.denied.len())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_target() {
+        let pipeline = Pipeline::new("Cargo.toml").zig_target(Some("aarch64-macos".to_string()));
+        assert_eq!(pipeline.resolve_target().unwrap(), "aarch64-apple-darwin");
+
+        let pipeline = pipeline.rust_target(Some("x86_64-unknown-freebsd".to_string()));
+        assert_eq!(pipeline.resolve_target().unwrap(), "x86_64-unknown-freebsd");
+
+        if rustc_targets().is_some() {
+            let error = Pipeline::new("Cargo.toml")
+                .zig_target(Some("sparc-plan9".to_string()))
+                .resolve_target()
+                .unwrap_err();
+            assert!(matches!(error, Error::TargetUnsupported { .. }));
+        }
+    }
+
+    #[test]
+    fn test_event_json() {
+        let event = Event::ManifestWritten { path: PathBuf::from("out/foo-manifest.json") };
+        assert_eq!(event.to_json(), json!({ "event": "manifest-written", "path": "out/foo-manifest.json" }));
+        assert_eq!(event.to_string(), "Generated manifest: out/foo-manifest.json");
+
+        let warning = Event::Warning("careful".to_string());
+        assert_eq!(warning.to_json()["level"], "warning");
+    }
+}
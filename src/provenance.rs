@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::cargo_integration::{BuildProfile, CargoBuilder, CrateInfo};
+use crate::header_generation::cbindgen_version;
+use crate::sbom::creation_time;
+
+/// Where a build came from: enough to trace a binary back to the sources, toolchain
+/// and settings that produced it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Provenance {
+    pub ghostbind_version: String,
+    /// When the build ran, or `SOURCE_DATE_EPOCH` for reproducible builds
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    /// Whether the crate directory had uncommitted changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_dirty: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rustc_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cargo_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cbindgen_version: Option<String>,
+    /// The crate's features as resolved by cargo
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub no_default_features: bool,
+    pub profile: String,
+    /// `[profile.<name>]` from the workspace Cargo.toml, with `CARGO_PROFILE_*` overrides
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile_settings: BTreeMap<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rustflags: Option<String>,
+    pub cargo_command: String,
+}
+
+impl Provenance {
+    /// `source_date_epoch` fixes the timestamp; otherwise the current time is recorded.
+    pub fn collect(
+        crate_info: &CrateInfo,
+        cargo_builder: &CargoBuilder,
+        profile: &BuildProfile,
+        no_default_features: bool,
+        source_date_epoch: Option<u64>,
+    ) -> Result<Self> {
+        let profile_name = match profile {
+            BuildProfile::Debug => "dev",
+            BuildProfile::Release => "release",
+        };
+
+        Ok(Self {
+            ghostbind_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: creation_time(source_date_epoch),
+            git_commit: command_output(Command::new("git").args(["rev-parse", "HEAD"]).current_dir(&crate_info.manifest_dir)),
+            git_dirty: git_dirty(&crate_info.manifest_dir),
+            rustc_version: command_output(Command::new("rustc").arg("--version")),
+            cargo_version: command_output(Command::new("cargo").arg("--version")),
+            cbindgen_version: cbindgen_version(),
+            features: crate_info.enabled_features.clone(),
+            no_default_features,
+            profile: profile_name.to_string(),
+            profile_settings: profile_settings(&crate_info.workspace_root, profile_name, std::env::vars())?,
+            rustflags: cargo_builder.rustflags()?,
+            cargo_command: cargo_builder.command_line()?,
+        })
+    }
+}
+
+fn git_dirty(crate_dir: &Path) -> Option<bool> {
+    let output = Command::new("git")
+        .args(["status", "--porcelain", "--", "."])
+        .current_dir(crate_dir)
+        .output()
+        .ok()?;
+    output.status.success().then_some(!output.stdout.is_empty())
+}
+
+/// Trimmed stdout of a command that succeeded.
+fn command_output(cmd: &mut Command) -> Option<String> {
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn profile_settings(
+    workspace_root: &Path,
+    profile_name: &str,
+    env: impl Iterator<Item = (String, String)>,
+) -> Result<BTreeMap<String, Value>> {
+    let mut settings = BTreeMap::new();
+
+    let manifest_path = workspace_root.join("Cargo.toml");
+    if manifest_path.exists() {
+        let content = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+        let manifest: toml::Table = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+        if let Some(toml::Value::Table(profile)) = manifest.get("profile").and_then(|p| p.get(profile_name)) {
+            for (key, value) in profile {
+                settings.insert(key.clone(), serde_json::to_value(value)?);
+            }
+        }
+    }
+
+    let prefix = format!("CARGO_PROFILE_{}_", profile_name.to_uppercase());
+    for (key, value) in env {
+        if let Some(setting) = key.strip_prefix(&prefix) {
+            settings.insert(setting.to_lowercase().replace('_', "-"), Value::String(value));
+        }
+    }
+
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_settings() {
+        let dir = std::env::temp_dir().join(format!("ghostbind-provenance-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"x\"\n\n[profile.release]\nlto = true\nopt-level = 3\n").unwrap();
+
+        let env = vec![
+            ("CARGO_PROFILE_RELEASE_CODEGEN_UNITS".to_string(), "1".to_string()),
+            ("CARGO_PROFILE_DEV_DEBUG".to_string(), "0".to_string()),
+        ];
+        let settings = profile_settings(&dir, "release", env.into_iter()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(settings.get("lto"), Some(&Value::Bool(true)));
+        assert_eq!(settings.get("opt-level"), Some(&Value::from(3)));
+        assert_eq!(settings.get("codegen-units"), Some(&Value::from("1")));
+        assert_eq!(settings.len(), 3);
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::artifact_discovery::ArtifactDiscovery;
+use crate::cargo_integration::{BuildProfile, CargoBuilder, CrateInfo};
+use crate::header_generation::HeaderGenerator;
+
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const AR_HEADER_LEN: usize = 60;
+const AR_MTIME: Range<usize> = 16..28;
+const AR_UID: Range<usize> = 28..34;
+const AR_GID: Range<usize> = 34..40;
+const AR_MODE: Range<usize> = 40..48;
+const AR_SIZE: Range<usize> = 48..58;
+
+const CBINDGEN_VERSION_MARKER: &str = "Generated with cbindgen:";
+
+/// Zeroes the timestamp, owner and group of every member of a `.a` or `.lib` archive and
+/// gives them all mode 644. Anything that isn't an ar archive is left untouched.
+pub fn normalize_archive(path: &Path) -> Result<()> {
+    let mut contents = fs::read(path)
+        .with_context(|| format!("Failed to read archive {}", path.display()))?;
+    if !contents.starts_with(AR_MAGIC) {
+        return Ok(());
+    }
+
+    normalize_archive_bytes(&mut contents)
+        .with_context(|| format!("Failed to normalize archive {}", path.display()))?;
+    fs::write(path, contents)
+        .with_context(|| format!("Failed to write archive {}", path.display()))
+}
+
+fn normalize_archive_bytes(contents: &mut [u8]) -> Result<()> {
+    let mut pos = AR_MAGIC.len();
+    while pos + AR_HEADER_LEN <= contents.len() {
+        let header = &mut contents[pos..pos + AR_HEADER_LEN];
+        if &header[58..60] != b"`\n" {
+            return Err(anyhow!("Malformed archive member header at offset {}", pos));
+        }
+
+        let size: usize = std::str::from_utf8(&header[AR_SIZE])
+            .ok()
+            .and_then(|size| size.trim().parse().ok())
+            .ok_or_else(|| anyhow!("Invalid archive member size at offset {}", pos))?;
+
+        set_field(header, AR_MTIME, "0");
+        set_field(header, AR_UID, "0");
+        set_field(header, AR_GID, "0");
+        set_field(header, AR_MODE, "644");
+
+        // Members are padded to an even offset
+        pos += AR_HEADER_LEN + size + size % 2;
+    }
+    Ok(())
+}
+
+fn set_field(header: &mut [u8], range: Range<usize>, value: &str) {
+    let field = &mut header[range];
+    field.fill(b' ');
+    field[..value.len()].copy_from_slice(value.as_bytes());
+}
+
+/// Rewrites cbindgen's `Generated with cbindgen:<version>` line without the version.
+pub fn stabilize_header_version(path: &Path) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read header {}", path.display()))?;
+    let stable = without_cbindgen_version(&content);
+    if stable != content {
+        fs::write(path, stable)
+            .with_context(|| format!("Failed to write header {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn without_cbindgen_version(content: &str) -> String {
+    let Some(start) = content.find(CBINDGEN_VERSION_MARKER) else {
+        return content.to_string();
+    };
+
+    let version_start = start + CBINDGEN_VERSION_MARKER.len();
+    let version_len = content[version_start..]
+        .find(|c: char| c.is_whitespace() || c == '*')
+        .unwrap_or(content.len() - version_start);
+    format!("{}Generated with cbindgen{}", &content[..start], &content[version_start + version_len..])
+}
+
+/// Builds a crate twice in reproducible mode, in fresh and separate target directories,
+/// and compares the hashes of what each build produced.
+pub struct ReproVerifier {
+    manifest_path: PathBuf,
+    rust_target: String,
+    cross_compile: bool,
+    profile: BuildProfile,
+    features: Vec<String>,
+    no_default_features: bool,
+    cbindgen_config: Option<PathBuf>,
+    work_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReproReport {
+    pub files: Vec<ReproFile>,
+}
+
+/// A file of both builds and its SHA-256 in each.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReproFile {
+    pub name: String,
+    pub first: String,
+    pub second: String,
+}
+
+impl ReproFile {
+    pub fn matches(&self) -> bool {
+        self.first == self.second
+    }
+}
+
+impl ReproReport {
+    pub fn is_reproducible(&self) -> bool {
+        self.files.iter().all(ReproFile::matches)
+    }
+}
+
+impl ReproVerifier {
+    pub fn new(manifest_path: impl AsRef<Path>, rust_target: impl Into<String>) -> Self {
+        Self {
+            manifest_path: manifest_path.as_ref().to_path_buf(),
+            rust_target: rust_target.into(),
+            cross_compile: false,
+            profile: BuildProfile::Release,
+            features: Vec::new(),
+            no_default_features: false,
+            cbindgen_config: None,
+            work_dir: PathBuf::from(".ghostbind/repro"),
+        }
+    }
+
+    pub fn cross_compile(mut self, cross_compile: bool) -> Self {
+        self.cross_compile = cross_compile;
+        self
+    }
+
+    pub fn profile(mut self, profile: BuildProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn features(mut self, features: Vec<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn no_default_features(mut self, no_default: bool) -> Self {
+        self.no_default_features = no_default;
+        self
+    }
+
+    pub fn cbindgen_config(mut self, cbindgen_config: Option<PathBuf>) -> Self {
+        self.cbindgen_config = cbindgen_config;
+        self
+    }
+
+    pub fn verify(&self, crate_info: &CrateInfo) -> Result<ReproReport> {
+        let first = self.build_and_hash(crate_info, "first")?;
+        let second = self.build_and_hash(crate_info, "second")?;
+
+        let mut report = ReproReport::default();
+        for (name, first_hash) in first {
+            let second_hash = second.iter()
+                .find(|(other, _)| *other == name)
+                .map(|(_, hash)| hash.clone())
+                .unwrap_or_else(|| "(missing)".to_string());
+            report.files.push(ReproFile { name, first: first_hash, second: second_hash });
+        }
+        Ok(report)
+    }
+
+    /// Builds into `<work_dir>/<target>/<run>`, returning `(file name, sha256)` for each
+    /// artifact and header.
+    fn build_and_hash(&self, crate_info: &CrateInfo, run: &str) -> Result<Vec<(String, String)>> {
+        let target_dir = self.work_dir.join(&self.rust_target).join(run);
+        if target_dir.exists() {
+            fs::remove_dir_all(&target_dir)
+                .with_context(|| format!("Failed to clean {}", target_dir.display()))?;
+        }
+
+        let mut cargo_builder = CargoBuilder::new(&self.manifest_path)
+            .profile(self.profile.clone())
+            .features(self.features.clone())
+            .no_default_features(self.no_default_features)
+            .target_dir(Some(target_dir.clone()))
+            .reproducible(true);
+        if self.cross_compile {
+            cargo_builder = cargo_builder.target(self.rust_target.clone());
+        }
+        cargo_builder.build()
+            .with_context(|| format!("Reproducibility build '{}' failed", run))?;
+
+        let discovery = ArtifactDiscovery::new(&target_dir, Some(self.rust_target.clone()), self.profile.clone())
+            .cross_compile(self.cross_compile);
+        let artifacts = discovery.discover_artifacts(crate_info)?;
+        if artifacts.is_empty() {
+            return Err(anyhow!("No library artifacts found in {}", target_dir.display()));
+        }
+
+        let mut hashes = Vec::new();
+        for artifact in &artifacts {
+            let mut contents = fs::read(&artifact.original_path)
+                .with_context(|| format!("Failed to read {}", artifact.original_path.display()))?;
+            if contents.starts_with(AR_MAGIC) {
+                normalize_archive_bytes(&mut contents)
+                    .with_context(|| format!("Failed to normalize archive {}", artifact.original_path.display()))?;
+            }
+            hashes.push((file_name(&artifact.original_path), hex::encode(Sha256::digest(&contents))));
+        }
+
+        let headers = HeaderGenerator::new(self.cbindgen_config.clone())
+            .features(self.features.clone())
+            .no_default_features(self.no_default_features)
+            .profile(self.profile.clone())
+            .reproducible(true)
+            .generate_headers(crate_info, Some(&self.rust_target))?;
+        for header in &headers {
+            let contents = fs::read(&header.header_path)
+                .with_context(|| format!("Failed to read {}", header.header_path.display()))?;
+            hashes.push((file_name(&header.header_path), hex::encode(Sha256::digest(&contents))));
+        }
+
+        Ok(hashes)
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member_header(name: &str, mtime: &str, size: usize) -> Vec<u8> {
+        format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", name, mtime, "1000", "1000", "100755", size).into_bytes()
+    }
+
+    #[test]
+    fn test_normalize_archive_bytes() {
+        let mut archive = AR_MAGIC.to_vec();
+        archive.extend(member_header("a.o/", "1700000000", 3));
+        archive.extend(b"abc\n");
+        archive.extend(member_header("b.o/", "1700000001", 2));
+        archive.extend(b"de");
+
+        normalize_archive_bytes(&mut archive).unwrap();
+
+        let expected_header = format!("{:<12}{:<6}{:<6}{:<8}", "0", "0", "0", "644");
+        let first = &archive[8..8 + AR_HEADER_LEN];
+        let second = &archive[8 + AR_HEADER_LEN + 4..8 + 2 * AR_HEADER_LEN + 4];
+        assert_eq!(&first[16..48], expected_header.as_bytes());
+        assert_eq!(&second[16..48], expected_header.as_bytes());
+        assert_eq!(&second[..4], b"b.o/");
+        assert!(archive.ends_with(b"de"));
+    }
+
+    #[test]
+    fn test_without_cbindgen_version() {
+        assert_eq!(
+            without_cbindgen_version("/* Generated with cbindgen:0.29.0 */\n\n#include <stdint.h>\n"),
+            "/* Generated with cbindgen */\n\n#include <stdint.h>\n"
+        );
+        assert_eq!(without_cbindgen_version("#pragma once\n"), "#pragma once\n");
+    }
+}
@@ -0,0 +1,363 @@
+use anyhow::{Context, Result};
+use cargo_metadata::{Metadata, Package};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cargo_integration::LinkedDependencies;
+use crate::manifest::BuildManifest;
+
+/// The SBOM document formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SbomFormat {
+    /// CycloneDX 1.5 JSON
+    CycloneDx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+impl SbomFormat {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SbomFormat::CycloneDx => "cyclonedx",
+            SbomFormat::Spdx => "spdx",
+        }
+    }
+
+    fn extension(&self) -> &str {
+        match self {
+            SbomFormat::CycloneDx => "cdx.json",
+            SbomFormat::Spdx => "spdx.json",
+        }
+    }
+}
+
+/// Writes a software bill of materials for a built artifact: the crate and every
+/// dependency linked into it, with package URLs, checksums and the dependency graph.
+pub struct SbomGenerator {
+    cache_dir: PathBuf,
+    format: SbomFormat,
+    profile: String,
+    features: Vec<String>,
+    source_date_epoch: Option<u64>,
+}
+
+/// A package as it appears in the SBOM.
+struct Component {
+    name: String,
+    version: String,
+    purl: String,
+    license: Option<String>,
+    sha256: Option<String>,
+}
+
+impl SbomGenerator {
+    pub fn new(format: SbomFormat) -> Self {
+        Self {
+            cache_dir: PathBuf::from(".ghostbind/cache"),
+            format,
+            profile: "release".to_string(),
+            features: Vec::new(),
+            source_date_epoch: None,
+        }
+    }
+
+    pub fn profile(mut self, profile: &str) -> Self {
+        self.profile = profile.to_string();
+        self
+    }
+
+    /// The crate features the artifact was built with.
+    pub fn features(mut self, features: Vec<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// The creation time to record, overriding `SOURCE_DATE_EPOCH` and the clock.
+    pub fn source_date_epoch(mut self, source_date_epoch: Option<u64>) -> Self {
+        self.source_date_epoch = source_date_epoch;
+        self
+    }
+
+    /// `metadata` should be resolved for the manifest's target and features, so the
+    /// SBOM lists exactly what was linked.
+    pub fn generate(&self, manifest: &BuildManifest, metadata: &Metadata) -> Result<PathBuf> {
+        let linked = LinkedDependencies::resolve(&manifest.crate_name, metadata)?;
+        let checksums = lockfile_checksums(&metadata.workspace_root.as_std_path().join("Cargo.lock"))?;
+
+        let artifact = fs::read(&manifest.artifact)
+            .with_context(|| format!("Failed to read artifact {}", manifest.artifact.display()))?;
+        let mut root = component(linked.root, &checksums);
+        root.sha256 = Some(hex::encode(Sha256::digest(&artifact)));
+
+        let components: Vec<Component> = linked.packages.iter().map(|p| component(p, &checksums)).collect();
+        let purls: HashMap<&cargo_metadata::PackageId, String> = std::iter::once(linked.root)
+            .chain(linked.packages.iter().copied())
+            .map(|p| (&p.id, purl(p)))
+            .collect();
+        let edges: Vec<(String, Vec<String>)> = linked.edges.iter()
+            .map(|(id, deps)| (purls[id].clone(), deps.iter().map(|d| purls[d].clone()).collect()))
+            .collect();
+
+        let document = match self.format {
+            SbomFormat::CycloneDx => self.cyclonedx(manifest, &root, &components, &edges),
+            SbomFormat::Spdx => self.spdx(manifest, &root, &components, &edges),
+        };
+
+        let artifact_name = manifest.artifact.file_name().unwrap_or_default().to_string_lossy();
+        let sbom_dir = self.cache_dir.join(&manifest.rustc_target).join("sbom");
+        fs::create_dir_all(&sbom_dir)
+            .with_context(|| format!("Failed to create SBOM directory: {}", sbom_dir.display()))?;
+        let path = sbom_dir.join(format!("{}.{}", artifact_name, self.format.extension()));
+        let json = serde_json::to_string_pretty(&document).context("Failed to serialize SBOM")?;
+        fs::write(&path, json)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+
+    fn cyclonedx(&self, manifest: &BuildManifest, root: &Component, components: &[Component], edges: &[(String, Vec<String>)]) -> Value {
+        let cdx_component = |c: &Component, kind: &str| {
+            let mut value = json!({
+                "type": kind,
+                "bom-ref": c.purl,
+                "name": c.name,
+                "version": c.version,
+                "purl": c.purl,
+            });
+            if let Some(license) = &c.license {
+                value["licenses"] = json!([{ "expression": license }]);
+            }
+            if let Some(sha256) = &c.sha256 {
+                value["hashes"] = json!([{ "alg": "SHA-256", "content": sha256 }]);
+            }
+            value
+        };
+
+        let mut main = cdx_component(root, "library");
+        main["properties"] = json!(self.properties(manifest).into_iter()
+            .map(|(name, value)| json!({ "name": format!("ghostbind:{}", name), "value": value }))
+            .collect::<Vec<_>>());
+
+        let mut document = json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "version": 1,
+            "metadata": {
+                "tools": {
+                    "components": [{
+                        "type": "application",
+                        "name": "ghostbind",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }],
+                },
+                "component": main,
+            },
+            "components": components.iter().map(|c| cdx_component(c, "library")).collect::<Vec<_>>(),
+            "dependencies": edges.iter()
+                .map(|(id, deps)| json!({ "ref": id, "dependsOn": deps }))
+                .collect::<Vec<_>>(),
+        });
+        // Derived from the content, so the same build gets the same serial number
+        document["serialNumber"] = json!(format!("urn:uuid:{}", content_uuid(&document)));
+        document
+    }
+
+    fn spdx(&self, manifest: &BuildManifest, root: &Component, components: &[Component], edges: &[(String, Vec<String>)]) -> Value {
+        let ids: HashMap<&str, String> = std::iter::once(root)
+            .chain(components)
+            .enumerate()
+            .map(|(i, c)| (c.purl.as_str(), format!("SPDXRef-Package-{}-{}", spdx_id_part(&c.name), i)))
+            .collect();
+
+        let spdx_package = |c: &Component| {
+            let mut value = json!({
+                "name": c.name,
+                "SPDXID": ids[c.purl.as_str()],
+                "versionInfo": c.version,
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": c.license.as_deref().unwrap_or("NOASSERTION"),
+                "copyrightText": "NOASSERTION",
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": c.purl,
+                }],
+            });
+            if let Some(sha256) = &c.sha256 {
+                value["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": sha256 }]);
+            }
+            value
+        };
+
+        let mut main = spdx_package(root);
+        main["primaryPackagePurpose"] = json!("LIBRARY");
+        main["comment"] = json!(self.properties(manifest).into_iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join("; "));
+
+        let mut relationships = vec![json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": ids[root.purl.as_str()],
+        })];
+        for (id, deps) in edges {
+            for dep in deps {
+                relationships.push(json!({
+                    "spdxElementId": ids[id.as_str()],
+                    "relationshipType": "DEPENDS_ON",
+                    "relatedSpdxElement": ids[dep.as_str()],
+                }));
+            }
+        }
+
+        let mut packages = vec![main];
+        packages.extend(components.iter().map(spdx_package));
+
+        let mut document = json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": manifest.artifact.file_name().unwrap_or_default().to_string_lossy(),
+            "creationInfo": {
+                "created": creation_time(self.source_date_epoch),
+                "creators": [format!("Tool: ghostbind-{}", env!("CARGO_PKG_VERSION"))],
+            },
+            "packages": packages,
+            "relationships": relationships,
+        });
+        document["documentNamespace"] = json!(format!(
+            "https://spdx.org/spdxdocs/{}-{}-{}",
+            root.name,
+            root.version,
+            content_uuid(&document)
+        ));
+        document
+    }
+
+    fn properties(&self, manifest: &BuildManifest) -> Vec<(&'static str, String)> {
+        vec![
+            ("artifact_kind", manifest.kind.clone()),
+            ("rustc_target", manifest.rustc_target.clone()),
+            ("profile", self.profile.clone()),
+            ("features", self.features.join(",")),
+        ]
+    }
+}
+
+fn component(package: &Package, checksums: &HashMap<(String, String), String>) -> Component {
+    Component {
+        name: package.name.clone(),
+        version: package.version.to_string(),
+        purl: purl(package),
+        license: package.license.clone(),
+        sha256: checksums.get(&(package.name.clone(), package.version.to_string())).cloned(),
+    }
+}
+
+/// The package URL of a crate, e.g. `pkg:cargo/serde@1.0.200`.
+fn purl(package: &Package) -> String {
+    format!("pkg:cargo/{}@{}", package.name, package.version)
+}
+
+/// SPDX identifiers may only contain letters, digits, `.` and `-`.
+fn spdx_id_part(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '-' }).collect()
+}
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    checksum: Option<String>,
+}
+
+/// The SHA-256 of each registry package's `.crate` file, as recorded in Cargo.lock.
+fn lockfile_checksums(lockfile: &Path) -> Result<HashMap<(String, String), String>> {
+    if !lockfile.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(lockfile)
+        .with_context(|| format!("Failed to read {}", lockfile.display()))?;
+    let lockfile: Lockfile = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", lockfile.display()))?;
+
+    Ok(lockfile.package.into_iter()
+        .filter_map(|p| p.checksum.map(|checksum| ((p.name, p.version), checksum)))
+        .collect())
+}
+
+/// A UUID (version 5 layout) derived from the document's SHA-256.
+fn content_uuid(document: &Value) -> String {
+    let digest = Sha256::digest(document.to_string().as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = hex::encode(bytes);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// The given epoch or `SOURCE_DATE_EPOCH` when set, for reproducible documents, otherwise
+/// the current time.
+pub(crate) fn creation_time(source_date_epoch: Option<u64>) -> String {
+    let seconds = source_date_epoch
+        .or_else(|| std::env::var("SOURCE_DATE_EPOCH").ok().and_then(|epoch| epoch.parse().ok()))
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default());
+    rfc3339(seconds)
+}
+
+fn rfc3339(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3_600, time % 3_600 / 60, time % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(1_700_000_000), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn test_content_uuid() {
+        let uuid = content_uuid(&json!({ "name": "my_crate" }));
+
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "5");
+        assert_eq!(uuid, content_uuid(&json!({ "name": "my_crate" })));
+        assert_ne!(uuid, content_uuid(&json!({ "name": "other" })));
+    }
+}
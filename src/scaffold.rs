@@ -0,0 +1,479 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::CONFIG_FILE;
+use crate::error::Error;
+use crate::header_generation::HeaderGenerator;
+use crate::zig_module::{fingerprint, zig_package_name};
+
+/// Creates a new FFI crate (`--lib`), a Zig project that builds and links one through
+/// ghostbind (`--zig`), or both: the Zig project in the directory and the crate in
+/// `rust/` inside it.
+pub struct Scaffolder {
+    dir: PathBuf,
+    name: Option<String>,
+    lib: bool,
+    zig: bool,
+    crate_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScaffoldReport {
+    pub name: String,
+    /// The new crate, with `--lib`
+    pub crate_dir: Option<PathBuf>,
+    /// The new Zig project, with `--zig`
+    pub zig_dir: Option<PathBuf>,
+    pub files: Vec<PathBuf>,
+}
+
+impl Scaffolder {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            name: None,
+            lib: false,
+            zig: false,
+            crate_dir: None,
+        }
+    }
+
+    /// The crate and project name; defaults to the directory's name.
+    pub fn name(mut self, name: Option<String>) -> Self {
+        self.name = name;
+        self
+    }
+
+    pub fn lib(mut self, lib: bool) -> Self {
+        self.lib = lib;
+        self
+    }
+
+    pub fn zig(mut self, zig: bool) -> Self {
+        self.zig = zig;
+        self
+    }
+
+    /// Where the Zig project finds the crate, relative to the project. Defaults to `rust`.
+    pub fn crate_dir(mut self, crate_dir: Option<PathBuf>) -> Self {
+        self.crate_dir = crate_dir;
+        self
+    }
+
+    pub fn generate(&self) -> Result<ScaffoldReport> {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => std::path::absolute(&self.dir)?
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .ok_or_else(|| anyhow!("Cannot derive a name from {}; pass --name", self.dir.display()))?,
+        };
+        if !is_valid_name(&name) {
+            return Err(Error::InvalidConfig(format!(
+                "Invalid name '{}': use lowercase letters, digits, '_' and '-', starting with a letter",
+                name
+            )).into());
+        }
+
+        let relative_crate_dir = self.crate_dir.clone().unwrap_or_else(|| PathBuf::from("rust"));
+        let crate_dir = if self.zig { self.dir.join(&relative_crate_dir) } else { self.dir.clone() };
+
+        let mut files = Vec::new();
+        if self.lib {
+            files.extend(lib_files(&name).into_iter().map(|(path, content)| (crate_dir.join(path), content)));
+        }
+        if self.zig {
+            let crate_name = if self.lib { name.clone() } else { existing_crate_name(&crate_dir)?.unwrap_or(name.clone()) };
+            files.extend(zig_files(&name, &crate_name, &relative_crate_dir, &self.dir, self.lib));
+        }
+
+        // Check everything first, so a refusal leaves nothing half-written
+        let existing: Vec<String> = files.iter()
+            .filter(|(path, _)| path.exists())
+            .map(|(path, _)| path.display().to_string())
+            .collect();
+        if !existing.is_empty() {
+            return Err(Error::InvalidConfig(format!("Refusing to overwrite {}", existing.join(", "))).into());
+        }
+
+        let mut report = ScaffoldReport {
+            name,
+            crate_dir: self.lib.then(|| crate_dir.clone()),
+            zig_dir: self.zig.then(|| self.dir.clone()),
+            files: Vec::new(),
+        };
+        for (path, content) in files {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+            }
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            report.files.push(path);
+        }
+        if self.lib {
+            let config = HeaderGenerator::new(None).create_default_cbindgen_config(&crate_dir, &report.name, false)?;
+            report.files.push(config.path);
+        }
+
+        Ok(report)
+    }
+}
+
+/// Crate names that are also usable as C and Zig identifier prefixes once `-` becomes `_`.
+fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+/// The package name in an existing crate's Cargo.toml.
+fn existing_crate_name(crate_dir: &Path) -> Result<Option<String>> {
+    let manifest_path = crate_dir.join("Cargo.toml");
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest: toml::Table = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+    Ok(manifest.get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .map(str::to_string))
+}
+
+fn lib_files(name: &str) -> Vec<(PathBuf, String)> {
+    let ident = name.replace('-', "_");
+    let render = |template: &str| template.replace("{name}", name).replace("{ident}", &ident);
+
+    vec![
+        (PathBuf::from("Cargo.toml"), render(CARGO_TOML)),
+        (PathBuf::from("src/lib.rs"), render(LIB_RS)),
+        (PathBuf::from(CONFIG_FILE), GHOSTBIND_TOML.to_string()),
+        (PathBuf::from(".gitignore"), "/target\n/.ghostbind\n".to_string()),
+    ]
+}
+
+/// `example` calls the functions of the crate `--lib` generates from `main.zig`.
+fn zig_files(name: &str, crate_name: &str, crate_dir: &Path, dir: &Path, example: bool) -> Vec<(PathBuf, String)> {
+    let package = zig_package_name(name);
+    let ident = crate_name.replace('-', "_");
+    // The default cbindgen config prefixes `ErrorCode` with the crate name
+    let error_prefix = format!("{}_ERROR_CODE", ident.to_uppercase());
+    let cargo_toml = crate_dir.join("Cargo.toml").to_string_lossy().replace('\\', "/");
+    let fingerprint = fingerprint(&package, &std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf()));
+
+    let render = |template: &str| {
+        template
+            .replace("{name}", name)
+            .replace("{package}", &package)
+            .replace("{crate}", crate_name)
+            .replace("{ident}", &ident)
+            .replace("{ERROR}", &error_prefix)
+            .replace("{cargo_toml}", &cargo_toml)
+            .replace("{fingerprint}", &format!("0x{:016x}", fingerprint))
+    };
+
+    vec![
+        (dir.join("build.zig"), render(BUILD_ZIG)),
+        (dir.join("build.zig.zon"), render(BUILD_ZIG_ZON)),
+        (dir.join("src/main.zig"), render(if example { MAIN_ZIG } else { MAIN_ZIG_EXISTING })),
+        (dir.join(".gitignore"), ".zig-cache/\nzig-out/\n.ghostbind/\n".to_string()),
+    ]
+}
+
+const CARGO_TOML: &str = r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2024"
+
+[lib]
+# staticlib for linking into Zig executables, cdylib for shared libraries
+crate-type = ["staticlib", "cdylib"]
+
+[dependencies]
+"#;
+
+const LIB_RS: &str = r#"//! The C ABI of {name}.
+//!
+//! Every function returns an `ErrorCode` and writes its result through an `out`
+//! pointer, and no memory crosses the boundary: see ghostbind's docs/FFI_SAFETY.md.
+
+/// The outcome of every call; `Ok` is success. C sees it as `<Crate>ErrorCode`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Ok = 0,
+    NullPointer = 1,
+    Overflow = 2,
+    InvalidInput = 3,
+}
+
+/// A point in 2D space
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Adds two numbers, failing instead of wrapping on overflow.
+/// ghostbind: errors = ErrorCode
+#[unsafe(no_mangle)]
+pub extern "C" fn {ident}_add(a: i32, b: i32, out: *mut i32) -> ErrorCode {
+    if out.is_null() {
+        return ErrorCode::NullPointer;
+    }
+
+    match a.checked_add(b) {
+        Some(sum) => {
+            // SAFETY: checked for null above; the caller provides a valid i32
+            unsafe { *out = sum };
+            ErrorCode::Ok
+        }
+        None => ErrorCode::Overflow,
+    }
+}
+
+/// The point halfway between `a` and `b`.
+#[unsafe(no_mangle)]
+pub extern "C" fn {ident}_midpoint(a: Point, b: Point) -> Point {
+    Point {
+        x: (a.x + b.x) / 2.0,
+        y: (a.y + b.y) / 2.0,
+    }
+}
+
+/// The mean of `values_len` numbers starting at `values`.
+/// ghostbind: errors = ErrorCode
+#[unsafe(no_mangle)]
+pub extern "C" fn {ident}_mean(values: *const f64, values_len: usize, out: *mut f64) -> ErrorCode {
+    if values.is_null() || out.is_null() {
+        return ErrorCode::NullPointer;
+    }
+    if values_len == 0 {
+        return ErrorCode::InvalidInput;
+    }
+
+    // SAFETY: checked for null above; the caller provides `values_len` readable values
+    let values = unsafe { std::slice::from_raw_parts(values, values_len) };
+    // SAFETY: checked for null above
+    unsafe { *out = values.iter().sum::<f64>() / values_len as f64 };
+    ErrorCode::Ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        let mut sum = 0;
+        assert_eq!({ident}_add(2, 3, &mut sum), ErrorCode::Ok);
+        assert_eq!(sum, 5);
+        assert_eq!({ident}_add(i32::MAX, 1, &mut sum), ErrorCode::Overflow);
+        assert_eq!({ident}_add(1, 1, std::ptr::null_mut()), ErrorCode::NullPointer);
+    }
+
+    #[test]
+    fn test_mean() {
+        let values = [1.0, 2.0, 6.0];
+        let mut mean = 0.0;
+        assert_eq!({ident}_mean(values.as_ptr(), values.len(), &mut mean), ErrorCode::Ok);
+        assert_eq!(mean, 3.0);
+        assert_eq!({ident}_mean(values.as_ptr(), 0, &mut mean), ErrorCode::InvalidInput);
+    }
+}
+"#;
+
+const GHOSTBIND_TOML: &str = r##"# ghostbind settings for this crate
+
+[licenses]
+# SPDX license identifiers no bundled dependency may be licensed under only,
+# checked by `ghostbind build --licenses` and `ghostbind licenses`
+deny = []
+"##;
+
+const BUILD_ZIG: &str = r#"const std = @import("std");
+
+pub fn build(b: *std.Build) void {
+    const target = b.standardTargetOptions(.{});
+    const optimize = b.standardOptimizeOption(.{});
+
+    const exe = b.addExecutable(.{
+        .name = "{name}",
+        .root_module = b.createModule(.{
+            .root_source_file = b.path("src/main.zig"),
+            .target = target,
+            .optimize = optimize,
+        }),
+    });
+    linkRustCrate(b, exe, "{cargo_toml}");
+    b.installArtifact(exe);
+
+    const run = b.addRunArtifact(exe);
+    if (b.args) |args| run.addArgs(args);
+    const run_step = b.step("run", "Run {name}");
+    run_step.dependOn(&run.step);
+}
+
+/// The fields of a ghostbind manifest needed to link the crate.
+const Manifest = struct {
+    kind: []const u8,
+    artifact: []const u8,
+    headers: []const []const u8,
+    link_libs: []const []const u8 = &.{},
+    link_search: []const []const u8 = &.{},
+    frameworks: []const []const u8 = &.{},
+};
+
+/// Builds the crate with `ghostbind build` for the executable's target while build.zig
+/// runs, then links the artifact, headers and system libraries its manifest lists.
+fn linkRustCrate(b: *std.Build, exe: *std.Build.Step.Compile, cargo_toml: []const u8) void {
+    const output = b.run(&.{
+        "ghostbind",       "--message-format", "json",
+        "build",           "--manifest-path",  cargo_toml,
+        "--zig-target",    ghostbindTarget(b, exe.rootModuleTarget()),
+    });
+
+    // One JSON event per line; `manifest-written` says where the manifest is
+    var manifest_path: ?[]const u8 = null;
+    var lines = std.mem.tokenizeScalar(u8, output, '\n');
+    while (lines.next()) |line| {
+        const Event = struct { event: []const u8, path: ?[]const u8 = null };
+        const event = std.json.parseFromSliceLeaky(Event, b.allocator, line, .{ .ignore_unknown_fields = true }) catch continue;
+        if (std.mem.eql(u8, event.event, "manifest-written") and manifest_path == null) {
+            manifest_path = event.path;
+        }
+    }
+
+    const path = manifest_path orelse @panic("ghostbind did not write a manifest");
+    const contents = std.fs.cwd().readFileAlloc(b.allocator, path, 1024 * 1024) catch @panic("cannot read the ghostbind manifest");
+    const manifest = std.json.parseFromSliceLeaky(Manifest, b.allocator, contents, .{ .ignore_unknown_fields = true }) catch @panic("invalid ghostbind manifest");
+
+    const module = exe.root_module;
+    module.addObjectFile(.{ .cwd_relative = manifest.artifact });
+    if (std.mem.eql(u8, manifest.kind, "cdylib")) {
+        module.addRPath(.{ .cwd_relative = std.fs.path.dirname(manifest.artifact).? });
+    }
+    for (manifest.headers) |header| {
+        module.addIncludePath(.{ .cwd_relative = std.fs.path.dirname(header).? });
+    }
+    for (manifest.link_search) |dir| {
+        module.addLibraryPath(.{ .cwd_relative = dir });
+    }
+    for (manifest.link_libs) |lib| {
+        // libc is linked through link_libc rather than as a system library
+        if (!std.mem.eql(u8, lib, "c")) module.linkSystemLibrary(lib, .{});
+    }
+    for (manifest.frameworks) |framework| {
+        module.linkFramework(framework, .{});
+    }
+    module.link_libc = true;
+}
+
+/// The target in the form `ghostbind build --zig-target` maps to a Rust triple.
+fn ghostbindTarget(b: *std.Build, target: std.Target) []const u8 {
+    const arch = if (target.cpu.arch == .x86) "i386" else @tagName(target.cpu.arch);
+    return switch (target.os.tag) {
+        .linux => b.fmt("{s}-linux-{s}", .{ arch, @tagName(target.abi) }),
+        .windows => if (target.cpu.arch == .aarch64)
+            "aarch64-windows"
+        else
+            b.fmt("{s}-windows-{s}", .{ arch, @tagName(target.abi) }),
+        else => b.fmt("{s}-{s}", .{ arch, @tagName(target.os.tag) }),
+    };
+}
+"#;
+
+const BUILD_ZIG_ZON: &str = r#".{
+    .name = .{package},
+    .version = "0.1.0",
+    .fingerprint = {fingerprint},
+    .minimum_zig_version = "0.14.0",
+    .dependencies = .{},
+    .paths = .{
+        "build.zig",
+        "build.zig.zon",
+        "src",
+    },
+}
+"#;
+
+const MAIN_ZIG: &str = r#"const std = @import("std");
+const c = @cImport(@cInclude("{crate}.h"));
+
+pub fn main() !void {
+    var sum: i32 = 0;
+    if (c.{ident}_add(2, 3, &sum) != c.{ERROR}_OK) return error.AddFailed;
+
+    const mid = c.{ident}_midpoint(.{ .x = 0, .y = 0 }, .{ .x = 2, .y = 4 });
+    std.debug.print("2 + 3 = {d}, midpoint = ({d}, {d})\n", .{ sum, mid.x, mid.y });
+}
+"#;
+
+const MAIN_ZIG_EXISTING: &str = r#"const std = @import("std");
+const c = @cImport(@cInclude("{crate}.h"));
+
+pub fn main() !void {
+    // Call the functions {crate}.h declares through `c`
+    std.debug.print("linked {crate}\n", .{});
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header_parser::parse_header;
+
+    #[test]
+    fn test_names() {
+        assert!(is_valid_name("my-crate_2"));
+        assert!(!is_valid_name("2crate"));
+        assert!(!is_valid_name("my crate"));
+        assert!(!is_valid_name("MyCrate"));
+
+        let files = lib_files("my-crate");
+        let lib_rs = &files.iter().find(|(path, _)| path.ends_with("src/lib.rs")).unwrap().1;
+        assert!(lib_rs.contains("ghostbind: errors = ErrorCode"));
+        assert!(lib_rs.contains("pub extern \"C\" fn my_crate_add("));
+        assert!(!lib_rs.contains("{ident}"));
+    }
+
+    #[test]
+    fn test_example_matches_prefixed_header() {
+        // What cbindgen writes for the `--lib` crate with the default config's prefix
+        let header = parse_header(r#"
+typedef enum MyCrateErrorCode {
+  MY_CRATE_ERROR_CODE_OK = 0,
+  MY_CRATE_ERROR_CODE_NULL_POINTER = 1,
+  MY_CRATE_ERROR_CODE_OVERFLOW = 2,
+  MY_CRATE_ERROR_CODE_INVALID_INPUT = 3,
+} MyCrateErrorCode;
+
+typedef struct MyCratePoint {
+  double x;
+  double y;
+} MyCratePoint;
+
+MyCrateErrorCode my_crate_add(int32_t a, int32_t b, int32_t *out);
+MyCratePoint my_crate_midpoint(MyCratePoint a, MyCratePoint b);
+MyCrateErrorCode my_crate_mean(const double *values, uintptr_t values_len, double *out);
+"#);
+
+        let files = zig_files("app", "my-crate", Path::new("my-crate"), Path::new("app"), true);
+        let main_zig = &files.iter().find(|(path, _)| path.ends_with("src/main.zig")).unwrap().1;
+        let used: Vec<&str> = main_zig.split("c.").skip(1)
+            .map(|rest| rest.split(|c: char| !c.is_ascii_alphanumeric() && c != '_').next().unwrap())
+            .collect();
+
+        assert_eq!(used, vec!["my_crate_add", "MY_CRATE_ERROR_CODE_OK", "my_crate_midpoint"]);
+        for name in used {
+            let declared = header.functions.iter().any(|function| function.name == name)
+                || header.types.iter().any(|ty| ty.variants.iter().any(|(variant, _)| variant == name));
+            assert!(declared, "{} is not declared in the header", name);
+        }
+    }
+}
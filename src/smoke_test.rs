@@ -0,0 +1,180 @@
+use anyhow::{anyhow, Context, Result};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::error::Error;
+use crate::header_parser::{parse_header_file, CFunction};
+use crate::manifest::BuildManifest;
+use crate::target_mapping::get_host_target;
+
+/// Builds and runs a minimal C consumer of a built artifact, linked the way the manifest
+/// tells Zig to link it, so missing symbols or system libraries show up before a Zig build.
+pub struct SmokeTester {
+    cache_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SmokeReport {
+    /// Functions whose address was taken
+    pub linked: Vec<String>,
+    /// Zero-argument functions that were called
+    pub called: Vec<String>,
+    pub program: PathBuf,
+}
+
+impl SmokeTester {
+    pub fn new() -> Self {
+        Self {
+            cache_dir: PathBuf::from(".ghostbind/cache"),
+        }
+    }
+
+    pub fn run(&self, manifest: &BuildManifest) -> Result<SmokeReport> {
+        let host = get_host_target()?;
+        if manifest.rustc_target != host {
+            return Err(anyhow!(
+                "Smoke tests only run on the host ({}), but the manifest is for {}",
+                host,
+                manifest.rustc_target
+            ));
+        }
+
+        let c_headers = manifest.c_headers()?;
+        let mut functions = Vec::new();
+        for header in &c_headers {
+            // Functions behind an undecidable #if may not exist in this build
+            functions.extend(parse_header_file(header)?.functions.into_iter().filter(|f| !f.conditional));
+        }
+
+        let test_dir = self.cache_dir.join(&manifest.rustc_target).join("test");
+        fs::create_dir_all(&test_dir)
+            .with_context(|| format!("Failed to create test directory: {}", test_dir.display()))?;
+
+        let headers = c_headers.iter()
+            .map(|h| h.canonicalize().with_context(|| format!("Header file does not exist: {}", h.display())))
+            .collect::<Result<Vec<_>>>()?;
+        let source_path = test_dir.join(format!("{}_smoke.c", manifest.crate_name));
+        fs::write(&source_path, smoke_source(&manifest.crate_name, &headers, &functions))
+            .with_context(|| format!("Failed to write {}", source_path.display()))?;
+
+        let artifact = manifest.artifact.canonicalize()
+            .with_context(|| format!("Artifact does not exist: {}", manifest.artifact.display()))?;
+        let program = test_dir.join(format!("{}_smoke", manifest.crate_name));
+
+        let mut cc = match (which::which("cc"), which::which("zig")) {
+            (Ok(cc), _) => Command::new(cc),
+            (Err(_), Ok(zig)) => {
+                let mut cmd = Command::new(zig);
+                cmd.arg("cc");
+                cmd
+            }
+            (Err(_), Err(_)) => return Err(Error::ToolMissing("No C compiler found. Install a C compiler (cc) or zig to run smoke tests".to_string()).into()),
+        };
+        cc.arg("-std=c11").arg(&source_path).arg(&artifact);
+        for search in &manifest.link_search {
+            cc.arg(format!("-L{}", search.display()));
+        }
+        for lib in &manifest.link_libs {
+            cc.arg(format!("-l{}", lib));
+        }
+        cc.arg("-o").arg(&program);
+
+        let output = cc.output().context("Failed to run the C compiler")?;
+        if !output.status.success() {
+            return Err(Error::CheckFailed(format!(
+                "Failed to link the smoke test against {} (check link_libs/link_search):\n{}",
+                artifact.display(),
+                String::from_utf8_lossy(&output.stderr)
+            )).into());
+        }
+
+        // A cdylib is found next to itself at runtime
+        let artifact_dir = artifact.parent().map(PathBuf::from).unwrap_or_default();
+        let output = Command::new(&program)
+            .env("LD_LIBRARY_PATH", &artifact_dir)
+            .env("DYLD_LIBRARY_PATH", &artifact_dir)
+            .output()
+            .with_context(|| format!("Failed to run {}", program.display()))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            let last_call = stdout.lines().rev().find_map(|l| l.strip_prefix("calling "));
+            return Err(Error::CheckFailed(format!(
+                "Smoke test failed{}: {}\n{}",
+                last_call.map(|f| format!(" while calling `{}`", f)).unwrap_or_default(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            )).into());
+        }
+
+        Ok(SmokeReport {
+            linked: functions.iter().map(|f| f.name.clone()).collect(),
+            called: functions.iter().filter(|f| f.params.is_empty()).map(|f| f.name.clone()).collect(),
+            program,
+        })
+    }
+}
+
+impl Default for SmokeTester {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn smoke_source(crate_name: &str, headers: &[PathBuf], functions: &[CFunction]) -> String {
+    let mut source = String::new();
+    let _ = writeln!(source, "/* Generated by ghostbind to smoke-test linking {} */", crate_name);
+    source.push_str("#include <stdio.h>\n");
+    for header in headers {
+        let _ = writeln!(source, "#include \"{}\"", header.display());
+    }
+
+    source.push_str("\ntypedef void (*ghostbind_fn)(void);\n\n");
+    source.push_str("static ghostbind_fn volatile ghostbind_functions[] = {\n");
+    for function in functions {
+        let _ = writeln!(source, "    (ghostbind_fn)&{},", function.name);
+    }
+    // Keeps the array non-empty for crates that export nothing
+    source.push_str("    0,\n};\n\nint main(void) {\n");
+
+    let _ = writeln!(source, "    for (unsigned i = 0; i < {}; i++) {{", functions.len());
+    source.push_str("        if (!ghostbind_functions[i]) {\n");
+    source.push_str("            printf(\"missing function %u\\n\", i);\n");
+    source.push_str("            return 1;\n");
+    source.push_str("        }\n    }\n");
+
+    for function in functions.iter().filter(|f| f.params.is_empty()) {
+        let _ = writeln!(source, "\n    printf(\"calling {}\\n\");", function.name);
+        source.push_str("    fflush(stdout);\n");
+        if function.return_type == "void" {
+            let _ = writeln!(source, "    {}();", function.name);
+        } else {
+            let _ = writeln!(source, "    (void){}();", function.name);
+        }
+    }
+
+    source.push_str("\n    return 0;\n}\n");
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header_parser::parse_header;
+
+    #[test]
+    fn test_smoke_source() {
+        let header = parse_header("int32_t add(int32_t a, int32_t b);\nvoid reset(void);\nuint32_t version(void);\n");
+
+        let source = smoke_source("my_crate", &[PathBuf::from("/tmp/my_crate.h")], &header.functions);
+
+        assert!(source.contains("#include \"/tmp/my_crate.h\""));
+        assert!(source.contains("(ghostbind_fn)&add,"));
+        assert!(source.contains("i < 3;"));
+        assert!(source.contains("    reset();\n"));
+        assert!(source.contains("    (void)version();\n"));
+        assert!(!source.contains("add();"));
+    }
+}
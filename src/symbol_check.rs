@@ -0,0 +1,274 @@
+use anyhow::{anyhow, Context, Result};
+use object::read::archive::ArchiveFile;
+use object::{Architecture, BinaryFormat, Object, ObjectSymbol, SymbolKind};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::header_parser::parse_header_file;
+
+/// Compares the functions declared in generated headers with the symbols an artifact exports,
+/// so a header that promises a function the library doesn't provide is caught before linking.
+pub struct SymbolChecker;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolCheckMode {
+    Off,
+    Warn,
+    Error,
+}
+
+impl SymbolCheckMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SymbolCheckMode::Off => "off",
+            SymbolCheckMode::Warn => "warn",
+            SymbolCheckMode::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolReport {
+    pub artifact: PathBuf,
+    /// Declared in a header but not exported by the artifact
+    pub missing: Vec<String>,
+    /// Exported by the artifact but not declared in any header
+    pub undeclared: Vec<String>,
+}
+
+impl SymbolReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.undeclared.is_empty()
+    }
+}
+
+impl SymbolChecker {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// `lib_name` is the library target's name, which the crate's archive members are named after.
+    pub fn check(&self, lib_name: &str, artifact: &Path, headers: &[PathBuf]) -> Result<SymbolReport> {
+        let exported = read_exported_symbols(artifact, lib_name)?;
+
+        let mut declared = BTreeSet::new();
+        let mut required = BTreeSet::new();
+        for header in headers {
+            let parsed = parse_header_file(header)?;
+            for function in parsed.functions {
+                // Functions under an undecidable #if may legitimately be absent
+                if !function.conditional {
+                    required.insert(function.name.clone());
+                }
+                declared.insert(function.name);
+            }
+        }
+
+        Ok(SymbolReport {
+            artifact: artifact.to_path_buf(),
+            missing: required.difference(&exported).cloned().collect(),
+            undeclared: exported.difference(&declared).cloned().collect(),
+        })
+    }
+}
+
+impl Default for SymbolChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads the C-level function symbols a static or dynamic library exports.
+///
+/// For archives only the object files of the library target `lib_name` are inspected,
+/// since a Rust staticlib also bundles std and every dependency.
+pub fn read_exported_symbols(path: &Path, lib_name: &str) -> Result<BTreeSet<String>> {
+    let data = fs::read(path)
+        .with_context(|| format!("Failed to read artifact: {}", path.display()))?;
+
+    if let Ok(archive) = ArchiveFile::parse(&*data) {
+        let lib_name = lib_name.replace('-', "_");
+        let mut members = Vec::new();
+        for member in archive.members() {
+            let member = member.with_context(|| format!("Failed to read archive member in {}", path.display()))?;
+            let name = String::from_utf8_lossy(member.name()).to_string();
+            if name.ends_with(".o") || name.ends_with(".obj") {
+                members.push((name, member.data(&*data)?));
+            }
+        }
+
+        let own_members: Vec<_> = members.iter()
+            .filter(|(name, _)| is_crate_member(name, &lib_name))
+            .collect();
+        let selected = if own_members.is_empty() { members.iter().collect() } else { own_members };
+
+        let mut symbols = BTreeSet::new();
+        for (name, member_data) in selected {
+            let file = object::File::parse(*member_data)
+                .with_context(|| format!("Failed to parse archive member {} in {}", name, path.display()))?;
+            symbols.extend(defined_functions(&file));
+        }
+        return Ok(symbols);
+    }
+
+    let file = object::File::parse(&*data)
+        .map_err(|e| anyhow!("Failed to parse {} as an object file: {}", path.display(), e))?;
+    let exports = file.exports()
+        .with_context(|| format!("Failed to read exports of {}", path.display()))?;
+
+    let symbols = exports.iter()
+        .map(|export| String::from_utf8_lossy(export.name()).to_string())
+        .map(|name| c_name(&file, name))
+        .filter(|name| !is_rust_internal(name))
+        .collect();
+
+    Ok(symbols)
+}
+
+/// The library target an artifact was built from: `libfoo.a`, `libfoo.so`, `foo.lib` and
+/// `foo.dll` are all `foo`.
+pub fn library_name(artifact: &Path) -> String {
+    let file_name = artifact.file_name().unwrap_or_default().to_string_lossy();
+    let stem = file_name.split('.').next().unwrap_or_default();
+    match artifact.extension().and_then(|ext| ext.to_str()) {
+        Some("lib" | "dll") => stem.to_string(),
+        _ => stem.strip_prefix("lib").unwrap_or(stem).to_string(),
+    }
+}
+
+/// Rust names the library's own archive members `<lib>.<cgu>.rcgu.o` (or `<lib>-<hash>...`).
+fn is_crate_member(member: &str, lib_name: &str) -> bool {
+    member.strip_prefix(lib_name)
+        .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('-'))
+}
+
+fn defined_functions(file: &object::File) -> BTreeSet<String> {
+    file.symbols()
+        .filter(|symbol| symbol.is_global() && symbol.is_definition() && symbol.kind() == SymbolKind::Text)
+        .filter_map(|symbol| symbol.name().ok().map(str::to_string))
+        .map(|name| c_name(file, name))
+        .filter(|name| !is_rust_internal(name))
+        .collect()
+}
+
+/// Strips the leading underscore Mach-O and 32-bit Windows add to C symbol names.
+fn c_name(file: &object::File, name: String) -> String {
+    let prefixed = match file.format() {
+        BinaryFormat::MachO => true,
+        BinaryFormat::Coff | BinaryFormat::Pe => file.architecture() == Architecture::I386,
+        _ => false,
+    };
+
+    match name.strip_prefix('_') {
+        Some(stripped) if prefixed => stripped.to_string(),
+        _ => name,
+    }
+}
+
+fn is_rust_internal(name: &str) -> bool {
+    ["_ZN", "_R", "__rust", "rust_", "__rdl", "__rg_"].iter().any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_internal_symbols() {
+        assert!(is_rust_internal("_ZN4core3fmt5write17h0123456789abcdefE"));
+        assert!(is_rust_internal("_RNvCs1234_7mycrate3foo"));
+        assert!(is_rust_internal("rust_eh_personality"));
+        assert!(is_rust_internal("__rust_alloc"));
+        assert!(!is_rust_internal("add_numbers"));
+        assert!(!is_rust_internal("Rectangle_area"));
+    }
+
+    #[test]
+    fn test_crate_members() {
+        assert!(is_crate_member("my_crate.my_crate.3558a9c13655fd8d-cgu.0.rcgu.o", "my_crate"));
+        assert!(is_crate_member("my_crate-0ce073fff809ec38.my_crate.cb55b9896937f0ec-cgu.0.rcgu.o", "my_crate"));
+        assert!(!is_crate_member("my_crate_macros-0ce073fff809ec38.rcgu.o", "my_crate"));
+        assert!(!is_crate_member("std-d1237ef7159db0a2.std.e28293b1aa0f68bd-cgu.0.rcgu.o", "my_crate"));
+    }
+
+    #[test]
+    fn test_library_name() {
+        assert_eq!(library_name(Path::new("cache/libmy_lib.a")), "my_lib");
+        assert_eq!(library_name(Path::new("cache/libmy_lib.so")), "my_lib");
+        assert_eq!(library_name(Path::new("cache/my_lib.lib")), "my_lib");
+        assert_eq!(library_name(Path::new("cache/my_lib.dll")), "my_lib");
+    }
+
+    /// An ELF object defining the given functions.
+    fn object_file(functions: &[&str]) -> Vec<u8> {
+        use object::write::{Object as WriteObject, StandardSection, Symbol, SymbolSection};
+        use object::{Endianness, SymbolFlags, SymbolScope};
+
+        let mut obj = WriteObject::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let text = obj.section_id(StandardSection::Text);
+        for function in functions {
+            let offset = obj.append_section_data(text, &[0xc3], 1);
+            obj.add_symbol(Symbol {
+                name: function.as_bytes().to_vec(),
+                value: offset,
+                size: 1,
+                kind: SymbolKind::Text,
+                scope: SymbolScope::Dynamic,
+                weak: false,
+                section: SymbolSection::Section(text),
+                flags: SymbolFlags::None,
+            });
+        }
+        obj.write().unwrap()
+    }
+
+    /// A BSD-style `ar` archive, whose `#1/<len>` headers allow the long rcgu member names.
+    fn archive(members: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut out = b"!<arch>\n".to_vec();
+        for (name, data) in members {
+            let size = name.len() + data.len();
+            let header = format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", format!("#1/{}", name.len()), 0, 0, 0, 644, size);
+            out.extend_from_slice(header.as_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+            if size % 2 == 1 {
+                out.push(b'\n');
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_report_compares_header_with_exports() {
+        let dir = std::env::temp_dir().join(format!("ghostbind-symbols-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let header = dir.join("my_crate.h");
+        fs::write(&header, "\
+int32_t add(int32_t a, int32_t b);
+int32_t removed(void);
+#if defined(__linux__)
+int32_t linux_only(void);
+#endif
+").unwrap();
+
+        // Package `my-crate` with `[lib] name = "my_lib"`, bundling a dependency
+        let artifact = dir.join("libmy_lib.a");
+        fs::write(&artifact, archive(&[
+            ("my_lib.my_lib.3558a9c13655fd8d-cgu.0.rcgu.o", object_file(&["add", "extra"])),
+            ("dep-0ce073fff809ec38.dep.cb55b9896937f0ec-cgu.0.rcgu.o", object_file(&["dep_exported"])),
+        ])).unwrap();
+
+        let own = read_exported_symbols(&artifact, &library_name(&artifact)).unwrap();
+        let by_package = read_exported_symbols(&artifact, "my-crate").unwrap();
+        let report = SymbolChecker::new().check("my_lib", &artifact, &[header]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(own.into_iter().collect::<Vec<_>>(), vec!["add", "extra"]);
+        // No member is named after the package, so every member is read
+        assert!(by_package.contains("dep_exported"));
+        assert_eq!(report.missing, vec!["removed".to_string()]);
+        assert_eq!(report.undeclared, vec!["extra".to_string()]);
+        assert!(!report.is_clean());
+    }
+}
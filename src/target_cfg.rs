@@ -0,0 +1,272 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use syn::visit::Visit;
+
+use crate::cargo_integration::BuildProfile;
+
+/// A single `cfg` predicate such as `unix` or `target_os = "linux"`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CfgAtom {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+impl CfgAtom {
+    pub fn new(name: &str, value: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.map(|v| v.to_string()),
+        }
+    }
+
+    pub fn feature(name: &str) -> Self {
+        Self::new("feature", Some(name))
+    }
+
+    /// The key cbindgen expects in its `[defines]` table.
+    pub fn define_key(&self) -> String {
+        match self.value {
+            Some(ref value) => format!("{} = {}", self.name, value),
+            None => self.name.clone(),
+        }
+    }
+
+    /// The preprocessor macro ghostbind uses to represent this predicate in headers.
+    pub fn macro_name(&self) -> String {
+        let raw = match self.value {
+            Some(ref value) => format!("GHOSTBIND_CFG_{}_{}", self.name, value),
+            None => format!("GHOSTBIND_CFG_{}", self.name),
+        };
+
+        raw.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect()
+    }
+
+    /// A C preprocessor condition that detects this predicate on the compiling platform.
+    pub fn platform_condition(&self) -> Option<&'static str> {
+        let condition = match (self.name.as_str(), self.value.as_deref()) {
+            ("unix", None) | ("target_family", Some("unix")) => "defined(__unix__) || defined(__APPLE__)",
+            ("windows", None) | ("target_family", Some("windows")) => "defined(_WIN32)",
+            ("target_os", Some("linux")) => "defined(__linux__)",
+            ("target_os", Some("macos")) => "defined(__APPLE__) && defined(__MACH__)",
+            ("target_os", Some("ios")) => "defined(__APPLE__) && defined(__ENVIRONMENT_IPHONE_OS_VERSION_MIN_REQUIRED__)",
+            ("target_os", Some("windows")) => "defined(_WIN32)",
+            ("target_os", Some("freebsd")) => "defined(__FreeBSD__)",
+            ("target_os", Some("android")) => "defined(__ANDROID__)",
+            ("target_vendor", Some("apple")) => "defined(__APPLE__)",
+            ("target_arch", Some("x86_64")) => "defined(__x86_64__) || defined(_M_X64)",
+            ("target_arch", Some("x86")) => "defined(__i386__) || defined(_M_IX86)",
+            ("target_arch", Some("aarch64")) => "defined(__aarch64__) || defined(_M_ARM64)",
+            ("target_arch", Some("arm")) => "defined(__arm__) || defined(_M_ARM)",
+            ("target_pointer_width", Some("64")) => "defined(__LP64__) || defined(_WIN64)",
+            ("target_pointer_width", Some("32")) => "!defined(__LP64__) && !defined(_WIN64)",
+            ("target_env", Some("msvc")) => "defined(_MSC_VER)",
+            ("target_env", Some("gnu")) => "defined(__GLIBC__) || defined(__MINGW32__)",
+            ("target_env", Some("musl")) => "defined(__linux__) && !defined(__GLIBC__)",
+            _ => return None,
+        };
+
+        Some(condition)
+    }
+}
+
+/// The cfg predicates that hold for a build: the target's cfgs plus enabled features.
+#[derive(Debug, Clone, Default)]
+pub struct TargetCfg {
+    atoms: BTreeSet<CfgAtom>,
+}
+
+impl TargetCfg {
+    /// Queries `rustc --print cfg` for the given target (or the host), with the profile's
+    /// debug assertions and the RUSTFLAGS cargo builds with, so `debug_assertions` and
+    /// `-C target-feature` cfgs match the artifact.
+    pub fn for_target(target_triple: Option<&str>, profile: &BuildProfile) -> Result<Self> {
+        let mut cmd = Command::new("rustc");
+        cmd.args(["--print", "cfg"]);
+        if let Some(target) = target_triple {
+            cmd.arg("--target").arg(target);
+        }
+        if matches!(profile, BuildProfile::Release) {
+            cmd.args(["-C", "debug-assertions=off"]);
+        }
+        // After the profile's flags, as cargo passes them
+        cmd.args(rustflags(
+            std::env::var("CARGO_ENCODED_RUSTFLAGS").ok(),
+            std::env::var("RUSTFLAGS").ok(),
+        ));
+
+        let output = cmd.output()
+            .context("Failed to run rustc to query target cfgs")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("rustc --print cfg failed: {}", stderr));
+        }
+
+        Ok(Self::parse(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    pub fn parse(output: &str) -> Self {
+        let atoms = output.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.split_once('=') {
+                Some((name, value)) => CfgAtom::new(name.trim(), Some(value.trim().trim_matches('"'))),
+                None => CfgAtom::new(line, None),
+            })
+            .collect();
+
+        Self { atoms }
+    }
+
+    pub fn with_features<I, S>(mut self, features: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.atoms.extend(features.into_iter().map(|f| CfgAtom::feature(f.as_ref())));
+        self
+    }
+
+    pub fn is_active(&self, atom: &CfgAtom) -> bool {
+        self.atoms.contains(atom)
+    }
+
+    /// The value of a single-valued cfg such as `target_pointer_width`.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.atoms.iter()
+            .find(|atom| atom.name == name)
+            .and_then(|atom| atom.value.as_deref())
+    }
+}
+
+/// The flags cargo hands rustc: `CARGO_ENCODED_RUSTFLAGS` (separated by 0x1f) takes
+/// precedence over the whitespace-separated `RUSTFLAGS`.
+pub(crate) fn rustflags(encoded: Option<String>, plain: Option<String>) -> Vec<String> {
+    match (encoded, plain) {
+        (Some(encoded), _) => encoded.split('\x1f').filter(|flag| !flag.is_empty()).map(str::to_string).collect(),
+        (None, Some(plain)) => plain.split_whitespace().map(str::to_string).collect(),
+        (None, None) => Vec::new(),
+    }
+}
+
+/// Collects every simple predicate used in `#[cfg(...)]` attributes across the given sources.
+pub fn collect_cfg_atoms(sources: &[PathBuf]) -> Result<BTreeSet<CfgAtom>> {
+    let mut collector = CfgCollector::default();
+
+    for source_file in sources {
+        let source = fs::read_to_string(source_file)
+            .with_context(|| format!("Failed to read {}", source_file.display()))?;
+        let file = syn::parse_file(&source)
+            .with_context(|| format!("Failed to parse {}", source_file.display()))?;
+        collector.visit_file(&file);
+    }
+
+    Ok(collector.atoms)
+}
+
+#[derive(Default)]
+struct CfgCollector {
+    atoms: BTreeSet<CfgAtom>,
+}
+
+impl CfgCollector {
+    fn collect_meta(&mut self, meta: &syn::Meta) {
+        match meta {
+            syn::Meta::Path(path) => {
+                if let Some(ident) = path.get_ident() {
+                    self.atoms.insert(CfgAtom::new(&ident.to_string(), None));
+                }
+            }
+            syn::Meta::NameValue(name_value) => {
+                if let (Some(ident), syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. })) =
+                    (name_value.path.get_ident(), &name_value.value)
+                {
+                    self.atoms.insert(CfgAtom::new(&ident.to_string(), Some(&value.value())));
+                }
+            }
+            syn::Meta::List(list) => {
+                // any(...), all(...) and not(...) combine nested predicates
+                let nested = list.parse_args_with(
+                    syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+                );
+                if let Ok(nested) = nested {
+                    for meta in &nested {
+                        self.collect_meta(meta);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CfgCollector {
+    fn visit_attribute(&mut self, attr: &'ast syn::Attribute) {
+        if attr.path().is_ident("cfg")
+            && let Ok(meta) = attr.parse_args::<syn::Meta>()
+        {
+            self.collect_meta(&meta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rustflags() {
+        assert_eq!(rustflags(Some("-C\x1ftarget-feature=+avx2".to_string()), Some("-O".to_string())), vec!["-C", "target-feature=+avx2"]);
+        assert_eq!(rustflags(None, Some(" -C  target-cpu=native ".to_string())), vec!["-C", "target-cpu=native"]);
+        assert!(rustflags(Some(String::new()), None).is_empty());
+    }
+
+    #[test]
+    fn test_parse_rustc_cfg_output() {
+        let cfg = TargetCfg::parse("debug_assertions\ntarget_os=\"linux\"\ntarget_pointer_width=\"64\"\nunix\n")
+            .with_features(["serde"]);
+
+        assert!(cfg.is_active(&CfgAtom::new("unix", None)));
+        assert!(cfg.is_active(&CfgAtom::new("target_os", Some("linux"))));
+        assert!(cfg.is_active(&CfgAtom::feature("serde")));
+        assert!(!cfg.is_active(&CfgAtom::new("target_os", Some("windows"))));
+        assert_eq!(cfg.value("target_pointer_width"), Some("64"));
+        assert_eq!(cfg.value("unix"), None);
+    }
+
+    #[test]
+    fn test_define_key_and_macro_name() {
+        let atom = CfgAtom::new("target_os", Some("linux"));
+        assert_eq!(atom.define_key(), "target_os = linux");
+        assert_eq!(atom.macro_name(), "GHOSTBIND_CFG_TARGET_OS_LINUX");
+
+        let feature = CfgAtom::feature("my-feature");
+        assert_eq!(feature.macro_name(), "GHOSTBIND_CFG_FEATURE_MY_FEATURE");
+
+        assert_eq!(CfgAtom::new("unix", None).define_key(), "unix");
+    }
+
+    #[test]
+    fn test_collect_nested_predicates() {
+        let file = syn::parse_file(r#"
+            #[cfg(all(unix, not(target_os = "macos")))]
+            pub extern "C" fn a() {}
+
+            #[cfg(any(feature = "fast", windows))]
+            pub struct B;
+        "#).unwrap();
+
+        let mut collector = CfgCollector::default();
+        collector.visit_file(&file);
+
+        assert!(collector.atoms.contains(&CfgAtom::new("unix", None)));
+        assert!(collector.atoms.contains(&CfgAtom::new("target_os", Some("macos"))));
+        assert!(collector.atoms.contains(&CfgAtom::feature("fast")));
+        assert!(collector.atoms.contains(&CfgAtom::new("windows", None)));
+        assert_eq!(collector.atoms.len(), 4);
+    }
+}
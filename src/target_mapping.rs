@@ -1,3 +1,4 @@
+use anyhow::{Context, Result};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -45,11 +46,52 @@ impl TargetMapping {
             .to_string()
     }
 
+    pub fn rust_to_zig(&self, rust_target: &str) -> Option<&str> {
+        self.zig_to_rust.iter()
+            .find(|(_, rust)| rust.as_str() == rust_target)
+            .map(|(zig, _)| zig.as_str())
+    }
+
     pub fn supported_targets(&self) -> Vec<&str> {
         self.zig_to_rust.keys().map(|s| s.as_str()).collect()
     }
 }
 
+impl Default for TargetMapping {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn get_host_target() -> Result<String> {
+    // This is a simplified version - in a real implementation,
+    // you might want to detect the actual host target more accurately
+    if cfg!(target_os = "linux") && cfg!(target_arch = "x86_64") {
+        Ok("x86_64-unknown-linux-gnu".to_string())
+    } else if cfg!(target_os = "macos") && cfg!(target_arch = "x86_64") {
+        Ok("x86_64-apple-darwin".to_string())
+    } else if cfg!(target_os = "macos") && cfg!(target_arch = "aarch64") {
+        Ok("aarch64-apple-darwin".to_string())
+    } else if cfg!(target_os = "windows") && cfg!(target_arch = "x86_64") {
+        Ok("x86_64-pc-windows-msvc".to_string())
+    } else {
+        // Fallback - use rustc to get the host target
+        let output = std::process::Command::new("rustc")
+            .args(["--version", "--verbose"])
+            .output()
+            .context("Failed to run rustc to detect host target")?;
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        for line in output_str.lines() {
+            if line.starts_with("host: ") {
+                return Ok(line.strip_prefix("host: ").unwrap().to_string());
+            }
+        }
+
+        Err(anyhow::anyhow!("Could not detect host target"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,4 +122,11 @@ mod tests {
         assert_eq!(mapping.map_target("unknown-target"), None);
         assert_eq!(mapping.map_target_or_default("unknown-target"), "unknown-target");
     }
+
+    #[test]
+    fn test_reverse_mapping() {
+        let mapping = TargetMapping::new();
+        assert_eq!(mapping.rust_to_zig("aarch64-apple-darwin"), Some("aarch64-macos"));
+        assert_eq!(mapping.rust_to_zig("unknown-target"), None);
+    }
 }
\ No newline at end of file
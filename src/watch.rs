@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::cargo_integration::CrateInfo;
+use crate::config::CONFIG_FILE;
+use crate::pipeline::BuildOutcome;
+
+/// Polls a set of files and directories for changes. Directories are watched
+/// recursively, so files added to them are picked up too.
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    interval: Duration,
+    debounce: Duration,
+    snapshot: Snapshot,
+}
+
+/// Size and modification time of every watched file
+type Snapshot = BTreeMap<PathBuf, (u64, Option<SystemTime>)>;
+
+impl Watcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let snapshot = snapshot(&paths);
+        Self {
+            paths,
+            interval: Duration::from_millis(200),
+            debounce: Duration::from_millis(300),
+            snapshot,
+        }
+    }
+
+    /// How often to look for changes.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// How long the files must stay unchanged before a change is reported, so a burst
+    /// of saves only triggers one rebuild.
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Replaces the watched paths, e.g. after modules were added or removed. Files that
+    /// were already watched keep their last seen state, so no change to them is lost.
+    pub fn set_paths(&mut self, paths: Vec<PathBuf>) {
+        let mut current = snapshot(&paths);
+        for (path, state) in current.iter_mut() {
+            if let Some(seen) = self.snapshot.get(path) {
+                *state = *seen;
+            }
+        }
+        self.paths = paths;
+        self.snapshot = current;
+    }
+
+    /// Blocks until something changed and then settled, returning what changed.
+    pub fn wait_for_change(&mut self) -> Vec<PathBuf> {
+        let mut changed = loop {
+            thread::sleep(self.interval);
+            let changed = self.poll();
+            if !changed.is_empty() {
+                break changed;
+            }
+        };
+
+        let mut quiet = Duration::ZERO;
+        while quiet < self.debounce {
+            thread::sleep(self.interval);
+            let more = self.poll();
+            if more.is_empty() {
+                quiet += self.interval;
+            } else {
+                changed.extend(more);
+                quiet = Duration::ZERO;
+            }
+        }
+
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+
+    fn poll(&mut self) -> Vec<PathBuf> {
+        let current = snapshot(&self.paths);
+        let changed = changed_paths(&self.snapshot, &current);
+        self.snapshot = current;
+        changed
+    }
+}
+
+/// The files a rebuild depends on: the library's module files, Cargo.toml, Cargo.lock,
+/// build.rs, ghostbind.toml and the cbindgen config.
+pub fn watched_paths(crate_info: &CrateInfo, cbindgen_config: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let crate_dir = &crate_info.manifest_dir;
+    let mut paths = crate_info.lib_source_files()?;
+    paths.extend([
+        crate_dir.join("Cargo.toml"),
+        crate_info.workspace_root.join("Cargo.lock"),
+        crate_dir.join("build.rs"),
+        crate_dir.join(CONFIG_FILE),
+        cbindgen_config.map(Path::to_path_buf).unwrap_or_else(|| crate_dir.join("cbindgen.toml")),
+    ]);
+    paths.dedup();
+    Ok(paths)
+}
+
+/// A hash of the contents of the artifacts and headers a build produced. When it
+/// hasn't changed, neither has anything a downstream build would consume.
+pub fn output_fingerprint(outcome: &BuildOutcome) -> Result<String> {
+    let files = outcome.artifacts.iter()
+        .map(|artifact| &artifact.cached_path)
+        .chain(outcome.headers.iter().map(|header| &header.header_path));
+
+    let mut hasher = Sha256::new();
+    for path in files {
+        let contents = fs::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update(Sha256::digest(&contents));
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn snapshot(paths: &[PathBuf]) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    for path in paths {
+        add_to_snapshot(path, &mut snapshot);
+    }
+    snapshot
+}
+
+/// Missing paths are skipped: they show up as added once they're created.
+fn add_to_snapshot(path: &Path, snapshot: &mut Snapshot) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+
+    if metadata.is_dir() {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            let hidden = entry_path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if !hidden {
+                add_to_snapshot(&entry_path, snapshot);
+            }
+        }
+    } else {
+        snapshot.insert(path.to_path_buf(), (metadata.len(), metadata.modified().ok()));
+    }
+}
+
+/// Files that were added, removed or modified between two snapshots.
+fn changed_paths(before: &Snapshot, after: &Snapshot) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = after.iter()
+        .filter(|(path, state)| before.get(*path) != Some(state))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(before.keys().filter(|path| !after.contains_key(*path)).cloned());
+    changed.sort();
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_paths() {
+        let dir = std::env::temp_dir().join(format!("ghostbind-watch-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "pub fn a() {}\n").unwrap();
+        fs::write(dir.join("src/.lib.rs.swp"), "").unwrap();
+        fs::write(dir.join("Cargo.toml"), "[package]\n").unwrap();
+
+        let paths = vec![dir.join("src"), dir.join("Cargo.toml"), dir.join("cbindgen.toml")];
+        let before = snapshot(&paths);
+        assert_eq!(before.len(), 2);
+
+        fs::write(dir.join("src/lib.rs"), "pub fn a() {}\npub fn b() {}\n").unwrap();
+        fs::write(dir.join("src/ffi.rs"), "").unwrap();
+        fs::write(dir.join("cbindgen.toml"), "").unwrap();
+        fs::remove_file(dir.join("Cargo.toml")).unwrap();
+        let after = snapshot(&paths);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(changed_paths(&before, &after), vec![
+            dir.join("Cargo.toml"),
+            dir.join("cbindgen.toml"),
+            dir.join("src/ffi.rs"),
+            dir.join("src/lib.rs"),
+        ]);
+        assert!(changed_paths(&after, &after).is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::manifest::BuildManifest;
+use crate::target_mapping::TargetMapping;
+
+/// Writes a Zig package whose `build.zig` exposes `link(step)` for a built crate, so a
+/// consumer's build.zig needs one call instead of hand-written linking code.
+pub struct ZigModuleGenerator {
+    cache_dir: PathBuf,
+    output_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedZigModule {
+    pub crate_name: String,
+    /// The crate name as a Zig identifier, used for the package and its import
+    pub package_name: String,
+    pub dir: PathBuf,
+    pub build_zig: PathBuf,
+    pub build_zig_zon: PathBuf,
+}
+
+impl GeneratedZigModule {
+    /// The entry to add under `.dependencies` in the consumer's build.zig.zon.
+    pub fn dependency_entry(&self) -> String {
+        format!(".{} = .{{ .path = \"{}\" }},", self.package_name, self.dir.display())
+    }
+}
+
+impl ZigModuleGenerator {
+    pub fn new() -> Self {
+        Self {
+            cache_dir: PathBuf::from(".ghostbind/cache"),
+            output_dir: None,
+        }
+    }
+
+    pub fn output_dir(mut self, output_dir: Option<PathBuf>) -> Self {
+        self.output_dir = output_dir;
+        self
+    }
+
+    /// Generates the package from one manifest per target of the same crate.
+    pub fn generate(&self, manifests: &[BuildManifest]) -> Result<GeneratedZigModule> {
+        let first = manifests.first().ok_or_else(|| anyhow!("No manifests given"))?;
+        if let Some(other) = manifests.iter().find(|m| m.crate_name != first.crate_name) {
+            return Err(anyhow!(
+                "All manifests must be for the same crate, found {} and {}",
+                first.crate_name,
+                other.crate_name
+            ));
+        }
+
+        let dir = self.output_dir.clone()
+            .unwrap_or_else(|| self.cache_dir.join("zig-module").join(&first.crate_name));
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create module directory: {}", dir.display()))?;
+        let dir = dir.canonicalize()?;
+
+        let build_zig = dir.join("build.zig");
+        fs::write(&build_zig, build_zig_source(manifests)?)
+            .with_context(|| format!("Failed to write {}", build_zig.display()))?;
+
+        let build_zig_zon = dir.join("build.zig.zon");
+        fs::write(&build_zig_zon, build_zig_zon_source(first, &dir))
+            .with_context(|| format!("Failed to write {}", build_zig_zon.display()))?;
+
+        Ok(GeneratedZigModule {
+            crate_name: first.crate_name.clone(),
+            package_name: zig_package_name(&first.crate_name),
+            dir,
+            build_zig,
+            build_zig_zon,
+        })
+    }
+}
+
+impl Default for ZigModuleGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn build_zig_source(manifests: &[BuildManifest]) -> Result<String> {
+    let crate_name = &manifests[0].crate_name;
+    let mut out = String::new();
+    writeln!(out, "// Generated by ghostbind. Do not edit.")?;
+    writeln!(out, "const std = @import(\"std\");\n")?;
+    writeln!(out, "pub fn build(b: *std.Build) void {{\n    _ = b;\n}}\n")?;
+    writeln!(out, "/// Links {} into `step`: the Rust artifact, its headers and the system", crate_name)?;
+    writeln!(out, "/// libraries and frameworks it needs, for the step's target.")?;
+    writeln!(out, "pub fn link(step: *std.Build.Step.Compile) void {{")?;
+    writeln!(out, "    const module = step.root_module;")?;
+    if manifests.len() > 1 {
+        writeln!(out, "    const target = step.rootModuleTarget();\n")?;
+    }
+
+    let mapping = TargetMapping::new();
+    for manifest in manifests {
+        let indent = if manifests.len() > 1 {
+            let condition = target_condition(&mapping, &manifest.rustc_target)?;
+            writeln!(out, "    if ({}) {{", condition)?;
+            "        "
+        } else {
+            "    "
+        };
+
+        writeln!(out, "{}// {}", indent, manifest.rustc_target)?;
+        link_statements(&mut out, indent, manifest)?;
+
+        if manifests.len() > 1 {
+            writeln!(out, "        return;\n    }}")?;
+        }
+    }
+
+    if manifests.len() > 1 {
+        writeln!(out, "\n    @panic(\"ghostbind: {} was not built for this target\");", crate_name)?;
+    }
+    writeln!(out, "}}")?;
+
+    Ok(out)
+}
+
+fn link_statements(out: &mut String, indent: &str, manifest: &BuildManifest) -> Result<()> {
+    let artifact = absolute(&manifest.artifact)?;
+    writeln!(out, "{}module.addObjectFile(.{{ .cwd_relative = {} }});", indent, zig_path(&artifact))?;
+    if manifest.kind == "cdylib"
+        && let Some(dir) = artifact.parent()
+    {
+        writeln!(out, "{}module.addRPath(.{{ .cwd_relative = {} }});", indent, zig_path(dir))?;
+    }
+
+    let include_dirs: BTreeSet<PathBuf> = manifest.headers.iter()
+        .map(|h| absolute(h).map(|h| h.parent().map(Path::to_path_buf).unwrap_or_default()))
+        .collect::<Result<_>>()?;
+    for dir in &include_dirs {
+        writeln!(out, "{}module.addIncludePath(.{{ .cwd_relative = {} }});", indent, zig_path(dir))?;
+    }
+
+    for dir in &manifest.link_search {
+        writeln!(out, "{}module.addLibraryPath(.{{ .cwd_relative = {} }});", indent, zig_path(dir))?;
+    }
+    for lib in &manifest.link_libs {
+        // libc is linked through link_libc rather than as a system library
+        if lib != "c" {
+            writeln!(out, "{}module.linkSystemLibrary(\"{}\", .{{}});", indent, lib)?;
+        }
+    }
+    for framework in &manifest.frameworks {
+        writeln!(out, "{}module.linkFramework(\"{}\", .{{}});", indent, framework)?;
+    }
+    writeln!(out, "{}module.link_libc = true;", indent)?;
+
+    Ok(())
+}
+
+/// A Zig expression that matches the manifest's target, e.g.
+/// `target.cpu.arch == .x86_64 and target.os.tag == .linux and target.abi == .gnu`.
+fn target_condition(mapping: &TargetMapping, rustc_target: &str) -> Result<String> {
+    let zig_target = mapping.rust_to_zig(rustc_target)
+        .ok_or_else(|| anyhow!("No Zig target known for {}", rustc_target))?;
+
+    let mut parts = zig_target.split('-');
+    let arch = match parts.next().unwrap_or_default() {
+        "i386" => "x86",
+        arch => arch,
+    };
+    let mut condition = format!("target.cpu.arch == .{}", arch);
+    if let Some(os) = parts.next() {
+        write!(condition, " and target.os.tag == .{}", os)?;
+    }
+    if let Some(abi) = parts.next() {
+        write!(condition, " and target.abi == .{}", abi)?;
+    }
+
+    Ok(condition)
+}
+
+/// A Zig 0.14 build.zig.zon; older versions reject the enum-literal name and fingerprint.
+fn build_zig_zon_source(manifest: &BuildManifest, dir: &Path) -> String {
+    let name = zig_package_name(&manifest.crate_name);
+    let version = if manifest.crate_version.is_empty() { "0.0.0" } else { &manifest.crate_version };
+
+    format!(
+        ".{{\n    .name = .{},\n    .version = \"{}\",\n    .fingerprint = 0x{:016x},\n    .minimum_zig_version = \"0.14.0\",\n    .paths = .{{\n        \"build.zig\",\n        \"build.zig.zon\",\n    }},\n}}\n",
+        name,
+        version,
+        fingerprint(&name, dir)
+    )
+}
+
+/// Zig package names must be identifiers.
+pub(crate) fn zig_package_name(crate_name: &str) -> String {
+    crate_name.replace('-', "_")
+}
+
+/// Zig checks that the upper half of a package fingerprint is the CRC32 of its name;
+/// the lower half only has to be unique, so it is derived from the package directory.
+pub(crate) fn fingerprint(name: &str, dir: &Path) -> u64 {
+    let id = fnv1a(dir.to_string_lossy().as_bytes()).clamp(1, 0xffff_fffe);
+    (u64::from(crc32(name.as_bytes())) << 32) | u64::from(id)
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn fnv1a(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5u32, |hash, &byte| (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193))
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+    path.canonicalize()
+        .with_context(|| format!("File does not exist: {}", path.display()))
+}
+
+fn zig_path(path: &Path) -> String {
+    format!("\"{}\"", path.display().to_string().replace('\\', "\\\\"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_condition() {
+        let mapping = TargetMapping::new();
+
+        assert_eq!(
+            target_condition(&mapping, "x86_64-unknown-linux-gnu").unwrap(),
+            "target.cpu.arch == .x86_64 and target.os.tag == .linux and target.abi == .gnu"
+        );
+        assert_eq!(
+            target_condition(&mapping, "aarch64-apple-darwin").unwrap(),
+            "target.cpu.arch == .aarch64 and target.os.tag == .macos"
+        );
+        assert!(target_condition(&mapping, "wasm32-unknown-unknown").is_err());
+    }
+
+    #[test]
+    fn test_fingerprint_checksum() {
+        // The CRC32 of "hello" is 0x3610a686
+        assert_eq!(crc32(b"hello"), 0x3610_a686);
+        assert_eq!(fingerprint("hello", Path::new("/tmp/hello")) >> 32, 0x3610_a686);
+    }
+}
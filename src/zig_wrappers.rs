@@ -0,0 +1,642 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::cargo_integration::CrateInfo;
+use crate::header_generation::GeneratedHeader;
+
+/// Generates idiomatic Zig wrappers on top of the cbindgen header.
+///
+/// Exported functions can opt into richer wrappers with doc comment annotations:
+///
+/// - `/// ghostbind: errors = MyErrorCode` turns a `#[repr(C)]` result code into a
+///   Zig error union (discriminant `0` is success). A trailing `*mut T` parameter
+///   named `out*` becomes the success value.
+/// - `/// ghostbind: free = free_thing` wraps the returned pointer in an `Owned`
+///   helper whose `deinit` calls the matching free function.
+///
+/// `(ptr, len)` parameter pairs are turned into Zig slices for every function.
+pub struct ZigWrapperGenerator {
+    cache_dir: PathBuf,
+    type_prefix: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedZigWrapper {
+    pub crate_name: String,
+    pub wrapper_path: PathBuf,
+}
+
+#[derive(Clone, Default)]
+struct FfiItems {
+    functions: Vec<ExportedFn>,
+    error_enums: BTreeMap<String, Vec<(String, i64)>>,
+}
+
+#[derive(Clone)]
+struct ExportedFn {
+    name: String,
+    params: Vec<(String, syn::Type)>,
+    ret: Option<syn::Type>,
+    errors: Option<String>,
+    free: Option<String>,
+}
+
+/// How a single Rust parameter is surfaced in the Zig wrapper.
+enum WrapperParam {
+    Plain { name: String, ty: String },
+    Slice { name: String, ty: String },
+    Out { name: String, ty: String },
+}
+
+impl ZigWrapperGenerator {
+    pub fn new() -> Self {
+        Self {
+            cache_dir: PathBuf::from(".ghostbind/cache"),
+            type_prefix: None,
+        }
+    }
+
+    /// The cbindgen `[export] prefix`, which the header puts in front of type names.
+    pub fn type_prefix(mut self, type_prefix: Option<String>) -> Self {
+        self.type_prefix = type_prefix;
+        self
+    }
+
+    pub fn generate(
+        &self,
+        crate_info: &CrateInfo,
+        headers: &[GeneratedHeader],
+        target_triple: Option<&str>,
+    ) -> Result<GeneratedZigWrapper> {
+        let header = headers.first()
+            .ok_or_else(|| anyhow!("Zig wrappers require a generated header"))?;
+        let header_name = header.header_path.file_name()
+            .ok_or_else(|| anyhow!("Invalid header path: {}", header.header_path.display()))?
+            .to_string_lossy()
+            .to_string();
+
+        let mut items = FfiItems::default();
+        for source_file in crate_info.lib_source_files()? {
+            let source = fs::read_to_string(&source_file)
+                .with_context(|| format!("Failed to read {}", source_file.display()))?;
+            collect_items(&source, &mut items)
+                .with_context(|| format!("Failed to parse {}", source_file.display()))?;
+        }
+
+        let zig_source = render_wrappers(&items, &header_name, self.type_prefix.as_deref().unwrap_or_default())?;
+
+        let wrapper_path = self.get_wrapper_path(&crate_info.name, target_triple);
+        if let Some(wrapper_parent) = wrapper_path.parent() {
+            fs::create_dir_all(wrapper_parent)
+                .with_context(|| format!("Failed to create Zig wrapper directory: {}", wrapper_parent.display()))?;
+        }
+
+        fs::write(&wrapper_path, zig_source)
+            .with_context(|| format!("Failed to write Zig wrappers to {}", wrapper_path.display()))?;
+
+        Ok(GeneratedZigWrapper {
+            crate_name: crate_info.name.clone(),
+            wrapper_path,
+        })
+    }
+
+    fn get_wrapper_path(&self, crate_name: &str, target_triple: Option<&str>) -> PathBuf {
+        let target_str = target_triple.unwrap_or("native");
+
+        self.cache_dir
+            .join(target_str)
+            .join("zig")
+            .join(format!("{}.zig", crate_name))
+    }
+}
+
+impl Default for ZigWrapperGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn collect_items(source: &str, items: &mut FfiItems) -> Result<()> {
+    let file = syn::parse_file(source)?;
+    collect_from_items(&file.items, items);
+    Ok(())
+}
+
+fn collect_from_items(syn_items: &[syn::Item], items: &mut FfiItems) {
+    for item in syn_items {
+        match item {
+            syn::Item::Fn(item_fn) => {
+                if let Some(function) = exported_fn(item_fn) {
+                    items.functions.push(function);
+                }
+            }
+            syn::Item::Enum(item_enum) => {
+                if let Some(variants) = enum_discriminants(item_enum) {
+                    items.error_enums.insert(item_enum.ident.to_string(), variants);
+                }
+            }
+            syn::Item::Mod(item_mod) => {
+                if let Some((_, ref content)) = item_mod.content {
+                    collect_from_items(content, items);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn exported_fn(item_fn: &syn::ItemFn) -> Option<ExportedFn> {
+    let abi = item_fn.sig.abi.as_ref()?;
+    if abi.name.as_ref().is_some_and(|name| name.value() != "C") {
+        return None;
+    }
+
+    let mut name = None;
+    for attr in &item_fn.attrs {
+        if attr.path().is_ident("no_mangle") {
+            name = Some(item_fn.sig.ident.to_string());
+        } else if attr.path().is_ident("unsafe") {
+            let tokens = attr.meta.require_list().ok()?.tokens.to_string();
+            if tokens.trim() == "no_mangle" {
+                name = Some(item_fn.sig.ident.to_string());
+            } else if let Some(export_name) = export_name_from_tokens(&tokens) {
+                name = Some(export_name);
+            }
+        } else if attr.path().is_ident("export_name")
+            && let syn::Meta::NameValue(syn::MetaNameValue { value: syn::Expr::Lit(lit), .. }) = &attr.meta
+            && let syn::Lit::Str(s) = &lit.lit
+        {
+            name = Some(s.value());
+        }
+    }
+
+    let mut function = ExportedFn {
+        name: name?,
+        params: Vec::new(),
+        ret: match &item_fn.sig.output {
+            syn::ReturnType::Default => None,
+            syn::ReturnType::Type(_, ty) => Some((**ty).clone()),
+        },
+        errors: None,
+        free: None,
+    };
+
+    for input in &item_fn.sig.inputs {
+        if let syn::FnArg::Typed(pat_type) = input {
+            let param_name = match &*pat_type.pat {
+                syn::Pat::Ident(ident) => ident.ident.to_string(),
+                _ => format!("arg{}", function.params.len()),
+            };
+            function.params.push((param_name, (*pat_type.ty).clone()));
+        }
+    }
+
+    for (key, value) in doc_annotations(&item_fn.attrs) {
+        match key.as_str() {
+            "errors" => function.errors = Some(value),
+            "free" => function.free = Some(value),
+            _ => {}
+        }
+    }
+
+    Some(function)
+}
+
+fn export_name_from_tokens(tokens: &str) -> Option<String> {
+    let rest = tokens.trim().strip_prefix("export_name")?.trim().strip_prefix('=')?;
+    Some(rest.trim().trim_matches('"').to_string())
+}
+
+/// Extracts `ghostbind: key = value` annotations from doc comments.
+fn doc_annotations(attrs: &[syn::Attribute]) -> Vec<(String, String)> {
+    let mut annotations = Vec::new();
+
+    for attr in attrs {
+        if !attr.path().is_ident("doc") {
+            continue;
+        }
+        let syn::Meta::NameValue(name_value) = &attr.meta else { continue };
+        let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(doc), .. }) = &name_value.value else { continue };
+
+        let doc = doc.value();
+        let Some(annotation) = doc.trim().strip_prefix("ghostbind:") else { continue };
+        for pair in annotation.split(',') {
+            if let Some((key, value)) = pair.split_once('=') {
+                annotations.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+    }
+
+    annotations
+}
+
+/// Returns the variant discriminants of a fieldless `#[repr(...)]` enum.
+fn enum_discriminants(item_enum: &syn::ItemEnum) -> Option<Vec<(String, i64)>> {
+    if !item_enum.attrs.iter().any(|attr| attr.path().is_ident("repr")) {
+        return None;
+    }
+
+    let mut variants = Vec::new();
+    let mut next = 0i64;
+
+    for variant in &item_enum.variants {
+        if !matches!(variant.fields, syn::Fields::Unit) {
+            return None;
+        }
+        if let Some((_, expr)) = &variant.discriminant {
+            next = int_literal(expr)?;
+        }
+        variants.push((variant.ident.to_string(), next));
+        next += 1;
+    }
+
+    Some(variants)
+}
+
+fn int_literal(expr: &syn::Expr) -> Option<i64> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. }) => int.base10_parse().ok(),
+        syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Neg(_), expr, .. }) => int_literal(expr).map(|v| -v),
+        syn::Expr::Paren(paren) => int_literal(&paren.expr),
+        _ => None,
+    }
+}
+
+/// `prefix` is the cbindgen export prefix of the header's type names.
+fn render_wrappers(items: &FfiItems, header_name: &str, prefix: &str) -> Result<String> {
+    let mut out = String::new();
+    let mut body = String::new();
+    let mut used_enums = Vec::new();
+    let mut needs_owned = false;
+
+    for function in &items.functions {
+        if let Some(ref enum_name) = function.errors {
+            if !items.error_enums.contains_key(enum_name) {
+                return Err(anyhow!(
+                    "Function '{}' references error enum '{}', which is not a fieldless #[repr] enum in this crate",
+                    function.name,
+                    enum_name
+                ));
+            }
+            if !used_enums.contains(enum_name) {
+                used_enums.push(enum_name.clone());
+            }
+        }
+        needs_owned |= function.free.is_some();
+        render_function(function, prefix, &mut body);
+    }
+
+    writeln!(out, "// Generated by ghostbind. Don't modify this manually.")?;
+    writeln!(out, "// Add the directory containing {} to the include path.", header_name)?;
+    writeln!(out)?;
+    writeln!(out, "pub const c = @cImport({{")?;
+    writeln!(out, "    @cInclude(\"{}\");", header_name)?;
+    writeln!(out, "}});")?;
+
+    if needs_owned {
+        writeln!(out)?;
+        writeln!(out, "/// A Rust-allocated value released by `deinit`, e.g. `defer value.deinit();`.")?;
+        writeln!(out, "pub fn Owned(comptime T: type, comptime free_fn: anytype) type {{")?;
+        writeln!(out, "    return struct {{")?;
+        writeln!(out, "        ptr: T,")?;
+        writeln!(out)?;
+        writeln!(out, "        pub fn deinit(self: @This()) void {{")?;
+        writeln!(out, "            free_fn(self.ptr);")?;
+        writeln!(out, "        }}")?;
+        writeln!(out, "    }};")?;
+        writeln!(out, "}}")?;
+    }
+
+    for enum_name in &used_enums {
+        let variants = &items.error_enums[enum_name];
+        let error_names: Vec<&str> = variants.iter()
+            .filter(|(_, value)| *value != 0)
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        writeln!(out)?;
+        write!(out, "pub const {}Error = error{{ ", enum_name)?;
+        for name in &error_names {
+            write!(out, "{}, ", zig_ident(name))?;
+        }
+        writeln!(out, "Unknown }};")?;
+        writeln!(out)?;
+        writeln!(out, "fn check{}(rc: anytype) {}Error!void {{", enum_name, enum_name)?;
+        writeln!(out, "    return switch (@as(i64, @intCast(rc))) {{")?;
+        for (name, value) in variants {
+            if *value == 0 {
+                writeln!(out, "        0 => {{}},")?;
+            } else {
+                writeln!(out, "        {} => error.{},", value, zig_ident(name))?;
+            }
+        }
+        writeln!(out, "        else => error.Unknown,")?;
+        writeln!(out, "    }};")?;
+        writeln!(out, "}}")?;
+    }
+
+    out.push_str(&body);
+    Ok(out)
+}
+
+fn render_function(function: &ExportedFn, prefix: &str, out: &mut String) {
+    let Some(params) = wrapper_params(function, prefix) else {
+        // Parameter types we can't express are left to the raw binding
+        let _ = write!(out, "\npub const {} = c.{};\n", zig_ident(&function.name), function.name);
+        return;
+    };
+    let Some(ret_ty) = function.ret.as_ref().map_or(Some("void".to_string()), |ty| zig_type(ty, prefix)) else {
+        let _ = write!(out, "\npub const {} = c.{};\n", zig_ident(&function.name), function.name);
+        return;
+    };
+
+    let has_slices = params.iter().any(|p| matches!(p, WrapperParam::Slice { .. }));
+    if !has_slices && function.errors.is_none() && function.free.is_none() {
+        let _ = write!(out, "\npub const {} = c.{};\n", zig_ident(&function.name), function.name);
+        return;
+    }
+
+    let out_param = params.iter().find_map(|p| match p {
+        WrapperParam::Out { name, ty } => Some((name.clone(), ty.clone())),
+        _ => None,
+    });
+
+    // The value handed back to Zig: the out-parameter when present, otherwise the return value
+    let value_ty = match (&function.errors, &out_param) {
+        (Some(_), Some((_, ty))) => ty.clone(),
+        (Some(_), None) => "void".to_string(),
+        (None, _) => ret_ty.clone(),
+    };
+    let value_ty = match function.free {
+        Some(ref free_fn) if value_ty != "void" => format!("Owned({}, c.{})", value_ty, free_fn),
+        _ => value_ty,
+    };
+    let result_ty = match function.errors {
+        Some(ref enum_name) => format!("{}Error!{}", enum_name, value_ty),
+        None if function.free.is_some() && value_ty != "void" => format!("?{}", value_ty),
+        None => value_ty,
+    };
+
+    let signature: Vec<String> = params.iter()
+        .filter_map(|p| match p {
+            WrapperParam::Plain { name, ty } => Some(format!("{}: {}", zig_ident(name), ty)),
+            WrapperParam::Slice { name, ty } => Some(format!("{}: {}", zig_ident(name), ty)),
+            WrapperParam::Out { .. } => None,
+        })
+        .collect();
+    let call_args: Vec<String> = params.iter()
+        .map(|p| match p {
+            WrapperParam::Plain { name, .. } => zig_ident(name),
+            WrapperParam::Slice { name, .. } => format!("{0}.ptr, {0}.len", zig_ident(name)),
+            WrapperParam::Out { name, .. } => format!("&{}", zig_ident(name)),
+        })
+        .collect();
+    let call = format!("c.{}({})", function.name, call_args.join(", "));
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "pub fn {}({}) {} {{", zig_ident(&function.name), signature.join(", "), result_ty);
+
+    if let Some((ref name, ref ty)) = out_param
+        && function.errors.is_some()
+    {
+        let _ = writeln!(out, "    var {}: {} = undefined;", zig_ident(name), ty);
+    }
+
+    match (&function.errors, &function.free) {
+        (Some(enum_name), _) => {
+            let _ = writeln!(out, "    try check{}({});", enum_name, call);
+            match (&out_param, &function.free) {
+                (Some((name, _)), Some(_)) => {
+                    let _ = writeln!(out, "    return .{{ .ptr = {} }};", zig_ident(name));
+                }
+                (Some((name, _)), None) => {
+                    let _ = writeln!(out, "    return {};", zig_ident(name));
+                }
+                (None, _) => {}
+            }
+        }
+        (None, Some(_)) if ret_ty != "void" => {
+            let _ = writeln!(out, "    const ptr = {};", call);
+            let _ = writeln!(out, "    if (ptr == null) return null;");
+            let _ = writeln!(out, "    return .{{ .ptr = ptr }};");
+        }
+        _ if ret_ty == "void" => {
+            let _ = writeln!(out, "    {};", call);
+        }
+        _ => {
+            let _ = writeln!(out, "    return {};", call);
+        }
+    }
+
+    let _ = writeln!(out, "}}");
+}
+
+fn wrapper_params(function: &ExportedFn, prefix: &str) -> Option<Vec<WrapperParam>> {
+    let mut params = Vec::new();
+    let mut index = 0;
+
+    while index < function.params.len() {
+        let (ref name, ref ty) = function.params[index];
+
+        if let syn::Type::Ptr(ptr) = ty {
+            let is_last = index + 1 == function.params.len();
+            if function.errors.is_some() && is_last && ptr.mutability.is_some() && name.starts_with("out") {
+                params.push(WrapperParam::Out { name: name.clone(), ty: zig_type(&ptr.elem, prefix)? });
+                index += 1;
+                continue;
+            }
+
+            if let Some((len_name, len_ty)) = function.params.get(index + 1)
+                && is_len_param(name, len_name)
+                && is_usize(len_ty)
+                && !is_void(&ptr.elem)
+            {
+                let elem = zig_type(&ptr.elem, prefix)?;
+                let slice_name = name.strip_suffix("_ptr").unwrap_or(name).to_string();
+                let ty = if ptr.mutability.is_some() {
+                    format!("[]{}", elem)
+                } else {
+                    format!("[]const {}", elem)
+                };
+                params.push(WrapperParam::Slice { name: slice_name, ty });
+                index += 2;
+                continue;
+            }
+        }
+
+        params.push(WrapperParam::Plain { name: name.clone(), ty: zig_type(ty, prefix)? });
+        index += 1;
+    }
+
+    Some(params)
+}
+
+fn is_len_param(ptr_name: &str, len_name: &str) -> bool {
+    let base = ptr_name.strip_suffix("_ptr").unwrap_or(ptr_name);
+    matches!(len_name, "len" | "count" | "size")
+        || len_name == format!("{}_len", base)
+        || len_name == format!("{}_count", base)
+}
+
+fn is_usize(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(path) if path.path.is_ident("usize"))
+}
+
+fn is_void(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(path) if path.path.segments.last().is_some_and(|s| s.ident == "c_void"))
+}
+
+/// Maps a Rust FFI type to the type Zig's `@cImport` produces for it.
+fn zig_type(ty: &syn::Type, prefix: &str) -> Option<String> {
+    match ty {
+        syn::Type::Ptr(ptr) => {
+            let elem = if is_void(&ptr.elem) { "anyopaque".to_string() } else { zig_type(&ptr.elem, prefix)? };
+            if ptr.mutability.is_some() {
+                Some(format!("[*c]{}", elem))
+            } else {
+                Some(format!("[*c]const {}", elem))
+            }
+        }
+        syn::Type::Tuple(tuple) if tuple.elems.is_empty() => Some("void".to_string()),
+        syn::Type::Never(_) => Some("noreturn".to_string()),
+        syn::Type::Paren(paren) => zig_type(&paren.elem, prefix),
+        syn::Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+            if !matches!(segment.arguments, syn::PathArguments::None) {
+                return None;
+            }
+            let name = segment.ident.to_string();
+            let mapped = match name.as_str() {
+                "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "isize" | "usize"
+                | "f32" | "f64" | "bool" | "c_int" | "c_uint" | "c_long" | "c_ulong" | "c_longlong"
+                | "c_ulonglong" | "c_short" | "c_ushort" => name,
+                "c_char" | "c_uchar" => "u8".to_string(),
+                "c_schar" => "i8".to_string(),
+                "c_float" => "f32".to_string(),
+                "c_double" => "f64".to_string(),
+                "c_void" => "anyopaque".to_string(),
+                _ => format!("c.{}{}", prefix, name),
+            };
+            Some(mapped)
+        }
+        _ => None,
+    }
+}
+
+fn zig_ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "addrspace", "align", "allowzero", "and", "anyframe", "anytype", "asm", "break", "callconv",
+        "catch", "comptime", "const", "continue", "defer", "else", "enum", "errdefer", "error",
+        "export", "extern", "fn", "for", "if", "inline", "linksection", "noalias", "noinline",
+        "nosuspend", "opaque", "or", "orelse", "packed", "pub", "resume", "return", "struct",
+        "suspend", "switch", "test", "threadlocal", "try", "type", "union", "unreachable", "var",
+        "volatile", "while",
+    ];
+
+    if KEYWORDS.contains(&name) {
+        format!("@\"{}\"", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+        #[repr(C)]
+        pub enum MyErrorCode {
+            Success = 0,
+            InvalidInput = 1,
+            NullPointer = 2,
+        }
+
+        /// Parse a config blob
+        /// ghostbind: errors = MyErrorCode
+        #[unsafe(no_mangle)]
+        pub extern "C" fn parse_config(input_ptr: *const u8, input_len: usize, out: *mut Config) -> MyErrorCode {
+            MyErrorCode::Success
+        }
+
+        /// ghostbind: free = free_string
+        #[unsafe(no_mangle)]
+        pub extern "C" fn get_message() -> *mut c_char {
+            std::ptr::null_mut()
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+
+        pub fn not_exported() {}
+    "#;
+
+    fn render(source: &str) -> String {
+        let mut items = FfiItems::default();
+        collect_items(source, &mut items).unwrap();
+        render_wrappers(&items, "my_crate.h", "").unwrap()
+    }
+
+    #[test]
+    fn test_collects_exported_functions() {
+        let mut items = FfiItems::default();
+        collect_items(SOURCE, &mut items).unwrap();
+
+        let names: Vec<&str> = items.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["parse_config", "get_message", "add"]);
+        assert_eq!(items.functions[0].errors.as_deref(), Some("MyErrorCode"));
+        assert_eq!(items.functions[1].free.as_deref(), Some("free_string"));
+        assert_eq!(items.error_enums["MyErrorCode"][2], ("NullPointer".to_string(), 2));
+    }
+
+    #[test]
+    fn test_error_union_with_slice_and_out_param() {
+        let zig = render(SOURCE);
+
+        assert!(zig.contains("@cInclude(\"my_crate.h\");"));
+        assert!(zig.contains("pub const MyErrorCodeError = error{ InvalidInput, NullPointer, Unknown };"));
+        assert!(zig.contains("pub fn parse_config(input: []const u8) MyErrorCodeError!c.Config {"));
+        assert!(zig.contains("    try checkMyErrorCode(c.parse_config(input.ptr, input.len, &out));"));
+    }
+
+    #[test]
+    fn test_free_pairing_and_passthrough() {
+        let zig = render(SOURCE);
+
+        assert!(zig.contains("pub fn Owned(comptime T: type, comptime free_fn: anytype) type {"));
+        assert!(zig.contains("pub fn get_message() ?Owned([*c]u8, c.free_string) {"));
+        assert!(zig.contains("pub const add = c.add;"));
+        assert!(!zig.contains("not_exported"));
+    }
+
+    #[test]
+    fn test_prefixed_header_types() {
+        let mut items = FfiItems::default();
+        collect_items(SOURCE, &mut items).unwrap();
+        let zig = render_wrappers(&items, "my_crate.h", "MyCrate").unwrap();
+
+        // Types come from the header under their prefixed names; functions keep theirs
+        assert!(zig.contains("pub fn parse_config(input: []const u8) MyErrorCodeError!c.MyCrateConfig {"));
+        assert!(zig.contains("    var out: c.MyCrateConfig = undefined;"));
+        assert!(zig.contains("    try checkMyErrorCode(c.parse_config(input.ptr, input.len, &out));"));
+        assert!(!zig.contains("c.Config"));
+    }
+
+    #[test]
+    fn test_unknown_error_enum_is_rejected() {
+        let source = r#"
+            /// ghostbind: errors = Missing
+            #[unsafe(no_mangle)]
+            pub extern "C" fn f() -> i32 { 0 }
+        "#;
+
+        let mut items = FfiItems::default();
+        collect_items(source, &mut items).unwrap();
+        assert!(render_wrappers(&items, "x.h", "").is_err());
+    }
+}